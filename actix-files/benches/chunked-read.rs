@@ -0,0 +1,85 @@
+//! Compares reading files on the blocking thread pool with io-uring reads.
+//!
+//! Run once with and once without the `experimental-io-uring` feature:
+//! ```sh
+//! cargo bench -p actix-files --bench chunked-read
+//! cargo bench -p actix-files --bench chunked-read --features experimental-io-uring
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use actix_files::NamedFile;
+use actix_web::{body, test::TestRequest};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures_util::future::join_all;
+
+#[cfg(not(feature = "experimental-io-uring"))]
+const READ_PATH: &str = "spawn_blocking";
+#[cfg(feature = "experimental-io-uring")]
+const READ_PATH: &str = "io-uring";
+
+const SIZES: &[usize] = &[64 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+const CONCURRENT_DOWNLOADS: usize = 32;
+
+/// Writes file of `size` bytes to temporary directory.
+fn fixture(size: usize) -> PathBuf {
+    let dir = std::env::temp_dir().join("actix-files-bench");
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(format!("{}.bin", size));
+    fs::write(&path, vec![0x5a; size]).unwrap();
+    path
+}
+
+/// Reads whole file through the `NamedFile` response body.
+async fn download(path: &Path) -> usize {
+    let req = TestRequest::default().to_http_request();
+    let res = NamedFile::open_async(path)
+        .await
+        .unwrap()
+        .into_response(&req);
+
+    body::to_bytes(res.into_body()).await.unwrap().len()
+}
+
+fn bench_download(c: &mut Criterion) {
+    let rt = actix_rt::System::new();
+    let mut group = c.benchmark_group("download");
+
+    for &size in SIZES {
+        let path = fixture(size);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new(READ_PATH, size), &path, |b, path| {
+            b.iter(|| assert_eq!(rt.block_on(download(path)), size))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_concurrent_downloads(c: &mut Criterion) {
+    let rt = actix_rt::System::new();
+    let mut group = c.benchmark_group("concurrent_downloads");
+
+    for &size in SIZES {
+        let path = fixture(size);
+
+        group.throughput(Throughput::Bytes((size * CONCURRENT_DOWNLOADS) as u64));
+        group.bench_with_input(BenchmarkId::new(READ_PATH, size), &path, |b, path| {
+            b.iter(|| {
+                let downloads = (0..CONCURRENT_DOWNLOADS).map(|_| download(path));
+                let lens = rt.block_on(join_all(downloads));
+                assert!(lens.iter().all(|&len| len == size));
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_download, bench_concurrent_downloads);
+criterion_main!(benches);
@@ -4,6 +4,7 @@ use std::{
     io,
     pin::Pin,
     task::{Context, Poll},
+    vec,
 };
 
 use actix_web::{error::Error, web::Bytes};
@@ -70,6 +71,70 @@ pub(crate) fn new_chunked_read(
     }
 }
 
+pin_project! {
+    /// Adapter to read several regions of a file, each preceded by a part head, as one stream.
+    struct ByteRangesReadFile<F, Fut> {
+        #[pin]
+        reader: ChunkedReadFile<F, Fut>,
+        parts: vec::IntoIter<(Bytes, u64, u64)>,
+        trailer: Option<Bytes>,
+    }
+}
+
+/// Reads file regions given as `(part head, offset, length)` followed by `trailer`.
+pub(crate) fn new_byte_ranges_read(
+    parts: Vec<(Bytes, u64, u64)>,
+    trailer: Bytes,
+    file: File,
+) -> impl Stream<Item = Result<Bytes, Error>> {
+    ByteRangesReadFile {
+        // empty reader yields the first part head on first poll
+        reader: ChunkedReadFile {
+            size: 0,
+            offset: 0,
+            #[cfg(not(feature = "experimental-io-uring"))]
+            state: ChunkedReadFileState::File { file: Some(file) },
+            #[cfg(feature = "experimental-io-uring")]
+            state: ChunkedReadFileState::File {
+                file: Some((file, BytesMut::new())),
+            },
+            counter: 0,
+            callback: chunked_read_file_callback,
+        },
+        parts: parts.into_iter(),
+        trailer: Some(trailer),
+    }
+}
+
+impl<F, Fut> Stream for ByteRangesReadFile<F, Fut>
+where
+    ChunkedReadFile<F, Fut>: Stream<Item = Result<Bytes, Error>>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if let Some(item) = ready!(this.reader.as_mut().poll_next(cx)) {
+            return Poll::Ready(Some(item));
+        }
+
+        match this.parts.next() {
+            // current region is done; reuse the reader, which holds the file again, for the next
+            Some((head, offset, length)) => {
+                let reader = this.reader.project();
+                *reader.size = length;
+                *reader.offset = offset;
+                *reader.counter = 0;
+
+                Poll::Ready(Some(Ok(head)))
+            }
+
+            None => Poll::Ready(this.trailer.take().map(Ok)),
+        }
+    }
+}
+
 #[cfg(not(feature = "experimental-io-uring"))]
 async fn chunked_read_file_callback(
     mut file: File,
@@ -0,0 +1,533 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{
+    http::header::{self, ContentEncoding, Encoding, Quality},
+    web, Error, HttpMessage as _, HttpRequest,
+};
+use bytes::Bytes;
+use rand::Rng as _;
+
+use crate::{named::accepted_quality, NamedFile};
+
+/// Encodings supported with the enabled crate features, in order of server preference, with
+/// their `Accept-Encoding` coding and cache file extension.
+const SUPPORTED_ENCODINGS: &[(ContentEncoding, Encoding, &str)] = &[
+    #[cfg(feature = "compress-brotli")]
+    (ContentEncoding::Br, Encoding::Brotli, "br"),
+    #[cfg(feature = "compress-zstd")]
+    (ContentEncoding::Zstd, Encoding::Zstd, "zst"),
+    #[cfg(feature = "compress-gzip")]
+    (ContentEncoding::Gzip, Encoding::Gzip, "gz"),
+];
+
+/// Default size of the smallest file that is compressed.
+const DEFAULT_MIN_FILE_SIZE: u64 = 1024;
+
+/// Cache of compressed variants of served files.
+///
+/// Each eligible file is compressed once per encoding, with the best compression level, when it
+/// is first requested with an `Accept-Encoding` header allowing one of the cache's encodings.
+/// Later requests are served from the cache, with correct `Content-Length` and support for range
+/// requests. Requests arriving while a variant is being compressed are served uncompressed.
+/// Entries are keyed by file path, encoding and the file's ETag, which is derived from its
+/// modification time and size, so changed files are compressed again.
+///
+/// Files are eligible if they are on disk, are of a compressible type (text, JavaScript, JSON,
+/// XML, SVG and WebAssembly), and are at least [`min_file_size`](Self::min_file_size) in size.
+/// Compressed variants that are not smaller than the original file, or that are larger than the
+/// whole cache, are not served.
+///
+/// When the cache is full, least recently used entries are evicted. Clones share the same
+/// entries, so one cache can be shared by the workers of a server.
+///
+/// Available encodings depend on the `compress-brotli`, `compress-gzip` and `compress-zstd`
+/// crate features.
+///
+/// # Examples
+/// ```
+/// use actix_files::{CompressedCache, Files};
+///
+/// let cache = CompressedCache::memory(64 * 1024 * 1024);
+/// let files = Files::new("/static", "./static").compressed_cache(cache);
+/// ```
+#[derive(Clone)]
+pub struct CompressedCache {
+    storage: Storage,
+    max_size: u64,
+    min_file_size: u64,
+    encodings: Vec<ContentEncoding>,
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Debug, Clone)]
+enum Storage {
+    Memory,
+    Directory(PathBuf),
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+
+    /// Keys of variants that are being compressed.
+    in_flight: HashSet<CacheKey>,
+
+    size: u64,
+    clock: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: PathBuf,
+    etag: String,
+    encoding: &'static str,
+}
+
+struct CacheEntry {
+    variant: Variant,
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Clone)]
+enum Variant {
+    Memory(Bytes),
+    File(PathBuf),
+
+    /// Compressed variant is not smaller than the file, or does not fit in the cache.
+    Skipped,
+}
+
+/// Result of looking up a variant in the cache.
+enum Lookup {
+    Cached(Variant),
+
+    /// Variant is not cached; it is compressed by the caller while holding the guard.
+    Compress(InFlight),
+
+    /// Variant is being compressed for another request.
+    InFlight,
+}
+
+/// Marks a variant as being compressed until dropped.
+struct InFlight {
+    state: Arc<Mutex<CacheState>>,
+    key: CacheKey,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.in_flight.remove(&self.key);
+        }
+    }
+}
+
+impl fmt::Debug for CompressedCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressedCache")
+            .field("storage", &self.storage)
+            .field("max_size", &self.max_size)
+            .field("min_file_size", &self.min_file_size)
+            .field("encodings", &self.encodings)
+            .finish()
+    }
+}
+
+impl CompressedCache {
+    /// Creates cache that keeps up to `max_size` bytes of compressed files in memory.
+    pub fn memory(max_size: u64) -> Self {
+        Self::new(Storage::Memory, max_size)
+    }
+
+    /// Creates cache that keeps up to `max_size` bytes of compressed files in directory `dir`.
+    ///
+    /// The directory is created if needed and should not be used for anything else. Files in it
+    /// are named after a hash of their cache key, so variants compressed by previous runs are
+    /// reused if the original file has not changed since. Evicted files are removed.
+    pub fn directory<P: Into<PathBuf>>(dir: P, max_size: u64) -> Self {
+        Self::new(Storage::Directory(dir.into()), max_size)
+    }
+
+    fn new(storage: Storage, max_size: u64) -> Self {
+        CompressedCache {
+            storage,
+            max_size,
+            min_file_size: DEFAULT_MIN_FILE_SIZE,
+            encodings: SUPPORTED_ENCODINGS
+                .iter()
+                .map(|(encoding, _, _)| *encoding)
+                .collect(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Sets encodings to compress files with, in order of preference between encodings that a
+    /// request accepts equally.
+    ///
+    /// Encodings that are not enabled by crate features are ignored. By default, all enabled
+    /// encodings are used, preferring Brotli over Zstd over Gzip.
+    pub fn encodings<I>(mut self, encodings: I) -> Self
+    where
+        I: IntoIterator<Item = ContentEncoding>,
+    {
+        self.encodings = encodings
+            .into_iter()
+            .filter(|encoding| supported(*encoding).is_some())
+            .collect();
+        self
+    }
+
+    /// Sets size of the smallest file that is compressed.
+    ///
+    /// Default is 1KiB.
+    pub fn min_file_size(mut self, size: u64) -> Self {
+        self.min_file_size = size;
+        self
+    }
+
+    /// Returns true if compressed variants of `file` can be served.
+    pub(crate) fn is_eligible(&self, file: &NamedFile) -> bool {
+        !self.encodings.is_empty()
+            && file.len >= self.min_file_size
+            && file.modified.is_some()
+            && is_compressible(&file.content_type)
+    }
+
+    /// Returns the variant of `file`, located at `path` on disk, that is compressed with the
+    /// encoding preferred by `req`, compressing and caching it first if needed.
+    ///
+    /// Returns `None` if `req` accepts none of the cache's encodings, or if the compressed variant
+    /// is not served.
+    pub(crate) async fn variant(
+        &self,
+        req: &HttpRequest,
+        file: &NamedFile,
+        path: &Path,
+    ) -> Option<(ContentEncoding, NamedFile)> {
+        let (encoding, ext) = self.preferred_encoding(req)?;
+
+        let key = CacheKey {
+            path: path.to_owned(),
+            etag: file.etag()?.tag().to_owned(),
+            encoding: encoding.as_str(),
+        };
+
+        let variant = match self.lookup(&key) {
+            Lookup::Cached(variant) => variant,
+            Lookup::Compress(_in_flight) => self.insert(key.clone(), ext, file.len).await?,
+            Lookup::InFlight => return None,
+        };
+
+        let variant_file = match variant {
+            Variant::Memory(data) => NamedFile::from_bytes(data, path, file.modified?).ok(),
+            Variant::File(ref cache_path) => NamedFile::open_async(cache_path).await.ok(),
+            Variant::Skipped => return None,
+        };
+
+        match variant_file {
+            Some(variant_file) => Some((encoding, variant_file)),
+
+            None => {
+                // cache file was removed; compress again next time
+                self.remove(&key);
+                None
+            }
+        }
+    }
+
+    fn preferred_encoding(&self, req: &HttpRequest) -> Option<(ContentEncoding, &'static str)> {
+        let accept = req.get_header::<header::AcceptEncoding>()?;
+
+        let mut candidates = self
+            .encodings
+            .iter()
+            .filter_map(|encoding| supported(*encoding))
+            .map(|(encoding, coding, ext)| {
+                (accepted_quality(&accept.0, coding), *encoding, *ext)
+            })
+            .filter(|(quality, _, _)| *quality > Quality::MIN)
+            .collect::<Vec<_>>();
+
+        // stable sort keeps configured preference between equal qualities
+        candidates.sort_by(|(a, _, _), (b, _, _)| b.cmp(a));
+
+        candidates
+            .into_iter()
+            .next()
+            .map(|(_, encoding, ext)| (encoding, ext))
+    }
+
+    /// Returns cached variant with `key`, or claims its compression if no other request is
+    /// compressing it already.
+    fn lookup(&self, key: &CacheKey) -> Lookup {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        state.clock += 1;
+
+        if let Some(entry) = state.entries.get_mut(key) {
+            entry.last_used = state.clock;
+            return Lookup::Cached(entry.variant.clone());
+        }
+
+        if !state.in_flight.insert(key.clone()) {
+            return Lookup::InFlight;
+        }
+
+        Lookup::Compress(InFlight {
+            state: Arc::clone(&self.state),
+            key: key.clone(),
+        })
+    }
+
+    /// Compresses file and stores its variant in the cache.
+    async fn insert(&self, key: CacheKey, ext: &'static str, len: u64) -> Option<Variant> {
+        let storage = self.storage.clone();
+        let max_size = self.max_size;
+        let (path, encoding) = (key.path.clone(), key.encoding);
+        let cache_path = match storage {
+            Storage::Memory => None,
+            Storage::Directory(ref dir) => Some(dir.join(cache_file_name(&key, ext))),
+        };
+
+        // skipped variants only hold their key but still count towards the cache size so that
+        // they are evicted eventually
+        let skipped_size = (key.path.as_os_str().len() + key.etag.len()) as u64;
+
+        let res = web::block(move || -> io::Result<(Variant, u64)> {
+            if let Some(ref cache_path) = cache_path {
+                // compressed by a previous run, or another cache sharing the directory
+                if let Ok(md) = fs::metadata(cache_path) {
+                    return Ok((Variant::File(cache_path.clone()), md.len()));
+                }
+            }
+
+            let data = compress(&fs::read(&path)?, encoding)?;
+            let size = data.len() as u64;
+
+            if size >= len || size > max_size {
+                return Ok((Variant::Skipped, skipped_size));
+            }
+
+            match cache_path {
+                None => Ok((Variant::Memory(Bytes::from(data)), size)),
+
+                Some(cache_path) => {
+                    let dir = cache_path.parent().expect("cache file has parent");
+                    fs::create_dir_all(dir)?;
+
+                    // concurrent writers of the same variant, e.g. other processes sharing the
+                    // directory, use distinct temporary files
+                    let mut tmp_path = cache_path.clone().into_os_string();
+                    tmp_path.push(format!(
+                        ".{}.tmp",
+                        rand::thread_rng()
+                            .sample_iter(&rand::distributions::Alphanumeric)
+                            .take(16)
+                            .map(char::from)
+                            .collect::<String>()
+                    ));
+
+                    if let Err(err) = fs::write(&tmp_path, data)
+                        .and_then(|_| fs::rename(&tmp_path, &cache_path))
+                    {
+                        let _ = fs::remove_file(&tmp_path);
+                        return Err(err);
+                    }
+
+                    Ok((Variant::File(cache_path), size))
+                }
+            }
+        })
+        .await;
+
+        let (variant, size) = match res.map_err(Error::from).and_then(|res| Ok(res?)) {
+            Ok(res) => res,
+            Err(err) => {
+                log::error!("unable to compress {}: {}", key.path.display(), err);
+                return None;
+            }
+        };
+
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            let state = &mut *state;
+
+            state.clock += 1;
+            let entry = CacheEntry {
+                variant: variant.clone(),
+                size,
+                last_used: state.clock,
+            };
+
+            if let Some(old) = state.entries.insert(key, entry) {
+                state.size -= old.size;
+            }
+            state.size += size;
+
+            let mut evicted = Vec::new();
+
+            while state.size > self.max_size {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone())
+                    .expect("cache is not empty while over size");
+
+                let entry = state.entries.remove(&oldest).unwrap();
+                state.size -= entry.size;
+
+                if let Variant::File(path) = entry.variant {
+                    evicted.push(path);
+                }
+            }
+
+            evicted
+        };
+
+        if !evicted.is_empty() {
+            let _ = web::block(move || {
+                for path in evicted {
+                    let _ = fs::remove_file(path);
+                }
+            })
+            .await;
+        }
+
+        Some(variant)
+    }
+
+    fn remove(&self, key: &CacheKey) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(entry) = state.entries.remove(key) {
+            state.size -= entry.size;
+        }
+    }
+}
+
+/// Returns encoding's `Accept-Encoding` coding and cache file extension if it is supported.
+fn supported(
+    encoding: ContentEncoding,
+) -> Option<&'static (ContentEncoding, Encoding, &'static str)> {
+    SUPPORTED_ENCODINGS
+        .iter()
+        .find(|(supported, _, _)| *supported == encoding)
+}
+
+/// Returns true if files of MIME type `ct` benefit from compression.
+fn is_compressible(ct: &mime::Mime) -> bool {
+    let suffix = ct.suffix().map(|suffix| suffix.as_str());
+
+    match ct.type_() {
+        mime::TEXT => true,
+        mime::IMAGE => ct.subtype() == mime::SVG,
+        mime::APPLICATION => {
+            matches!(
+                ct.subtype().as_str(),
+                "javascript" | "json" | "xml" | "wasm"
+            ) || matches!(suffix, Some("json") | Some("xml"))
+        }
+        _ => false,
+    }
+}
+
+/// Returns name of file holding cached variant with `key`.
+fn cache_file_name(key: &CacheKey, ext: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+
+    format!("{:016x}.{}", hasher.finish(), ext)
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "compress-brotli",
+        feature = "compress-gzip",
+        feature = "compress-zstd"
+    )),
+    allow(unused_variables)
+)]
+fn compress(data: &[u8], encoding: &str) -> io::Result<Vec<u8>> {
+    match encoding {
+        #[cfg(feature = "compress-brotli")]
+        "br" => {
+            use std::io::Write as _;
+
+            let mut encoder = brotli2::write::BrotliEncoder::new(Vec::new(), 11);
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+
+        #[cfg(feature = "compress-gzip")]
+        "gzip" => {
+            use std::io::Write as _;
+
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+
+        #[cfg(feature = "compress-zstd")]
+        "zstd" => zstd::stream::encode_all(data, 19),
+
+        _ => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("unsupported encoding: {}", encoding),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_flight() {
+        let cache = CompressedCache::memory(1024);
+        let key = CacheKey {
+            path: PathBuf::from("file.txt"),
+            etag: "etag".to_owned(),
+            encoding: "gzip",
+        };
+
+        let in_flight = match cache.lookup(&key) {
+            Lookup::Compress(in_flight) => in_flight,
+            _ => panic!("variant is not claimed for compression"),
+        };
+        assert!(matches!(cache.lookup(&key), Lookup::InFlight));
+
+        // claim is released when compression ends or is abandoned
+        drop(in_flight);
+        assert!(matches!(cache.lookup(&key), Lookup::Compress(_)));
+    }
+
+    #[test]
+    fn compressible() {
+        for ct in &[
+            "text/css",
+            "text/html; charset=utf-8",
+            "application/javascript",
+            "application/json",
+            "application/ld+json",
+            "application/wasm",
+            "image/svg+xml",
+        ] {
+            assert!(is_compressible(&ct.parse().unwrap()), "{}", ct);
+        }
+
+        for ct in &["image/png", "application/octet-stream", "application/zip"] {
+            assert!(!is_compressible(&ct.parse().unwrap()), "{}", ct);
+        }
+    }
+}
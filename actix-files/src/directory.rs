@@ -1,23 +1,166 @@
-use std::{fmt::Write, fs::DirEntry, io, path::Path, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Write},
+    fs::DirEntry,
+    io,
+    path::Path,
+    path::PathBuf,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use actix_web::{dev::ServiceResponse, HttpRequest, HttpResponse};
+use actix_web::{
+    dev::ServiceResponse,
+    http::header::{self, HttpDate},
+    web, HttpMessage as _, HttpRequest, HttpResponse,
+};
 use askama_escape::{escape as escape_html_entity, Html};
-use percent_encoding::{utf8_percent_encode, CONTROLS};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{DiskFileSystem, FileSystem, PathFilter, SymlinkPolicy};
 
 /// A directory; responds with the generated directory listing.
-#[derive(Debug)]
 pub struct Directory {
     /// Base directory.
+    ///
+    /// Empty if files are not served from disk.
     pub base: PathBuf,
 
     /// Path of subdirectory to generate listing for.
+    ///
+    /// Relative to the root of the file system if files are not served from disk.
     pub path: PathBuf,
+
+    fs: Rc<dyn FileSystem>,
+    fs_path: PathBuf,
+    path_filter: Option<Rc<PathFilter>>,
+    symlink_policy: SymlinkPolicy,
+}
+
+impl fmt::Debug for Directory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Directory")
+            .field("base", &self.base)
+            .field("path", &self.path)
+            .finish()
+    }
 }
 
 impl Directory {
     /// Create a new directory
     pub fn new(base: PathBuf, path: PathBuf) -> Directory {
-        Directory { base, path }
+        let fs_path = path.strip_prefix(&base).unwrap_or(&path).to_path_buf();
+
+        Directory {
+            fs: Rc::new(DiskFileSystem::new(&base)),
+            base,
+            path,
+            fs_path,
+            path_filter: None,
+            symlink_policy: SymlinkPolicy::default(),
+        }
+    }
+
+    /// Creates directory at `path` of file system `fs` whose entries are listed if they pass
+    /// `path_filter` and `symlink_policy`.
+    pub(crate) fn from_fs(
+        fs: Rc<dyn FileSystem>,
+        path: PathBuf,
+        path_filter: Option<Rc<PathFilter>>,
+        symlink_policy: SymlinkPolicy,
+    ) -> Directory {
+        let base = fs.local_path(Path::new("")).unwrap_or_default();
+        let full_path = fs.local_path(&path).unwrap_or_else(|| path.clone());
+
+        Directory {
+            base,
+            path: full_path,
+            fs,
+            fs_path: path,
+            path_filter,
+            symlink_policy,
+        }
+    }
+
+    /// Returns listing of this directory for `req`, e.g., for rendering with a template engine.
+    ///
+    /// Hidden entries, i.e., those starting with a dot, and entries rejected by the
+    /// [path filter](crate::Files::path_filter) or [symlink policy](crate::Files::symlink_policy)
+    /// are left out. Entries are sorted according to the `sort` (`name`, `size`, or `modified`)
+    /// and `order` (`asc` or `desc`) query parameters of `req`, by name in ascending order by
+    /// default.
+    pub fn listing(&self, req: &HttpRequest) -> io::Result<DirectoryListing> {
+        let query = web::Query::<ListingQuery>::from_query(req.query_string())
+            .map(web::Query::into_inner)
+            .unwrap_or_default();
+        let sort = query.sort.unwrap_or(SortKey::Name);
+        let order = query.order.unwrap_or(SortOrder::Asc);
+
+        let base = Path::new(req.path());
+        let mut entries = Vec::new();
+
+        for entry in self.fs.read_dir(&self.fs_path)? {
+            let name = entry.name.to_string_lossy().into_owned();
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if !self.base.as_os_str().is_empty()
+                && self
+                    .symlink_policy
+                    .check(&self.base, &self.fs_path.join(&entry.name))
+                    .is_err()
+            {
+                continue;
+            }
+
+            if let Some(ref filter) = self.path_filter {
+                if !filter(&self.fs_path.join(&entry.name), req.head()) {
+                    continue;
+                }
+            }
+
+            let url = if cfg!(windows) {
+                base.join(&name).to_string_lossy().replace("\\", "/")
+            } else {
+                base.join(&name).to_string_lossy().into_owned()
+            };
+
+            entries.push(DirectoryEntry {
+                url: utf8_percent_encode(&url, URL_ENCODE_SET).to_string(),
+                name,
+                entry_type: if entry.metadata.is_dir {
+                    EntryType::Directory
+                } else {
+                    EntryType::File
+                },
+                size: entry.metadata.len,
+                modified: entry.metadata.modified,
+            });
+        }
+
+        entries.sort_by(|a, b| {
+            let ord = match sort {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Size => a.size.cmp(&b.size),
+                SortKey::Modified => a.modified.cmp(&b.modified),
+            }
+            .then_with(|| a.name.cmp(&b.name));
+
+            match order {
+                SortOrder::Asc => ord,
+                SortOrder::Desc => ord.reverse(),
+            }
+        });
+
+        Ok(DirectoryListing {
+            path: req.path().to_owned(),
+            entries,
+            sort,
+            order,
+        })
     }
 
     /// Is this entry visible from this directory?
@@ -37,76 +180,214 @@ impl Directory {
     }
 }
 
+/// Characters encoded in entry URLs, which are used in HTML attributes as-is.
+const URL_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'"').add(b'\'').add(b'<').add(b'>').add(b'`');
+
 pub(crate) type DirectoryRenderer =
     dyn Fn(&Directory, &HttpRequest) -> Result<ServiceResponse, io::Error>;
 
-// show file url as relative to static path
-macro_rules! encode_file_url {
-    ($path:ident) => {
-        utf8_percent_encode(&$path, CONTROLS)
-    };
+/// Listing of a directory, as returned by [`Directory::listing`].
+///
+/// Serializes into a form suitable for template engines and JSON responses.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryListing {
+    /// Request path of the directory.
+    pub path: String,
+
+    /// Sorted entries of the directory.
+    pub entries: Vec<DirectoryEntry>,
+
+    /// Key entries are sorted by.
+    pub sort: SortKey,
+
+    /// Order entries are sorted in.
+    pub order: SortOrder,
+}
+
+/// Entry of a [`DirectoryListing`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryEntry {
+    /// File name of the entry.
+    pub name: String,
+
+    /// Percent-encoded URL of the entry.
+    pub url: String,
+
+    /// Whether the entry is a file or a directory.
+    #[serde(rename = "type")]
+    pub entry_type: EntryType,
+
+    /// Size of the entry in bytes.
+    pub size: u64,
+
+    /// Last modification time of the entry, if known.
+    ///
+    /// Serialized as seconds since the Unix epoch.
+    #[serde(serialize_with = "serialize_unix_time")]
+    pub modified: Option<SystemTime>,
+}
+
+/// Type of a [`DirectoryEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    /// Regular file, or link to one.
+    File,
+
+    /// Directory.
+    Directory,
+}
+
+/// Key to sort [`DirectoryListing`] entries by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Sort by name.
+    Name,
+
+    /// Sort by size, then name.
+    Size,
+
+    /// Sort by last modification time, then name.
+    Modified,
+}
+
+/// Order to sort [`DirectoryListing`] entries in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Ascending order.
+    Asc,
+
+    /// Descending order.
+    Desc,
+}
+
+impl SortKey {
+    fn as_str(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+        }
+    }
 }
 
-// " -- &quot;  & -- &amp;  ' -- &#x27;  < -- &lt;  > -- &gt;  / -- &#x2f;
-macro_rules! encode_file_name {
-    ($entry:ident) => {
-        escape_html_entity(&$entry.file_name().to_string_lossy(), Html)
+#[derive(Debug, Default, Deserialize)]
+struct ListingQuery {
+    sort: Option<SortKey>,
+    order: Option<SortOrder>,
+}
+
+fn serialize_unix_time<S: Serializer>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(dur) => serializer.serialize_some(&dur.as_secs()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Returns true if `req` prefers a JSON response over an HTML one.
+fn prefers_json(req: &HttpRequest) -> bool {
+    let accept = match req.get_header::<header::Accept>() {
+        Some(accept) => accept,
+        None => return false,
     };
+
+    for mime in accept.ranked() {
+        if mime.essence_str() == mime::APPLICATION_JSON.essence_str() {
+            return true;
+        }
+
+        let html = mime.type_() == mime::TEXT
+            && (mime.subtype() == mime::HTML || mime.subtype() == mime::STAR);
+
+        if html || mime.type_() == mime::STAR {
+            return false;
+        }
+    }
+
+    false
 }
 
 pub(crate) fn directory_listing(
     dir: &Directory,
     req: &HttpRequest,
 ) -> Result<ServiceResponse, io::Error> {
+    let listing = dir.listing(req)?;
+
+    // both formats carry `Vary: Accept` since the format depends on that header
+    if prefers_json(req) {
+        return Ok(ServiceResponse::new(
+            req.clone(),
+            HttpResponse::Ok()
+                .insert_header((header::VARY, "Accept"))
+                .json(&listing),
+        ));
+    }
+
     let index_of = format!("Index of {}", req.path());
+
+    // sort links toggle the order of the current sort key
+    let mut head = String::new();
+    for (key, title) in &[
+        (SortKey::Name, "Name"),
+        (SortKey::Size, "Size"),
+        (SortKey::Modified, "Last Modified"),
+    ] {
+        let order = match listing.order {
+            SortOrder::Asc if listing.sort == *key => "desc",
+            _ => "asc",
+        };
+
+        let _ = write!(
+            head,
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            key.as_str(),
+            order,
+            title
+        );
+    }
+
     let mut body = String::new();
-    let base = Path::new(req.path());
-
-    for entry in dir.path.read_dir()? {
-        if dir.is_visible(&entry) {
-            let entry = entry.unwrap();
-            let p = match entry.path().strip_prefix(&dir.path) {
-                Ok(p) if cfg!(windows) => base.join(p).to_string_lossy().replace("\\", "/"),
-                Ok(p) => base.join(p).to_string_lossy().into_owned(),
-                Err(_) => continue,
-            };
+    for entry in &listing.entries {
+        let name = escape_html_entity(&entry.name, Html);
 
-            // if file is a directory, add '/' to the end of the name
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_dir() {
-                    let _ = write!(
-                        body,
-                        "<li><a href=\"{}\">{}/</a></li>",
-                        encode_file_url!(p),
-                        encode_file_name!(entry),
-                    );
-                } else {
-                    let _ = write!(
-                        body,
-                        "<li><a href=\"{}\">{}</a></li>",
-                        encode_file_url!(p),
-                        encode_file_name!(entry),
-                    );
-                }
-            } else {
-                continue;
-            }
-        }
+        // if file is a directory, add '/' to the end of the name
+        let (slash, size) = match entry.entry_type {
+            EntryType::Directory => ("/", "-".to_owned()),
+            EntryType::File => ("", entry.size.to_string()),
+        };
+
+        let modified = entry
+            .modified
+            .map(|time| HttpDate::from(time).to_string())
+            .unwrap_or_default();
+
+        let _ = write!(
+            body,
+            "<tr><td><a href=\"{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>",
+            entry.url, name, slash, size, modified,
+        );
     }
 
     let html = format!(
         "<html>\
          <head><title>{}</title></head>\
          <body><h1>{}</h1>\
-         <ul>\
-         {}\
-         </ul></body>\n</html>",
-        index_of, index_of, body
+         <table>\
+         <thead><tr>{}</tr></thead>\
+         <tbody>{}</tbody>\
+         </table></body>\n</html>",
+        index_of, index_of, head, body
     );
     Ok(ServiceResponse::new(
         req.clone(),
         HttpResponse::Ok()
             .content_type("text/html; charset=utf-8")
+            .insert_header((header::VARY, "Accept"))
             .body(html),
     ))
 }
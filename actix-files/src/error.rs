@@ -2,6 +2,7 @@ use actix_web::{http::StatusCode, ResponseError};
 use derive_more::Display;
 
 /// Errors which can occur when serving static files.
+#[non_exhaustive]
 #[derive(Display, Debug, PartialEq)]
 pub enum FilesError {
     /// Path is not a directory
@@ -12,6 +13,14 @@ pub enum FilesError {
     /// Cannot render directory
     #[display(fmt = "Unable to render directory without index file")]
     IsDirectory,
+
+    /// Path contains a symbolic link that is not allowed by the symlink policy
+    #[display(fmt = "Path contains a symbolic link which is not allowed")]
+    SymlinkDenied,
+
+    /// Path resolves to a location outside of the served directory
+    #[display(fmt = "Path resolves to a location outside of the served directory")]
+    OutsideRoot,
 }
 
 /// Return `NotFound` for `FilesError`
@@ -13,7 +13,7 @@ use actix_web::{
     },
     error::Error,
     guard::Guard,
-    http::header::DispositionType,
+    http::header::{CacheControl, DispositionType},
     HttpRequest,
 };
 use futures_core::future::LocalBoxFuture;
@@ -21,7 +21,10 @@ use futures_core::future::LocalBoxFuture;
 use crate::{
     directory_listing, named,
     service::{FilesService, FilesServiceInner},
-    Directory, DirectoryRenderer, HttpNewService, MimeOverride, PathFilter,
+    spa::SpaFallback,
+    write::DEFAULT_UPLOAD_LIMIT,
+    CompressedCache, Directory, DirectoryRenderer, FileSystem, HttpNewService, IntoFileSystem,
+    MimeOverride, PathFilter, PathPattern, SymlinkPolicy,
 };
 
 /// Static files handling service.
@@ -37,7 +40,7 @@ use crate::{
 /// ```
 pub struct Files {
     path: String,
-    directory: PathBuf,
+    fs: Rc<dyn FileSystem>,
     index: Option<String>,
     show_index: bool,
     redirect_to_slash: bool,
@@ -49,6 +52,12 @@ pub struct Files {
     use_guards: Option<Rc<dyn Guard>>,
     guards: Vec<Rc<dyn Guard>>,
     hidden_files: bool,
+    spa: SpaFallback,
+    cache_rules: Vec<(PathPattern, CacheControl)>,
+    allow_writes: bool,
+    upload_limit: u64,
+    symlink_policy: SymlinkPolicy,
+    compressed_cache: Option<CompressedCache>,
 }
 
 impl fmt::Debug for Files {
@@ -60,7 +69,7 @@ impl fmt::Debug for Files {
 impl Clone for Files {
     fn clone(&self) -> Self {
         Self {
-            directory: self.directory.clone(),
+            fs: self.fs.clone(),
             index: self.index.clone(),
             show_index: self.show_index,
             redirect_to_slash: self.redirect_to_slash,
@@ -73,6 +82,12 @@ impl Clone for Files {
             use_guards: self.use_guards.clone(),
             guards: self.guards.clone(),
             hidden_files: self.hidden_files,
+            spa: self.spa.clone(),
+            cache_rules: self.cache_rules.clone(),
+            allow_writes: self.allow_writes,
+            upload_limit: self.upload_limit,
+            symlink_policy: self.symlink_policy,
+            compressed_cache: self.compressed_cache.clone(),
         }
     }
 }
@@ -86,7 +101,9 @@ impl Files {
     ///
     /// The second argument (`serve_from`) is the location on disk at which files are loaded.
     /// This can be a relative path. For example, `./` would serve files from the current
-    /// working directory.
+    /// working directory. Other [file systems](FileSystem), like a
+    /// [`MemoryFileSystem`](crate::MemoryFileSystem) with files embedded in the binary, can be
+    /// given instead of a path.
     ///
     /// # Implementation Notes
     /// If the mount path is set as the root path `/`, services registered after this one will
@@ -95,19 +112,10 @@ impl Files {
     /// `Files` utilizes the existing Tokio thread-pool for blocking filesystem operations.
     /// The number of running threads is adjusted over time as needed, up to a maximum of 512 times
     /// the number of server [workers](actix_web::HttpServer::workers), by default.
-    pub fn new<T: Into<PathBuf>>(mount_path: &str, serve_from: T) -> Files {
-        let orig_dir = serve_from.into();
-        let dir = match orig_dir.canonicalize() {
-            Ok(canon_dir) => canon_dir,
-            Err(_) => {
-                log::error!("Specified path is not a directory: {:?}", orig_dir);
-                PathBuf::new()
-            }
-        };
-
+    pub fn new<T: IntoFileSystem>(mount_path: &str, serve_from: T) -> Files {
         Files {
             path: mount_path.trim_end_matches('/').to_owned(),
-            directory: dir,
+            fs: serve_from.into_file_system(),
             index: None,
             show_index: false,
             redirect_to_slash: false,
@@ -119,6 +127,12 @@ impl Files {
             use_guards: None,
             guards: Vec::new(),
             hidden_files: false,
+            spa: SpaFallback::default(),
+            cache_rules: Vec::new(),
+            allow_writes: false,
+            upload_limit: DEFAULT_UPLOAD_LIMIT,
+            symlink_policy: SymlinkPolicy::default(),
+            compressed_cache: None,
         }
     }
 
@@ -228,6 +242,69 @@ impl Files {
         self
     }
 
+    /// Specifies whether to serve precompressed `.br` and `.gz` sidecar files.
+    ///
+    /// See [`NamedFile::use_precompressed`](crate::NamedFile::use_precompressed) for details.
+    ///
+    /// Default is false.
+    pub fn use_precompressed(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::PRECOMPRESSED, value);
+        self
+    }
+
+    /// Specifies whether to detect the MIME type of files with a missing or unknown extension
+    /// from their contents.
+    ///
+    /// See [`NamedFile::use_content_sniffing`](crate::NamedFile::use_content_sniffing) for
+    /// details.
+    ///
+    /// Default is false.
+    pub fn use_content_sniffing(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::SNIFF, value);
+        self
+    }
+
+    /// Serves compressed variants of files from `cache`, compressing each file once per encoding.
+    ///
+    /// Precompressed sidecar files, if [enabled](Files::use_precompressed), take precedence over
+    /// the cache. See [`CompressedCache`] for details.
+    ///
+    /// By default no cache is used.
+    pub fn compressed_cache(mut self, cache: CompressedCache) -> Self {
+        self.compressed_cache = Some(cache);
+        self
+    }
+
+    /// Sets `Cache-Control` header for files matching `pattern`.
+    ///
+    /// Rules are checked in the order they are added and the first matching rule applies. Files
+    /// that match no rule are served without a `Cache-Control` header. The header is also sent
+    /// with `304 Not Modified` responses to conditional requests.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::{Files, PathPattern};
+    /// use actix_web::http::header::{CacheControl, CacheDirective};
+    ///
+    /// let files_service = Files::new("/", "./static")
+    ///     .cache_control(
+    ///         PathPattern::glob("assets/**"),
+    ///         CacheControl(vec![
+    ///             CacheDirective::Public,
+    ///             CacheDirective::MaxAge(31_536_000),
+    ///             CacheDirective::Extension("immutable".to_owned(), None),
+    ///         ]),
+    ///     )
+    ///     .cache_control(
+    ///         PathPattern::extensions(vec!["html"]),
+    ///         CacheControl(vec![CacheDirective::NoCache]),
+    ///     );
+    /// ```
+    pub fn cache_control(mut self, pattern: PathPattern, cache_control: CacheControl) -> Self {
+        self.cache_rules.push((pattern, cache_control));
+        self
+    }
+
     /// Adds a routing guard.
     ///
     /// Use this to allow multiple chained file services that respond to strictly different
@@ -315,11 +392,120 @@ impl Files {
         self
     }
 
+    /// Serves `file` for requests that do not match a file, for single-page applications.
+    ///
+    /// The path of `file` is relative to `serve_from` path. It is served with status `200 OK` and
+    /// all the usual file handling (e.g., `HEAD` requests, conditional requests, and ranges) so
+    /// that client-side routing works for any application path.
+    ///
+    /// Requests that look like asset requests (see [`Files::spa_asset_extensions`]) and requests
+    /// under excluded path prefixes (see [`Files::spa_exclude_prefix`]) are not answered with the
+    /// fallback file; they get the usual not found handling instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::Files;
+    ///
+    /// let files_service = Files::new("/", "./static")
+    ///     .index_file("index.html")
+    ///     .spa_fallback("index.html")
+    ///     .spa_exclude_prefix("/api");
+    /// ```
+    pub fn spa_fallback<T: Into<PathBuf>>(mut self, file: T) -> Self {
+        self.spa.file = Some(file.into());
+        self
+    }
+
+    /// Sets file extensions of asset requests that are never answered with the SPA fallback file.
+    ///
+    /// Extensions are compared case-insensitively and are given without the leading dot.
+    ///
+    /// By default, any request path whose last segment has an extension is an asset request.
+    pub fn spa_asset_extensions<I, T>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let extensions = extensions.into_iter().map(Into::into).collect();
+        self.spa.asset_extensions = Some(extensions);
+        self
+    }
+
+    /// Adds path prefix, such as `/api`, that is never answered with the SPA fallback file.
+    ///
+    /// The prefix is matched against whole segments of the request path relative to the mount
+    /// path; `/api` matches `/api` and `/api/users`, but not `/apiary`.
+    pub fn spa_exclude_prefix<T: AsRef<str>>(mut self, prefix: T) -> Self {
+        let prefix = prefix.as_ref().trim_matches('/').to_owned();
+        self.spa.excluded_prefixes.push(prefix);
+        self
+    }
+
     /// Enables serving hidden files and directories, allowing a leading dots in url fragments.
     pub fn use_hidden_files(mut self) -> Self {
         self.hidden_files = true;
         self
     }
+
+    /// Sets policy for serving files through symbolic links.
+    ///
+    /// The policy applies to served files, index files, directory listings and writes. Requests
+    /// for paths that violate the policy are answered with `404 Not Found` and such entries are
+    /// left out of directory listings. Only applies to files served from disk.
+    ///
+    /// By default symbolic links are followed, even if they point outside of the served
+    /// directory.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::{Files, SymlinkPolicy};
+    ///
+    /// let files = Files::new("/", "./static").symlink_policy(SymlinkPolicy::WithinRoot);
+    /// ```
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlink_policy = policy;
+        self
+    }
+
+    /// Enables uploading files with `PUT` requests and removing them with `DELETE` requests.
+    ///
+    /// Uploads are streamed to a temporary file in the target directory that is then renamed into
+    /// place, so that readers never observe partially written files. Missing parent directories
+    /// are created. Responses to uploads carry the new `ETag` of the file, and `If-Match` and
+    /// `If-None-Match` request headers can be used to avoid overwriting concurrent changes;
+    /// `If-None-Match: *` only allows creating new files.
+    ///
+    /// Request paths are checked the same way as for reads: traversal out of the served directory
+    /// is not possible, hidden files can only be written with [`Files::use_hidden_files`], and
+    /// paths rejected by [`Files::path_filter`] are not written. Writes are only supported for
+    /// file systems backed by a local directory; other file systems respond with
+    /// `405 Method Not Allowed`.
+    ///
+    /// `PUT` and `DELETE` requests are only accepted when no [`Files::method_guard`] is set, or
+    /// when the method guard allows them.
+    ///
+    /// By default writes are disabled.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::Files;
+    ///
+    /// let files = Files::new("/uploads", "./uploads")
+    ///     .allow_writes()
+    ///     .upload_limit(16 * 1024 * 1024);
+    /// ```
+    pub fn allow_writes(mut self) -> Self {
+        self.allow_writes = true;
+        self
+    }
+
+    /// Sets maximum size, in bytes, of files uploaded with `PUT` requests.
+    ///
+    /// Larger uploads are rejected with `413 Payload Too Large`. Default limit is 64MiB.
+    pub fn upload_limit(mut self, limit: u64) -> Self {
+        self.upload_limit = limit;
+        self
+    }
 }
 
 impl HttpServiceFactory for Files {
@@ -360,7 +546,7 @@ impl ServiceFactory<ServiceRequest> for Files {
 
     fn new_service(&self, _: ()) -> Self::Future {
         let mut inner = FilesServiceInner {
-            directory: self.directory.clone(),
+            fs: self.fs.clone(),
             index: self.index.clone(),
             show_index: self.show_index,
             redirect_to_slash: self.redirect_to_slash,
@@ -371,6 +557,12 @@ impl ServiceFactory<ServiceRequest> for Files {
             file_flags: self.file_flags,
             guards: self.use_guards.clone(),
             hidden_files: self.hidden_files,
+            spa: Rc::new(self.spa.clone()),
+            cache_rules: self.cache_rules.clone(),
+            allow_writes: self.allow_writes,
+            upload_limit: self.upload_limit,
+            symlink_policy: self.symlink_policy,
+            compressed_cache: self.compressed_cache.clone(),
         };
 
         if let Some(ref default) = *self.default.borrow() {
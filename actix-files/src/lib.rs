@@ -24,21 +24,35 @@ use mime_guess::from_ext;
 use std::path::Path;
 
 mod chunked;
+mod compressed_cache;
 mod directory;
 mod encoding;
 mod error;
 mod files;
 mod named;
 mod path_buf;
+mod path_pattern;
 mod range;
 mod service;
+mod sniff;
+mod spa;
+mod vfs;
+mod write;
 
 pub use self::chunked::ChunkedReadFile;
-pub use self::directory::Directory;
+pub use self::compressed_cache::CompressedCache;
+pub use self::directory::{
+    Directory, DirectoryEntry, DirectoryListing, EntryType, SortKey, SortOrder,
+};
 pub use self::files::Files;
 pub use self::named::NamedFile;
+pub use self::path_buf::SymlinkPolicy;
+pub use self::path_pattern::PathPattern;
 pub use self::range::HttpRange;
 pub use self::service::FilesService;
+pub use self::vfs::{
+    DiskFileSystem, FileSystem, FsDirEntry, FsMetadata, IntoFileSystem, MemoryFileSystem,
+};
 
 use self::directory::{directory_listing, DirectoryRenderer};
 use self::error::FilesError;
@@ -64,6 +78,7 @@ mod tests {
     use std::{
         fs::{self},
         ops::Add,
+        path::PathBuf,
         time::{Duration, SystemTime},
     };
 
@@ -71,8 +86,10 @@ mod tests {
     use actix_web::{
         guard,
         http::{
-            header::{self, ContentDisposition, DispositionParam, DispositionType},
-            Method, StatusCode,
+            header::{
+                self, ContentDisposition, ContentEncoding, DispositionParam, DispositionType,
+            },
+            Method, StatusCode, Version,
         },
         middleware::Compress,
         test::{self, TestRequest},
@@ -83,6 +100,37 @@ mod tests {
     use super::*;
     use crate::named::File;
 
+    /// Temporary directory that is removed with its contents when dropped.
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new() -> Self {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+            let path = std::env::temp_dir().join(format!(
+                "actix-files-{}-{}",
+                std::process::id(),
+                NEXT_ID.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            TempDir(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
     #[actix_web::test]
     async fn test_file_extension_to_mime() {
         let m = file_extension_to_mime("");
@@ -420,6 +468,34 @@ mod tests {
         assert_eq!(content_disposition, "attachment; filename=\"Cargo.toml\"");
     }
 
+    #[cfg(all(target_os = "linux", not(feature = "experimental-io-uring")))]
+    #[actix_rt::test]
+    async fn test_named_file_send_file_extension() {
+        async fn send_file(req: TestRequest, encoding: Option<ContentEncoding>) -> bool {
+            let mut file = NamedFile::open_async("Cargo.toml").await.unwrap();
+            if let Some(encoding) = encoding {
+                file = file.set_content_encoding(encoding);
+            }
+
+            let res = file.into_response(&req.to_http_request());
+            let send_file = res.extensions().contains::<actix_http::SendFile>();
+            send_file
+        }
+
+        assert!(send_file(TestRequest::get(), None).await);
+        assert!(
+            send_file(
+                TestRequest::get().insert_header((header::RANGE, "bytes=10-20")),
+                Some(ContentEncoding::Gzip)
+            )
+            .await
+        );
+
+        assert!(!send_file(TestRequest::get(), Some(ContentEncoding::Gzip)).await);
+        assert!(!send_file(TestRequest::default().method(Method::HEAD), None).await);
+        assert!(!send_file(TestRequest::get().version(Version::HTTP_2), None).await);
+    }
+
     #[actix_rt::test]
     async fn test_named_file_ranges_status_code() {
         let srv = test::init_service(
@@ -509,6 +585,90 @@ mod tests {
         assert_eq!(bytes, data);
     }
 
+    #[actix_rt::test]
+    async fn test_large_file_contents() {
+        let dir = TempDir::new();
+        let data = (0..4 * 1024 * 1024u32)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        fs::write(dir.path().join("large.bin"), &data).unwrap();
+
+        let root = dir.path().to_owned();
+        let srv = actix_test::start(move || App::new().service(Files::new("/", &root)));
+
+        let mut response = srv.get("/large.bin").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.body().limit(8 * 1024 * 1024).await.unwrap();
+        assert_eq!(bytes, data);
+
+        let mut response = srv
+            .get("/large.bin")
+            .insert_header((header::RANGE, "bytes=1000-2000999"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        let bytes = response.body().limit(8 * 1024 * 1024).await.unwrap();
+        assert_eq!(bytes, &data[1000..2001000]);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_multiple_ranges() {
+        let srv = actix_test::start(|| App::new().service(Files::new("/", ".")));
+        let data = fs::read("tests/test.binary").unwrap();
+
+        // overlapping ranges are coalesced
+        let mut response = srv
+            .get("/tests/test.binary")
+            .insert_header((header::RANGE, "bytes=20-29,0-9,5-14"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+        let content_type = response.headers().get(header::CONTENT_TYPE).unwrap();
+        let boundary = content_type
+            .to_str()
+            .unwrap()
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+
+        let mut expected = Vec::new();
+        for &(start, end) in &[(0, 14), (20, 29)] {
+            expected.extend_from_slice(
+                format!(
+                    "\r\n--{}\r\nContent-Type: application/octet-stream\r\n\
+                    Content-Range: bytes {}-{}/100\r\n\r\n",
+                    boundary, start, end
+                )
+                .as_bytes(),
+            );
+            expected.extend_from_slice(&data[start..=end]);
+        }
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let content_length = response.headers().get(header::CONTENT_LENGTH).unwrap();
+        assert_eq!(content_length.to_str().unwrap(), expected.len().to_string());
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, expected);
+
+        // too many ranges; whole file is sent
+        let ranges = (0..20)
+            .map(|i| format!("{}-{}", i * 4, i * 4 + 1))
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut response = srv
+            .get("/tests/test.binary")
+            .insert_header((header::RANGE, format!("bytes={}", ranges)))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, data);
+    }
+
     #[actix_rt::test]
     async fn test_head_content_length_headers() {
         let srv = actix_test::start(|| App::new().service(Files::new("/", ".")));
@@ -628,6 +788,922 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_precompressed_files() {
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::write(dir.join("app.js"), "console.log('plain')").unwrap();
+        fs::write(dir.join("app.js.br"), "brotli data").unwrap();
+        fs::write(dir.join("app.js.gz"), "gzip data").unwrap();
+        fs::write(dir.join("plain.js"), "console.log('plain')").unwrap();
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(Compress::default())
+                .service(Files::new("/", dir).use_precompressed(true)),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/app.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+        let content_type = res.headers().get(header::CONTENT_TYPE).unwrap().clone();
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        let bytes = test::read_body(res).await;
+        assert_eq!(bytes, "console.log('plain')");
+
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip, br"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            content_type
+        );
+        assert_ne!(res.headers().get(header::ETAG).unwrap(), etag);
+        let bytes = test::read_body(res).await;
+        assert_eq!(bytes, "brotli data");
+
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "br;q=0.5, gzip"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        let bytes = test::read_body(res).await;
+        assert_eq!(bytes, "gzip data");
+
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "*, br;q=0"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+
+        // no sidecar files, so compressed by middleware instead
+        let req = TestRequest::get()
+            .uri("/plain.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_ne!(test::read_body(res).await, "console.log('plain')");
+    }
+
+    #[actix_rt::test]
+    async fn test_spa_fallback() {
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("index.html"), "<div id=app></div>").unwrap();
+        fs::write(dir.join("assets/app.js"), "mount()").unwrap();
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", dir)
+                    .index_file("index.html")
+                    .spa_fallback("index.html")
+                    .spa_exclude_prefix("/api/"),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/assets/app.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "mount()");
+
+        for path in &["/users/42", "/users/42/", "/assets"] {
+            let req = TestRequest::get().uri(path).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert_eq!(test::read_body(res).await, "<div id=app></div>");
+        }
+
+        let req = TestRequest::default()
+            .method(Method::HEAD)
+            .uri("/users/42")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::get()
+            .uri("/users/42")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::get()
+            .uri("/users/42")
+            .insert_header((header::RANGE, "bytes=1-3"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(test::read_body(res).await, "div");
+
+        for path in &["/assets/missing.js", "/api", "/api/users"] {
+            let req = TestRequest::get().uri(path).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_memory_file_system() {
+        let fs = MemoryFileSystem::new()
+            .with_file("index.html", "<h1>index</h1>")
+            .with_file("assets/app.js", "console.log('app')")
+            .with_file("assets/img/logo.svg", "<svg/>");
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", fs)
+                    .index_file("index.html")
+                    .show_files_listing(),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "<h1>index</h1>");
+
+        let req = TestRequest::get().uri("/assets/app.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(test::read_body(res).await, "console.log('app')");
+
+        let req = TestRequest::get()
+            .uri("/assets/app.js")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::get()
+            .uri("/assets/app.js")
+            .insert_header((header::RANGE, "bytes=8-10"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 8-10/18"
+        );
+        assert_eq!(test::read_body(res).await, "log");
+
+        let req = TestRequest::get()
+            .uri("/assets/app.js")
+            .insert_header((header::RANGE, "bytes=0-6,11-"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("Content-Range: bytes 0-6/18\r\n\r\nconsole\r\n"));
+        assert!(body.contains("Content-Range: bytes 11-17/18\r\n\r\n('app')\r\n"));
+
+        let req = TestRequest::get().uri("/assets/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<a href=\"/assets/app.js\">app.js</a>"));
+        assert!(body.contains("<a href=\"/assets/img\">img/</a>"));
+
+        let req = TestRequest::get().uri("/missing.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_file_system() {
+        struct Prefixed(MemoryFileSystem);
+
+        impl FileSystem for Prefixed {
+            fn metadata(&self, path: &Path) -> std::io::Result<FsMetadata> {
+                self.0.metadata(&Path::new("site").join(path))
+            }
+
+            fn read_dir(&self, path: &Path) -> std::io::Result<Vec<FsDirEntry>> {
+                self.0.read_dir(&Path::new("site").join(path))
+            }
+
+            fn open(
+                &self,
+                path: &Path,
+            ) -> futures_core::future::LocalBoxFuture<'static, std::io::Result<NamedFile>>
+            {
+                self.0.open(&Path::new("site").join(path))
+            }
+        }
+
+        let fs = MemoryFileSystem::new().with_file("site/hello.txt", "hello");
+        let fs: Box<dyn FileSystem> = Box::new(Prefixed(fs));
+        let srv = test::init_service(App::new().service(Files::new("/", fs))).await;
+
+        let req = TestRequest::get().uri("/hello.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "hello");
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_control_rules() {
+        use actix_web::http::header::{CacheControl, CacheDirective};
+
+        let fs = MemoryFileSystem::new()
+            .with_file("index.html", "<h1>index</h1>")
+            .with_file("assets/app.0123abcd.js", "app()")
+            .with_file("robots.txt", "");
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", fs)
+                    .index_file("index.html")
+                    .cache_control(
+                        PathPattern::glob("assets/**/*.????????.js"),
+                        CacheControl(vec![
+                            CacheDirective::Public,
+                            CacheDirective::MaxAge(31_536_000),
+                            CacheDirective::Extension("immutable".to_owned(), None),
+                        ]),
+                    )
+                    .cache_control(
+                        PathPattern::extensions(vec!["html"]),
+                        CacheControl(vec![CacheDirective::NoCache]),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/assets/app.0123abcd.js")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "public, max-age=31536000, immutable"
+        );
+
+        let req = TestRequest::get().uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+
+        let req = TestRequest::get().uri("/robots.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[actix_rt::test]
+    async fn test_directory_listing() {
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        let fs = MemoryFileSystem::new()
+            .with_modified_file("b.txt", "bb", time(300))
+            .with_modified_file("a.txt", "aaa", time(100))
+            .with_modified_file("c.txt", "c", time(200))
+            .with_modified_file("secret.txt", "secret", time(400))
+            .with_modified_file(".env", "env", time(500))
+            .with_modified_file("sub/d.txt", "d", time(600));
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/files", fs)
+                    .show_files_listing()
+                    .path_filter(|path, _| path != Path::new("secret.txt")),
+            ),
+        )
+        .await;
+
+        let listing = |uri: &'static str| {
+            let req = TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT, "application/json"))
+                .to_request();
+            let srv = &srv;
+
+            async move {
+                let res = test::call_service(srv, req).await;
+                assert_eq!(res.status(), StatusCode::OK);
+                assert_eq!(
+                    res.headers().get(header::CONTENT_TYPE).unwrap(),
+                    "application/json"
+                );
+                assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept");
+                let body = test::read_body(res).await;
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let names = |json: &serde_json::Value| {
+            json["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| entry["name"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let json = listing("/files/").await;
+        assert_eq!(names(&json), vec!["a.txt", "b.txt", "c.txt", "sub"]);
+        assert_eq!(json["sort"], "name");
+        assert_eq!(json["order"], "asc");
+        assert_eq!(
+            json["entries"][0],
+            serde_json::json!({
+                "name": "a.txt",
+                "url": "/files/a.txt",
+                "type": "file",
+                "size": 3,
+                "modified": 100,
+            })
+        );
+        assert_eq!(json["entries"][3]["type"], "directory");
+
+        let json = listing("/files/?sort=size&order=desc").await;
+        assert_eq!(names(&json), vec!["a.txt", "b.txt", "c.txt", "sub"]);
+
+        // implied directories of memory file systems have no modification time
+        let json = listing("/files/?sort=modified&order=desc").await;
+        assert_eq!(names(&json), vec!["b.txt", "c.txt", "a.txt", "sub"]);
+
+        // invalid parameters are ignored
+        let json = listing("/files/?sort=owner").await;
+        assert_eq!(names(&json), vec!["a.txt", "b.txt", "c.txt", "sub"]);
+
+        let req = TestRequest::get()
+            .uri("/files/?sort=size")
+            .insert_header((header::ACCEPT, "text/html,application/json;q=0.9"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept");
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<a href=\"?sort=size&amp;order=desc\">Size</a>"));
+        assert!(body.contains(
+            "<tr><td><a href=\"/files/c.txt\">c.txt</a></td><td>1</td>\
+             <td>Thu, 01 Jan 1970 00:03:20 GMT</td></tr>"
+        ));
+        assert!(body.contains("<a href=\"/files/sub\">sub/</a></td><td>-</td>"));
+        assert!(!body.contains("secret.txt"));
+        assert!(!body.contains(".env"));
+    }
+
+    #[actix_rt::test]
+    async fn test_directory_listing_renderer() {
+        let fs = MemoryFileSystem::new()
+            .with_file("one.txt", "1")
+            .with_file("two.txt", "22");
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", fs)
+                    .show_files_listing()
+                    .files_listing_renderer(|dir, req| {
+                        let listing = dir.listing(req)?;
+                        let names = listing
+                            .entries
+                            .iter()
+                            .map(|entry| format!("{} ({} bytes)", entry.name, entry.size))
+                            .collect::<Vec<_>>();
+
+                        Ok(ServiceResponse::new(
+                            req.clone(),
+                            HttpResponse::Ok().body(names.join("\n")),
+                        ))
+                    }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/?order=desc").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            test::read_body(res).await,
+            "two.txt (2 bytes)\none.txt (1 bytes)"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_dropped_upload() {
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+
+        let srv =
+            test::init_service(App::new().service(Files::new("/", dir).allow_writes())).await;
+
+        let (mut tx, payload) = actix_http::h1::Payload::create(false);
+        tx.feed_data(Bytes::from_static(b"partial"));
+
+        let mut req = TestRequest::put().uri("/note.txt").to_request();
+        *req.payload() = payload.into();
+
+        // upload waits for the rest of the payload and is dropped by the timeout
+        let res =
+            actix_rt::time::timeout(Duration::from_millis(500), test::call_service(&srv, req))
+                .await;
+        assert!(res.is_err());
+
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_writes() {
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+
+        let srv = test::init_service(
+            App::new().service(Files::new("/", dir).allow_writes().upload_limit(16)),
+        )
+        .await;
+
+        let req = TestRequest::put()
+            .uri("/docs/note.txt")
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .set_payload("first")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(fs::read(dir.join("docs/note.txt")).unwrap(), b"first");
+
+        let req = TestRequest::get().uri("/docs/note.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag);
+        assert_eq!(test::read_body(res).await, "first");
+
+        // file already exists
+        let req = TestRequest::put()
+            .uri("/docs/note.txt")
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .set_payload("second")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::put()
+            .uri("/docs/note.txt")
+            .insert_header((header::IF_MATCH, "\"stale\""))
+            .set_payload("second")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(fs::read(dir.join("docs/note.txt")).unwrap(), b"first");
+
+        let req = TestRequest::put()
+            .uri("/docs/note.txt")
+            .insert_header((header::IF_MATCH, etag))
+            .set_payload("second")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(fs::read(dir.join("docs/note.txt")).unwrap(), b"second");
+
+        let req = TestRequest::put()
+            .uri("/docs/large.txt")
+            .set_payload("more than sixteen bytes")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(fs::read_dir(dir.join("docs")).unwrap().count(), 1);
+
+        for path in &["/.hidden", "/docs/.note.txt", "/docs"] {
+            let req = TestRequest::put()
+                .uri(path)
+                .set_payload("data")
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert!(res.status().is_client_error(), "{}", path);
+        }
+
+        let req = TestRequest::put()
+            .uri("/../outside.txt")
+            .set_payload("data")
+            .to_request();
+        test::call_service(&srv, req).await;
+        assert!(!dir.parent().unwrap().join("outside.txt").exists());
+
+        let req = TestRequest::delete()
+            .uri("/docs/note.txt")
+            .insert_header((header::IF_MATCH, "\"stale\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::delete().uri("/docs/note.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(!dir.join("docs/note.txt").exists());
+
+        let req = TestRequest::delete().uri("/docs/note.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_writes_not_allowed() {
+        let srv = test::init_service(
+            App::new().service(Files::new("/read-only", ".")).service(
+                Files::new("/memory", MemoryFileSystem::new().with_file("a.txt", "a"))
+                    .allow_writes(),
+            ),
+        )
+        .await;
+
+        for path in &["/read-only/new.txt", "/memory/a.txt"] {
+            let req = TestRequest::put()
+                .uri(path)
+                .set_payload("data")
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED, "{}", path);
+        }
+
+        let req = TestRequest::delete()
+            .uri("/read-only/Cargo.toml")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(Path::new("Cargo.toml").exists());
+    }
+
+    #[cfg(unix)]
+    #[actix_rt::test]
+    async fn test_symlink_policy() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/file.txt"), "inside").unwrap();
+        fs::write(dir.join("secret.txt"), "outside").unwrap();
+        symlink(root.join("sub"), root.join("inner")).unwrap();
+        symlink(dir.join("secret.txt"), root.join("outer.txt")).unwrap();
+        symlink(dir.join("secret.txt"), root.join("sub/file.txt.gz")).unwrap();
+
+        let srv = test::init_service(
+            App::new()
+                .service(
+                    Files::new("/follow", &root)
+                        .show_files_listing()
+                        .use_precompressed(true)
+                        .symlink_policy(SymlinkPolicy::Follow),
+                )
+                .service(
+                    Files::new("/within", &root)
+                        .show_files_listing()
+                        .use_precompressed(true)
+                        .symlink_policy(SymlinkPolicy::WithinRoot),
+                )
+                .service(
+                    Files::new("/deny", &root)
+                        .show_files_listing()
+                        .symlink_policy(SymlinkPolicy::Deny),
+                ),
+        )
+        .await;
+
+        let status = |path: &'static str| {
+            let srv = &srv;
+            async move {
+                let req = TestRequest::get().uri(path).to_request();
+                test::call_service(srv, req).await.status()
+            }
+        };
+
+        assert_eq!(status("/follow/outer.txt").await, StatusCode::OK);
+        assert_eq!(status("/follow/inner/file.txt").await, StatusCode::OK);
+
+        assert_eq!(status("/within/outer.txt").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/within/inner/file.txt").await, StatusCode::OK);
+
+        assert_eq!(status("/deny/outer.txt").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/deny/inner/file.txt").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/deny/sub/file.txt").await, StatusCode::OK);
+
+        // precompressed sidecar files are subject to the policy too
+        let body = |path: &'static str| {
+            let srv = &srv;
+            async move {
+                let req = TestRequest::get()
+                    .uri(path)
+                    .insert_header((header::ACCEPT_ENCODING, "gzip"))
+                    .to_request();
+                test::read_body(test::call_service(srv, req).await).await
+            }
+        };
+
+        assert_eq!(body("/follow/sub/file.txt").await, "outside");
+        assert_eq!(body("/within/sub/file.txt").await, "inside");
+
+        let listing = |path: &'static str| {
+            let srv = &srv;
+            async move {
+                let req = TestRequest::get()
+                    .uri(path)
+                    .insert_header((header::ACCEPT, "application/json"))
+                    .to_request();
+                let listing: serde_json::Value = test::read_response_json(srv, req).await;
+                listing["entries"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|entry| entry["name"].as_str().unwrap().to_owned())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(listing("/follow/").await, vec!["inner", "outer.txt", "sub"]);
+        assert_eq!(listing("/within/").await, vec!["inner", "sub"]);
+        assert_eq!(listing("/deny/").await, vec!["sub"]);
+
+        // SPA fallback file is subject to the policy too
+        let srv = test::init_service(
+            App::new()
+                .service(
+                    Files::new("/follow", &root)
+                        .spa_fallback("outer.txt")
+                        .symlink_policy(SymlinkPolicy::Follow),
+                )
+                .service(
+                    Files::new("/within", &root)
+                        .spa_fallback("outer.txt")
+                        .symlink_policy(SymlinkPolicy::WithinRoot),
+                )
+                .service(
+                    Files::new("/deny", &root)
+                        .spa_fallback("inner/file.txt")
+                        .symlink_policy(SymlinkPolicy::Deny),
+                ),
+        )
+        .await;
+
+        let status = |path: &'static str| {
+            let srv = &srv;
+            async move {
+                let req = TestRequest::get().uri(path).to_request();
+                test::call_service(srv, req).await.status()
+            }
+        };
+
+        assert_eq!(status("/follow/missing").await, StatusCode::OK);
+        assert_eq!(status("/within/missing").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/deny/missing").await, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_content_sniffing() {
+        let fs = MemoryFileSystem::new()
+            .with_file("README", "# Project\n")
+            .with_file("logo", &b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"[..])
+            .with_file("data.unknownext", "{\"key\": 1}")
+            .with_file("blob", &b"\x7fELF\x02\x01\x01\0"[..])
+            .with_file("style.css", "<html>");
+
+        let srv = test::init_service(
+            App::new()
+                .service(Files::new("/sniff", fs.clone()).use_content_sniffing(true))
+                .service(Files::new("/plain", fs)),
+        )
+        .await;
+
+        for (path, content_type) in &[
+            ("/sniff/README", "text/plain; charset=utf-8"),
+            ("/sniff/logo", "image/png"),
+            ("/sniff/data.unknownext", "application/json"),
+            ("/sniff/blob", "application/octet-stream"),
+            ("/sniff/style.css", "text/css"),
+            ("/plain/README", "application/octet-stream"),
+        ] {
+            let req = TestRequest::get().uri(path).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                content_type,
+                "{}",
+                path
+            );
+        }
+
+        let req = TestRequest::get().uri("/sniff/logo").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"logo\""
+        );
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::get()
+            .uri("/sniff/logo")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            res.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+
+        let req = TestRequest::get().uri("/plain/logo").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(header::X_CONTENT_TYPE_OPTIONS));
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_content_sniffing() {
+        let dir = TempDir::new();
+        let path = dir.path().join("sniffed-file");
+        fs::write(&path, "<!DOCTYPE html>\n<p>hello</p>").unwrap();
+
+        let file = NamedFile::open_async(&path)
+            .await
+            .unwrap()
+            .use_content_sniffing(true);
+        assert_eq!(file.content_type, mime::TEXT_HTML);
+
+        // sniffing must not affect the served contents
+        let req = TestRequest::default().to_http_request();
+        let res = file.into_response(&req);
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(bytes, "<!DOCTYPE html>\n<p>hello</p>");
+    }
+
+    #[cfg(feature = "compress-gzip")]
+    #[actix_rt::test]
+    async fn test_compressed_cache() {
+        use std::io::Read as _;
+
+        use actix_web::{
+            body::{BodySize, MessageBody as _},
+            http::header::ContentEncoding,
+        };
+
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        let script = "console.log('compress me');\n".repeat(100);
+        fs::write(dir.join("app.js"), &script).unwrap();
+        fs::write(dir.join("tiny.js"), "tiny()").unwrap();
+
+        let cache = CompressedCache::memory(1024 * 1024)
+            .encodings(vec![ContentEncoding::Gzip])
+            .min_file_size(100);
+        let srv = test::init_service(
+            App::new().service(Files::new("/", dir).compressed_cache(cache)),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/app.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip, br"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+        let gzip_etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert_ne!(gzip_etag, etag);
+        let compressed = test::read_body(res).await;
+        assert!(compressed.len() < script.len());
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, script);
+
+        // served from cache
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), gzip_etag);
+        assert_eq!(
+            res.response().body().size(),
+            BodySize::Sized(compressed.len() as u64)
+        );
+
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .insert_header((header::RANGE, "bytes=10-19"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers()
+                .get(header::CONTENT_RANGE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            format!("bytes 10-19/{}", compressed.len())
+        );
+        assert_eq!(test::read_body(res).await, compressed.slice(10..20));
+
+        // too small to be compressed
+        let req = TestRequest::get()
+            .uri("/tiny.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert!(!res.headers().contains_key(header::VARY));
+
+        // changed file is compressed again
+        let script = "console.log('changed');\n".repeat(100);
+        fs::write(dir.join("app.js"), &script).unwrap();
+        let req = TestRequest::get()
+            .uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&test::read_body(res).await[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, script);
+    }
+
+    #[cfg(feature = "compress-gzip")]
+    #[actix_rt::test]
+    async fn test_compressed_cache_directory() {
+        use actix_web::http::header::ContentEncoding;
+
+        let tmp = TempDir::new();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("files")).unwrap();
+        fs::write(dir.join("files/a.css"), "a { color: red; }\n".repeat(100)).unwrap();
+        fs::write(dir.join("files/b.css"), "b { color: blue; }\n".repeat(100)).unwrap();
+
+        // only fits one compressed file
+        let cache = CompressedCache::directory(dir.join("cache"), 100)
+            .encodings(vec![ContentEncoding::Gzip])
+            .min_file_size(0);
+        let srv = test::init_service(
+            App::new().service(Files::new("/", dir.join("files")).compressed_cache(cache)),
+        )
+        .await;
+
+        let cached_files = || fs::read_dir(dir.join("cache")).unwrap().count();
+
+        for (path, cached) in &[("/a.css", 1), ("/a.css", 1), ("/b.css", 1)] {
+            let req = TestRequest::get()
+                .uri(path)
+                .insert_header((header::ACCEPT_ENCODING, "gzip"))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+            assert!(test::read_body(res).await.len() < 100);
+            assert_eq!(cached_files(), *cached, "{}", path);
+        }
+    }
+
     #[actix_rt::test]
     async fn test_named_file_allowed_method() {
         let req = TestRequest::default().method(Method::GET).to_http_request();
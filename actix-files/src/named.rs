@@ -1,6 +1,8 @@
 use std::{
+    collections::hash_map::DefaultHasher,
     fmt,
     fs::Metadata,
+    hash::{Hash as _, Hasher as _},
     io,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
@@ -20,17 +22,25 @@ use actix_web::{
     http::{
         header::{
             self, Charset, ContentDisposition, ContentEncoding, DispositionParam,
-            DispositionType, ExtendedValue,
+            DispositionType, Encoding, ExtendedValue, Quality, QualityItem,
         },
         StatusCode,
     },
-    Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+    Error, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, Responder,
 };
 use bitflags::bitflags;
+use bytes::{Bytes, BytesMut};
 use futures_core::future::LocalBoxFuture;
 use mime_guess::from_path;
-
-use crate::{encoding::equiv_utf8_text, range::HttpRange};
+use rand::Rng as _;
+
+use crate::{
+    encoding::equiv_utf8_text,
+    path_buf::SymlinkPolicy,
+    range::HttpRange,
+    sniff::{sniff, SNIFF_LEN},
+    vfs::MemoryFile,
+};
 
 bitflags! {
     pub(crate) struct Flags: u8 {
@@ -38,6 +48,9 @@ bitflags! {
         const LAST_MD =             0b0000_0010;
         const CONTENT_DISPOSITION = 0b0000_0100;
         const PREFER_UTF8 =         0b0000_1000;
+        const PRECOMPRESSED =       0b0001_0000;
+        const SNIFF =               0b0010_0000;
+        const COMPRESSED_CACHE =    0b0100_0000;
     }
 }
 
@@ -73,38 +86,35 @@ impl Default for Flags {
 /// ```
 pub struct NamedFile {
     path: PathBuf,
-    file: File,
-    modified: Option<SystemTime>,
-    pub(crate) md: Metadata,
+    file: FileSource,
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) len: u64,
+    ino: u64,
+    compressed: Option<ContentEncoding>,
     pub(crate) flags: Flags,
     pub(crate) status_code: StatusCode,
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    pub(crate) cache_control: Option<header::CacheControl>,
+
+    /// Symlink policy, served directory and path relative to it, for checking sidecar files.
+    pub(crate) symlink_check: Option<(SymlinkPolicy, PathBuf, PathBuf)>,
 }
 
 impl fmt::Debug for NamedFile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("NamedFile")
             .field("path", &self.path)
-            .field(
-                "file",
-                #[cfg(feature = "experimental-io-uring")]
-                {
-                    &"tokio_uring::File"
-                },
-                #[cfg(not(feature = "experimental-io-uring"))]
-                {
-                    &self.file
-                },
-            )
+            .field("file", &self.file)
             .field("modified", &self.modified)
-            .field("md", &self.md)
+            .field("len", &self.len)
             .field("flags", &self.flags)
             .field("status_code", &self.status_code)
             .field("content_type", &self.content_type)
             .field("content_disposition", &self.content_disposition)
             .field("encoding", &self.encoding)
+            .field("cache_control", &self.cache_control)
             .finish()
     }
 }
@@ -114,6 +124,24 @@ pub(crate) use std::fs::File;
 #[cfg(feature = "experimental-io-uring")]
 pub(crate) use tokio_uring::fs::File;
 
+/// Source of the contents of a [`NamedFile`].
+enum FileSource {
+    Disk(File),
+    Memory(Bytes),
+}
+
+impl fmt::Debug for FileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "experimental-io-uring"))]
+            FileSource::Disk(file) => file.fmt(f),
+            #[cfg(feature = "experimental-io-uring")]
+            FileSource::Disk(_) => f.write_str("tokio_uring::File"),
+            FileSource::Memory(data) => write!(f, "Memory({} bytes)", data.len()),
+        }
+    }
+}
+
 use super::chunked;
 
 impl NamedFile {
@@ -138,7 +166,58 @@ impl NamedFile {
     /// }
     /// ```
     pub fn from_file<P: AsRef<Path>>(file: File, path: P) -> io::Result<NamedFile> {
-        let path = path.as_ref().to_path_buf();
+        let md = file_metadata(&file)?;
+
+        Self::from_source(
+            FileSource::Disk(file),
+            path.as_ref(),
+            md.len(),
+            ino(&md),
+            md.modified().ok(),
+        )
+    }
+
+    /// Creates an instance from file contents held in memory.
+    ///
+    /// The given `path` is only used to determine the `ContentType` and `ContentDisposition`
+    /// headers. The ETag is derived from the contents and the `modified` time.
+    ///
+    /// Files created this way can not be accessed as a [`File`], they can only be served.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::SystemTime;
+    ///
+    /// use actix_files::NamedFile;
+    ///
+    /// let named_file = NamedFile::from_bytes(&b"Hello, world!"[..], "hello.txt", SystemTime::now());
+    /// ```
+    pub fn from_bytes<B, P>(data: B, path: P, modified: SystemTime) -> io::Result<NamedFile>
+    where
+        B: Into<Bytes>,
+        P: AsRef<Path>,
+    {
+        Self::from_memory_file(MemoryFile::new(data.into(), modified), path.as_ref())
+    }
+
+    pub(crate) fn from_memory_file(file: MemoryFile, path: &Path) -> io::Result<NamedFile> {
+        Self::from_source(
+            FileSource::Memory(file.data.clone()),
+            path,
+            file.data.len() as u64,
+            file.hash,
+            Some(file.modified),
+        )
+    }
+
+    fn from_source(
+        file: FileSource,
+        path: &Path,
+        len: u64,
+        ino: u64,
+        modified: Option<SystemTime>,
+    ) -> io::Result<NamedFile> {
+        let path = path.to_path_buf();
 
         // Get the name of the file and use it to construct default Content-Type
         // and Content-Disposition values
@@ -154,16 +233,7 @@ impl NamedFile {
             };
 
             let ct = from_path(&path).first_or_octet_stream();
-
-            let disposition = match ct.type_() {
-                mime::IMAGE | mime::TEXT | mime::VIDEO => DispositionType::Inline,
-                mime::APPLICATION => match ct.subtype() {
-                    mime::JAVASCRIPT | mime::JSON => DispositionType::Inline,
-                    name if name == "wasm" => DispositionType::Inline,
-                    _ => DispositionType::Attachment,
-                },
-                _ => DispositionType::Attachment,
-            };
+            let disposition = default_disposition(&ct);
 
             let mut parameters =
                 vec![DispositionParam::Filename(String::from(filename.as_ref()))];
@@ -184,31 +254,6 @@ impl NamedFile {
             (ct, cd)
         };
 
-        let md = {
-            #[cfg(not(feature = "experimental-io-uring"))]
-            {
-                file.metadata()?
-            }
-
-            #[cfg(feature = "experimental-io-uring")]
-            {
-                use std::os::unix::prelude::{AsRawFd, FromRawFd};
-
-                let fd = file.as_raw_fd();
-
-                // SAFETY: fd is borrowed and lives longer than the unsafe block
-                unsafe {
-                    let file = std::fs::File::from_raw_fd(fd);
-                    let md = file.metadata();
-                    // SAFETY: forget the fd before exiting block in success or error case but don't
-                    // run destructor (that would close file handle)
-                    std::mem::forget(file);
-                    md?
-                }
-            }
-        };
-
-        let modified = md.modified().ok();
         let encoding = None;
 
         Ok(NamedFile {
@@ -216,9 +261,13 @@ impl NamedFile {
             file,
             content_type,
             content_disposition,
-            md,
+            len,
+            ino,
             modified,
+            compressed: None,
             encoding,
+            cache_control: None,
+            symlink_check: None,
             status_code: StatusCode::OK,
             flags: Flags::default(),
         })
@@ -265,10 +314,24 @@ impl NamedFile {
         Self::from_file(file, path)
     }
 
+    /// Returns metadata of the opened file, unless the file is held in memory.
+    pub(crate) fn disk_metadata(&self) -> Option<io::Result<Metadata>> {
+        match self.file {
+            FileSource::Disk(ref file) => Some(file_metadata(file)),
+            FileSource::Memory(_) => None,
+        }
+    }
+
     /// Returns reference to the underlying `File` object.
+    ///
+    /// # Panics
+    /// Panics if the file is held in memory (see [`NamedFile::from_bytes`]).
     #[inline]
     pub fn file(&self) -> &File {
-        &self.file
+        match self.file {
+            FileSource::Disk(ref file) => file,
+            FileSource::Memory(_) => panic!("NamedFile is held in memory and has no File"),
+        }
     }
 
     /// Retrieve the path of this file.
@@ -337,6 +400,15 @@ impl NamedFile {
         self
     }
 
+    /// Set the `Cache-Control` header for serving this file.
+    ///
+    /// The header is also sent with `304 Not Modified` responses to conditional requests.
+    #[inline]
+    pub fn set_cache_control(mut self, cache_control: header::CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+        self
+    }
+
     /// Specifies whether to use ETag or not.
     ///
     /// Default is true.
@@ -364,28 +436,213 @@ impl NamedFile {
         self
     }
 
-    pub(crate) fn etag(&self) -> Option<header::EntityTag> {
-        // This etag format is similar to Apache's.
-        self.modified.as_ref().map(|mtime| {
-            let ino = {
-                #[cfg(unix)]
-                {
-                    self.md.ino()
-                }
-                #[cfg(not(unix))]
-                {
-                    0
+    /// Specifies whether to serve precompressed sidecar files.
+    ///
+    /// When enabled, a `.br` or `.gz` file next to this file (e.g., `app.js.br` for `app.js`) is
+    /// served instead if the request's `Accept-Encoding` header allows it. Only files on disk have
+    /// sidecar files. The response keeps the
+    /// MIME type of this file, has the matching `Content-Encoding`, and is not compressed again by
+    /// [`Compress`](actix_web::middleware::Compress). Its ETag and Last-Modified headers are
+    /// derived from the sidecar file. Responses carry `Vary: Accept-Encoding` either way.
+    ///
+    /// Default is false.
+    #[inline]
+    pub fn use_precompressed(mut self, value: bool) -> Self {
+        self.flags.set(Flags::PRECOMPRESSED, value);
+        self
+    }
+
+    /// Specifies whether to detect the MIME type of files with a missing or unknown extension
+    /// from their contents.
+    ///
+    /// When enabled, the first bytes of such files are inspected to detect common image formats,
+    /// PDF, HTML, JSON and UTF-8 text, and the `Content-Type` and `Content-Disposition` headers
+    /// are set accordingly. Files that are not recognized are still served as
+    /// `application/octet-stream`. Responses also carry `X-Content-Type-Options: nosniff` so that
+    /// browsers do not guess a different type themselves.
+    ///
+    /// Contents are inspected when this is enabled, so call it after
+    /// [`set_content_type`](Self::set_content_type) if both are used.
+    ///
+    /// Default is false.
+    pub fn use_content_sniffing(mut self, value: bool) -> Self {
+        self.flags.set(Flags::SNIFF, value);
+
+        if value {
+            self.sniff_content_type();
+        }
+
+        self
+    }
+
+    /// Detects MIME type from contents of the file if its extension is missing or unknown.
+    pub(crate) fn sniff_content_type(&mut self) {
+        if self.content_type != mime::APPLICATION_OCTET_STREAM
+            || from_path(&self.path).first().is_some()
+        {
+            return;
+        }
+
+        let prefix = match self.file {
+            #[cfg(not(feature = "experimental-io-uring"))]
+            FileSource::Disk(ref file) => {
+                use std::io::{Read as _, Seek as _};
+
+                let mut reader: &File = file;
+                let mut buf = Vec::with_capacity(SNIFF_LEN);
+
+                let read = reader
+                    .seek(io::SeekFrom::Start(0))
+                    .and_then(|_| reader.take(SNIFF_LEN as u64).read_to_end(&mut buf));
+
+                match read {
+                    Ok(_) => Bytes::from(buf),
+                    Err(err) => {
+                        log::debug!(
+                            "unable to read {} for sniffing: {}",
+                            self.path.display(),
+                            err
+                        );
+                        return;
+                    }
                 }
+            }
+
+            // uring files can not be read synchronously
+            #[cfg(feature = "experimental-io-uring")]
+            FileSource::Disk(_) => return,
+
+            FileSource::Memory(ref data) => data.slice(..data.len().min(SNIFF_LEN)),
+        };
+
+        if let Some(ct) = sniff(&prefix) {
+            self.content_disposition.disposition = default_disposition(&ct);
+            self.content_type = ct;
+        }
+    }
+
+    /// Swaps file for precompressed sidecar that is preferred by request's `Accept-Encoding`.
+    fn open_precompressed(&mut self, req: &HttpRequest) -> Option<ContentEncoding> {
+        if let FileSource::Memory(_) = self.file {
+            return None;
+        }
+
+        let accept = req.get_header::<header::AcceptEncoding>()?;
+
+        let mut candidates = PRECOMPRESSED
+            .iter()
+            .map(|(encoding, coding, ext)| (accepted_quality(&accept.0, coding), encoding, ext))
+            .filter(|(quality, _, _)| *quality > Quality::MIN)
+            .collect::<Vec<_>>();
+
+        // stable sort keeps server preference between equal qualities
+        candidates.sort_by(|(a, _, _), (b, _, _)| b.cmp(a));
+
+        for (_, encoding, ext) in candidates {
+            let (file, md) = match open_sidecar(&self.sidecar_path(ext)) {
+                Ok((file, md)) if self.sidecar_allowed(ext, &md) => (file, md),
+                _ => continue,
             };
 
+            self.file = FileSource::Disk(file);
+            self.modified = md.modified().ok();
+            self.len = md.len();
+            self.ino = ino(&md);
+
+            return Some(*encoding);
+        }
+
+        None
+    }
+
+    /// Returns true if there is a precompressed sidecar file next to this file.
+    pub(crate) fn has_precompressed_sidecar(&self) -> bool {
+        if let FileSource::Memory(_) = self.file {
+            return false;
+        }
+
+        PRECOMPRESSED.iter().any(|(_, _, ext)| {
+            std::fs::metadata(self.sidecar_path(ext))
+                .map_or(false, |md| md.is_file() && self.sidecar_allowed(ext, &md))
+        })
+    }
+
+    /// Returns path of sidecar file with extension `ext`.
+    fn sidecar_path(&self, ext: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".");
+        path.push(ext);
+        path.into()
+    }
+
+    /// Returns true if sidecar file with extension `ext`, opened with `md` metadata, is allowed
+    /// by the symlink policy.
+    fn sidecar_allowed(&self, ext: &str, md: &Metadata) -> bool {
+        match self.symlink_check {
+            Some((policy, ref root, ref rel_path)) => {
+                let mut rel_path = rel_path.clone().into_os_string();
+                rel_path.push(".");
+                rel_path.push(ext);
+
+                policy.check_opened(root, Path::new(&rel_path), md).is_ok()
+            }
+
+            None => true,
+        }
+    }
+
+    /// Serves contents of `variant`, which are the contents of this file compressed with
+    /// `encoding`, instead of the contents of this file.
+    ///
+    /// Last-Modified header is kept and a distinct ETag is derived from this file's ETag.
+    pub(crate) fn use_compressed_variant(
+        &mut self,
+        encoding: ContentEncoding,
+        variant: NamedFile,
+    ) {
+        let mut hasher = DefaultHasher::new();
+        (self.ino, encoding.as_str()).hash(&mut hasher);
+
+        self.file = variant.file;
+        self.len = variant.len;
+        self.ino = hasher.finish();
+        self.compressed = Some(encoding);
+    }
+
+    /// Sets encoding related headers on response builder.
+    fn set_encoding(
+        &self,
+        precompressed: Option<ContentEncoding>,
+        res: &mut HttpResponseBuilder,
+    ) {
+        if let Some(encoding) = precompressed {
+            res.insert_header((header::CONTENT_ENCODING, encoding.as_str()));
+
+            // body is compressed already
+            res.encoding(ContentEncoding::Identity);
+        } else if let Some(current_encoding) = self.encoding {
+            res.encoding(current_encoding);
+        }
+
+        if self
+            .flags
+            .intersects(Flags::PRECOMPRESSED | Flags::COMPRESSED_CACHE)
+        {
+            res.insert_header((header::VARY, "Accept-Encoding"));
+        }
+    }
+
+    pub(crate) fn etag(&self) -> Option<header::EntityTag> {
+        // This etag format is similar to Apache's.
+        self.modified.as_ref().map(|mtime| {
             let dur = mtime
                 .duration_since(UNIX_EPOCH)
                 .expect("modification time must be after epoch");
 
             header::EntityTag::strong(format!(
                 "{:x}:{:x}:{:x}:{:x}",
-                ino,
-                self.md.len(),
+                self.ino,
+                self.len,
                 dur.as_secs(),
                 dur.subsec_nanos()
             ))
@@ -397,7 +654,15 @@ impl NamedFile {
     }
 
     /// Creates an `HttpResponse` with file as a streaming body.
-    pub fn into_response(self, req: &HttpRequest) -> HttpResponse<BoxBody> {
+    pub fn into_response(mut self, req: &HttpRequest) -> HttpResponse<BoxBody> {
+        let precompressed = if self.compressed.is_some() {
+            self.compressed
+        } else if self.flags.contains(Flags::PRECOMPRESSED) {
+            self.open_precompressed(req)
+        } else {
+            None
+        };
+
         if self.status_code != StatusCode::OK {
             let mut res = HttpResponse::build(self.status_code);
 
@@ -415,13 +680,22 @@ impl NamedFile {
                 ));
             }
 
-            if let Some(current_encoding) = self.encoding {
-                res.encoding(current_encoding);
+            self.set_encoding(precompressed, &mut res);
+
+            if let Some(cache_control) = self.cache_control {
+                res.insert_header(cache_control);
             }
 
-            let reader = chunked::new_chunked_read(self.md.len(), 0, self.file);
+            if self.flags.contains(Flags::SNIFF) {
+                res.insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+            }
 
-            return res.streaming(reader);
+            return match self.file {
+                FileSource::Disk(file) => {
+                    res.streaming(chunked::new_chunked_read(self.len, 0, file))
+                }
+                FileSource::Memory(data) => res.body(data),
+            };
         }
 
         let etag = if self.flags.contains(Flags::ETAG) {
@@ -474,12 +748,12 @@ impl NamedFile {
 
         let mut resp = HttpResponse::build(self.status_code);
 
-        if self.flags.contains(Flags::PREFER_UTF8) {
-            let ct = equiv_utf8_text(self.content_type.clone());
-            resp.insert_header((header::CONTENT_TYPE, ct.to_string()));
+        let content_type = if self.flags.contains(Flags::PREFER_UTF8) {
+            equiv_utf8_text(self.content_type.clone())
         } else {
-            resp.insert_header((header::CONTENT_TYPE, self.content_type.to_string()));
-        }
+            self.content_type.clone()
+        };
+        resp.insert_header((header::CONTENT_TYPE, content_type.to_string()));
 
         if self.flags.contains(Flags::CONTENT_DISPOSITION) {
             resp.insert_header((
@@ -489,9 +763,7 @@ impl NamedFile {
         }
 
         // default compressing
-        if let Some(current_encoding) = self.encoding {
-            resp.encoding(current_encoding);
-        }
+        self.set_encoding(precompressed, &mut resp);
 
         if let Some(lm) = last_modified {
             resp.insert_header((header::LAST_MODIFIED, lm.to_string()));
@@ -501,23 +773,53 @@ impl NamedFile {
             resp.insert_header((header::ETAG, etag.to_string()));
         }
 
+        if let Some(cache_control) = self.cache_control {
+            resp.insert_header(cache_control);
+        }
+
+        if self.flags.contains(Flags::SNIFF) {
+            resp.insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+        }
+
         resp.insert_header((header::ACCEPT_RANGES, "bytes"));
 
-        let mut length = self.md.len();
+        let mut length = self.len;
         let mut offset = 0;
+        let mut byte_ranges = None;
 
         // check for range header
         if let Some(ranges) = req.headers().get(header::RANGE) {
             if let Ok(ranges_header) = ranges.to_str() {
                 if let Ok(ranges) = HttpRange::parse(ranges_header, length) {
-                    length = ranges[0].length;
-                    offset = ranges[0].start;
-
-                    resp.encoding(ContentEncoding::Identity);
-                    resp.insert_header((
-                        header::CONTENT_RANGE,
-                        format!("bytes {}-{}/{}", offset, offset + length - 1, self.md.len()),
-                    ));
+                    let ranges = HttpRange::coalesce(ranges);
+
+                    match ranges.len() {
+                        0 => {}
+
+                        1 => {
+                            length = ranges[0].length;
+                            offset = ranges[0].start;
+
+                            resp.encoding(ContentEncoding::Identity);
+                            resp.insert_header((
+                                header::CONTENT_RANGE,
+                                format!(
+                                    "bytes {}-{}/{}",
+                                    offset,
+                                    offset + length - 1,
+                                    self.len
+                                ),
+                            ));
+                        }
+
+                        // too many ranges to serve; whole file is sent instead
+                        n if n > MAX_BYTE_RANGES => {}
+
+                        _ => {
+                            resp.encoding(ContentEncoding::Identity);
+                            byte_ranges = Some(ranges);
+                        }
+                    }
                 } else {
                     resp.insert_header((header::CONTENT_RANGE, format!("bytes */{}", length)));
                     return resp.status(StatusCode::RANGE_NOT_SATISFIABLE).finish();
@@ -536,13 +838,216 @@ impl NamedFile {
                 .map_into_boxed_body();
         }
 
-        let reader = chunked::new_chunked_read(length, offset, self.file);
+        if let Some(ranges) = byte_ranges {
+            let (boundary, parts, trailer) =
+                byte_ranges_parts(&ranges, &content_type, self.len);
+            let length = parts
+                .iter()
+                .map(|(head, _, length)| head.len() as u64 + length)
+                .sum::<u64>()
+                + trailer.len() as u64;
+
+            resp.status(StatusCode::PARTIAL_CONTENT);
+            resp.insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/byteranges; boundary={}", boundary),
+            ));
+
+            return match self.file {
+                FileSource::Disk(file) => {
+                    let reader = chunked::new_byte_ranges_read(parts, trailer, file);
+                    resp.body(SizedStream::new(length, reader))
+                }
+
+                FileSource::Memory(data) => {
+                    let mut body = BytesMut::with_capacity(length as usize);
+                    for (head, offset, length) in parts {
+                        body.extend_from_slice(&head);
+                        body.extend_from_slice(
+                            &data[offset as usize..(offset + length) as usize],
+                        );
+                    }
+                    body.extend_from_slice(&trailer);
+
+                    resp.body(body.freeze())
+                }
+            };
+        }
 
-        if offset != 0 || length != self.md.len() {
+        if offset != 0 || length != self.len {
             resp.status(StatusCode::PARTIAL_CONTENT);
         }
 
-        resp.body(SizedStream::new(length, reader))
+        // ranges and precompressed files are sent as they are; other bodies may be compressed
+        #[cfg(all(target_os = "linux", not(feature = "experimental-io-uring")))]
+        let send_file = sendfile_applies(req)
+            && (precompressed.is_some()
+                || offset != 0
+                || length != self.len
+                || matches!(self.encoding, None | Some(ContentEncoding::Identity)));
+
+        match self.file {
+            FileSource::Disk(file) => {
+                // lets plaintext HTTP/1 connections send the file without copying it through user
+                // space; the chunked reader is used whenever that is not possible
+                #[cfg(all(target_os = "linux", not(feature = "experimental-io-uring")))]
+                if send_file {
+                    if let Ok(file) = file.try_clone() {
+                        resp.extensions_mut()
+                            .insert(actix_http::SendFile::new(file, offset, length));
+                    }
+                }
+
+                let reader = chunked::new_chunked_read(length, offset, file);
+                resp.body(SizedStream::new(length, reader))
+            }
+
+            FileSource::Memory(data) => {
+                resp.body(data.slice(offset as usize..(offset + length) as usize))
+            }
+        }
+    }
+}
+
+/// Returns true if the response to request may be written with `sendfile(2)`, which is only done
+/// for bodies of plaintext HTTP/1 connections.
+#[cfg(all(target_os = "linux", not(feature = "experimental-io-uring")))]
+fn sendfile_applies(req: &HttpRequest) -> bool {
+    req.method() != actix_web::http::Method::HEAD
+        && req.version() < actix_web::http::Version::HTTP_2
+        && !req.app_config().secure()
+}
+
+/// Returns metadata of opened file.
+fn file_metadata(file: &File) -> io::Result<Metadata> {
+    #[cfg(not(feature = "experimental-io-uring"))]
+    {
+        file.metadata()
+    }
+
+    #[cfg(feature = "experimental-io-uring")]
+    {
+        use std::os::unix::prelude::{AsRawFd, FromRawFd};
+
+        let fd = file.as_raw_fd();
+
+        // SAFETY: fd is borrowed and lives longer than the unsafe block
+        unsafe {
+            let file = std::fs::File::from_raw_fd(fd);
+            let md = file.metadata();
+            // SAFETY: forget the fd before exiting block in success or error case but don't
+            // run destructor (that would close file handle)
+            std::mem::forget(file);
+            md
+        }
+    }
+}
+
+/// Returns inode number used for ETags of files on disk.
+fn ino(md: &Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        md.ino()
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = md;
+        0
+    }
+}
+
+/// Precompressed sidecar files in order of server preference, as content encoding, matching
+/// `Accept-Encoding` item, and file extension.
+const PRECOMPRESSED: &[(ContentEncoding, Encoding, &str)] = &[
+    (ContentEncoding::Br, Encoding::Brotli, "br"),
+    (ContentEncoding::Gzip, Encoding::Gzip, "gz"),
+];
+
+/// Returns quality given to `coding` by `Accept-Encoding` header items.
+pub(crate) fn accepted_quality(accept: &[QualityItem<Encoding>], coding: &Encoding) -> Quality {
+    let mut wildcard = Quality::MIN;
+
+    for item in accept {
+        match item.item {
+            ref item_coding if item_coding == coding => return item.quality,
+            Encoding::EncodingExt(ref ext) if ext == "*" => wildcard = item.quality,
+            _ => {}
+        }
+    }
+
+    wildcard
+}
+
+/// Opens precompressed sidecar file.
+fn open_sidecar(path: &Path) -> io::Result<(File, Metadata)> {
+    let file = std::fs::File::open(path)?;
+    let md = file.metadata()?;
+
+    if !md.is_file() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+
+    #[cfg(feature = "experimental-io-uring")]
+    let file = {
+        use std::os::unix::io::{FromRawFd as _, IntoRawFd as _};
+
+        // SAFETY: descriptor is owned by the std file that is consumed here
+        unsafe { File::from_raw_fd(file.into_raw_fd()) }
+    };
+
+    Ok((file, md))
+}
+
+/// Maximum number of (coalesced) ranges served as `multipart/byteranges` in one response.
+///
+/// Requests for more ranges are answered with the whole file.
+const MAX_BYTE_RANGES: usize = 16;
+
+/// Returns multipart boundary, `(part head, offset, length)` for each range, and the closing
+/// delimiter of a `multipart/byteranges` body.
+fn byte_ranges_parts(
+    ranges: &[HttpRange],
+    content_type: &mime::Mime,
+    size: u64,
+) -> (String, Vec<(Bytes, u64, u64)>, Bytes) {
+    let boundary = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect::<String>();
+
+    let parts = ranges
+        .iter()
+        .map(|range| {
+            let head = format!(
+                "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                boundary,
+                content_type,
+                range.start,
+                range.start + range.length - 1,
+                size
+            );
+
+            (Bytes::from(head), range.start, range.length)
+        })
+        .collect();
+
+    let trailer = Bytes::from(format!("\r\n--{}--\r\n", boundary));
+
+    (boundary, parts, trailer)
+}
+
+/// Returns `Content-Disposition` type for serving files of MIME type `ct`.
+fn default_disposition(ct: &mime::Mime) -> DispositionType {
+    match ct.type_() {
+        mime::IMAGE | mime::TEXT | mime::VIDEO => DispositionType::Inline,
+        mime::APPLICATION => match ct.subtype() {
+            mime::JAVASCRIPT | mime::JSON => DispositionType::Inline,
+            name if name == "wasm" => DispositionType::Inline,
+            _ => DispositionType::Attachment,
+        },
+        _ => DispositionType::Attachment,
     }
 }
 
@@ -586,17 +1091,22 @@ fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     }
 }
 
+/// Panics if the file is held in memory, like [`NamedFile::file`].
 impl Deref for NamedFile {
     type Target = File;
 
     fn deref(&self) -> &Self::Target {
-        &self.file
+        self.file()
     }
 }
 
+/// Panics if the file is held in memory, like [`NamedFile::file`].
 impl DerefMut for NamedFile {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.file
+        match self.file {
+            FileSource::Disk(ref mut file) => file,
+            FileSource::Memory(_) => panic!("NamedFile is held in memory and has no File"),
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use std::{
+    fs::Metadata,
     path::{Path, PathBuf},
     str::FromStr,
 };
@@ -6,11 +7,108 @@ use std::{
 use actix_utils::future::{ready, Ready};
 use actix_web::{dev::Payload, FromRequest, HttpRequest};
 
-use crate::error::UriSegmentError;
+use crate::error::{FilesError, UriSegmentError};
 
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct PathBufWrap(PathBuf);
 
+/// Policy for serving files through symbolic links.
+///
+/// Used with [`Files::symlink_policy`](crate::Files::symlink_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow symbolic links, including those pointing outside of the served directory.
+    Follow,
+
+    /// Do not serve paths that go through a symbolic link.
+    Deny,
+
+    /// Follow symbolic links only if they resolve to a location within the served directory.
+    WithinRoot,
+}
+
+impl Default for SymlinkPolicy {
+    fn default() -> Self {
+        SymlinkPolicy::Follow
+    }
+}
+
+impl SymlinkPolicy {
+    /// Checks that `path`, relative to the served directory `root`, is allowed by this policy.
+    ///
+    /// Symbolic links on the path are canonicalized and must resolve within the canonicalized
+    /// `root`. Components after the first one that does not exist are not checked.
+    pub(crate) fn check(self, root: &Path, path: &Path) -> Result<(), FilesError> {
+        if self == SymlinkPolicy::Follow {
+            return Ok(());
+        }
+
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_owned());
+        let mut current = root.to_owned();
+
+        for component in path.components() {
+            current.push(component);
+
+            match current.symlink_metadata() {
+                Ok(md) if md.file_type().is_symlink() => {}
+                Ok(_) => continue,
+                Err(_) => break,
+            }
+
+            if self == SymlinkPolicy::Deny {
+                return Err(FilesError::SymlinkDenied);
+            }
+
+            // dangling links are rejected too since writes through them could escape the root
+            match current.canonicalize() {
+                Ok(target) if target.starts_with(&canonical_root) => {}
+                _ => return Err(FilesError::OutsideRoot),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the file at `path`, relative to the served directory `root`, which has been
+    /// opened with `opened` metadata, is allowed by this policy.
+    ///
+    /// The path is checked after the file was opened and must still resolve to the opened file,
+    /// so that links swapped in between the check and opening the file are not followed.
+    pub(crate) fn check_opened(
+        self,
+        root: &Path,
+        path: &Path,
+        opened: &Metadata,
+    ) -> Result<(), FilesError> {
+        if self == SymlinkPolicy::Follow {
+            return Ok(());
+        }
+
+        self.check(root, path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+
+            let same_file = root.join(path).metadata().map_or(false, |md| {
+                (md.dev(), md.ino()) == (opened.dev(), opened.ino())
+            });
+
+            if !same_file {
+                return Err(match self {
+                    SymlinkPolicy::Deny => FilesError::SymlinkDenied,
+                    _ => FilesError::OutsideRoot,
+                });
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = opened;
+
+        Ok(())
+    }
+}
+
 impl FromStr for PathBufWrap {
     type Err = UriSegmentError;
 
@@ -137,4 +235,52 @@ mod tests {
             PathBuf::from_iter(vec!["etc/passwd"])
         );
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy() {
+        use std::{fs, os::unix::fs::symlink};
+
+        let tmp = crate::tests::TempDir::new();
+        let dir = tmp.path();
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/file.txt"), "inside").unwrap();
+        fs::write(dir.join("secret.txt"), "outside").unwrap();
+        symlink(root.join("sub"), root.join("inner")).unwrap();
+        symlink(dir.join("secret.txt"), root.join("outer.txt")).unwrap();
+        symlink(dir.join("missing.txt"), root.join("dangling.txt")).unwrap();
+
+        let check = |path: &str, policy| {
+            let path = PathBufWrap::parse_path(path, false).unwrap();
+            SymlinkPolicy::check(policy, &root, path.as_ref())
+        };
+
+        for path in &[
+            "/sub/file.txt",
+            "/inner/file.txt",
+            "/outer.txt",
+            "/new/file.txt",
+        ] {
+            assert_eq!(check(path, SymlinkPolicy::Follow), Ok(()), "{}", path);
+        }
+
+        assert_eq!(check("/sub/file.txt", SymlinkPolicy::Deny), Ok(()));
+        assert_eq!(check("/new/file.txt", SymlinkPolicy::Deny), Ok(()));
+        assert_eq!(
+            check("/inner/file.txt", SymlinkPolicy::Deny),
+            Err(FilesError::SymlinkDenied)
+        );
+
+        assert_eq!(check("/inner/file.txt", SymlinkPolicy::WithinRoot), Ok(()));
+        assert_eq!(check("/inner/new.txt", SymlinkPolicy::WithinRoot), Ok(()));
+        assert_eq!(
+            check("/outer.txt", SymlinkPolicy::WithinRoot),
+            Err(FilesError::OutsideRoot)
+        );
+        assert_eq!(
+            check("/dangling.txt", SymlinkPolicy::WithinRoot),
+            Err(FilesError::OutsideRoot)
+        );
+    }
 }
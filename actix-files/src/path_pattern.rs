@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use regex::Regex;
+
+/// Pattern matching the paths of served files.
+///
+/// Paths are matched relative to the directory files are served from, with `/` separators and
+/// without a leading `/`, e.g. `assets/app.js`.
+///
+/// Used to select [cache policies](crate::Files::cache_control).
+#[derive(Debug, Clone)]
+pub struct PathPattern(PatternInner);
+
+#[derive(Debug, Clone)]
+enum PatternInner {
+    Extensions(Vec<String>),
+    Regex(Regex),
+}
+
+impl PathPattern {
+    /// Matches files with any of the given extensions.
+    ///
+    /// Extensions are compared case-insensitively and are given without the leading dot.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::PathPattern;
+    ///
+    /// let html = PathPattern::extensions(vec!["html", "htm"]);
+    /// ```
+    pub fn extensions<I, T>(extensions: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let extensions = extensions.into_iter().map(Into::into).collect();
+        PathPattern(PatternInner::Extensions(extensions))
+    }
+
+    /// Matches files with a glob pattern.
+    ///
+    /// `?` matches any character but `/`, `*` matches any sequence of characters without `/`, and
+    /// `**/` matches any sequence of directories. Patterns without `/` are matched against file
+    /// names so that `*.html` matches `index.html` as well as `docs/index.html`.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::PathPattern;
+    ///
+    /// let hashed_assets = PathPattern::glob("assets/**/*.*.js");
+    /// ```
+    pub fn glob(pattern: &str) -> Self {
+        let mut re = String::from("^");
+
+        if !pattern.contains('/') {
+            re.push_str("(?:.*/)?");
+        }
+
+        let mut rest = pattern;
+        while let Some(ch) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("**/") {
+                re.push_str("(?:.*/)?");
+                rest = after;
+                continue;
+            }
+
+            if let Some(after) = rest.strip_prefix("**") {
+                re.push_str(".*");
+                rest = after;
+                continue;
+            }
+
+            match ch {
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                _ => re.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4]))),
+            }
+
+            rest = &rest[ch.len_utf8()..];
+        }
+
+        re.push('$');
+
+        PathPattern(PatternInner::Regex(
+            Regex::new(&re).expect("escaped glob is a valid regex"),
+        ))
+    }
+
+    /// Matches files with a regular expression.
+    ///
+    /// The regular expression is not anchored; use `^` and `$` to match whole paths.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_files::PathPattern;
+    ///
+    /// let hashed_assets = PathPattern::regex(r"\.[0-9a-f]{8,}\.(js|css)$");
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `pattern` is not a valid regular expression.
+    pub fn regex(pattern: &str) -> Self {
+        let re = match Regex::new(pattern) {
+            Ok(re) => re,
+            Err(err) => panic!("Wrong path pattern: {}", err),
+        };
+
+        PathPattern(PatternInner::Regex(re))
+    }
+
+    /// Returns true if `path` matches this pattern.
+    pub fn is_match(&self, path: &Path) -> bool {
+        match self.0 {
+            PatternInner::Extensions(ref exts) => path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| {
+                    exts.iter()
+                        .any(|candidate| candidate.eq_ignore_ascii_case(ext))
+                }),
+
+            PatternInner::Regex(ref re) => {
+                let path = path.to_string_lossy();
+
+                if cfg!(windows) {
+                    re.is_match(&path.replace('\\', "/"))
+                } else {
+                    re.is_match(&path)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions() {
+        let pattern = PathPattern::extensions(vec!["html", "HTM"]);
+
+        assert!(pattern.is_match(Path::new("index.html")));
+        assert!(pattern.is_match(Path::new("docs/INDEX.HTML")));
+        assert!(pattern.is_match(Path::new("page.htm")));
+        assert!(!pattern.is_match(Path::new("app.js")));
+        assert!(!pattern.is_match(Path::new("html")));
+    }
+
+    #[test]
+    fn glob() {
+        let pattern = PathPattern::glob("*.html");
+        assert!(pattern.is_match(Path::new("index.html")));
+        assert!(pattern.is_match(Path::new("docs/index.html")));
+        assert!(!pattern.is_match(Path::new("index.html.br")));
+        assert!(!pattern.is_match(Path::new("indexhtml")));
+
+        let pattern = PathPattern::glob("assets/**/*.????????.js");
+        assert!(pattern.is_match(Path::new("assets/app.0123abcd.js")));
+        assert!(pattern.is_match(Path::new("assets/vendor/lib.0123abcd.js")));
+        assert!(!pattern.is_match(Path::new("assets/app.js")));
+        assert!(!pattern.is_match(Path::new("static/assets/app.0123abcd.js")));
+
+        let pattern = PathPattern::glob("static/**");
+        assert!(pattern.is_match(Path::new("static/a/b/c.txt")));
+        assert!(!pattern.is_match(Path::new("other/c.txt")));
+
+        let pattern = PathPattern::glob("img/?.png");
+        assert!(pattern.is_match(Path::new("img/a.png")));
+        assert!(!pattern.is_match(Path::new("img/ab.png")));
+        assert!(!pattern.is_match(Path::new("img/a/png")));
+    }
+
+    #[test]
+    fn regex() {
+        let pattern = PathPattern::regex(r"\.[0-9a-f]{8}\.(js|css)$");
+        assert!(pattern.is_match(Path::new("assets/app.0123abcd.css")));
+        assert!(!pattern.is_match(Path::new("assets/app.css")));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_regex() {
+        PathPattern::regex("(");
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp;
+
 use derive_more::{Display, Error};
 
 /// HTTP Range header representation.
@@ -31,6 +33,25 @@ impl HttpRange {
             Err(_) => Err(ParseRangeErr(())),
         }
     }
+
+    /// Sorts ranges by start and merges those that overlap or are adjacent.
+    pub(crate) fn coalesce(mut ranges: Vec<HttpRange>) -> Vec<HttpRange> {
+        ranges.sort_unstable_by_key(|range| range.start);
+
+        let mut merged: Vec<HttpRange> = Vec::with_capacity(ranges.len());
+
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.start + last.length => {
+                    let end = cmp::max(last.start + last.length, range.start + range.length);
+                    last.length = end - last.start;
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
 }
 
 #[cfg(test)]
@@ -307,4 +328,20 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_coalesce() {
+        let ranges = HttpRange::parse("bytes=20-29,0-4,5-9,25-39,50-59", 100).unwrap();
+        let ranges = HttpRange::coalesce(ranges)
+            .iter()
+            .map(|range| (range.start, range.length))
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, vec![(0, 10), (20, 20), (50, 10)]);
+
+        let ranges = HttpRange::parse("bytes=0-9,2-3,-5", 10).unwrap();
+        let ranges = HttpRange::coalesce(ranges);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!((ranges[0].start, ranges[0].length), (0, 10));
+    }
 }
@@ -1,18 +1,27 @@
-use std::{fmt, io, ops::Deref, path::PathBuf, rc::Rc};
+use std::{
+    fmt, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use actix_service::Service;
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
     error::Error,
     guard::Guard,
-    http::{header, Method},
+    http::{
+        header::{self, CacheControl},
+        Method,
+    },
     HttpResponse,
 };
 use futures_core::future::LocalBoxFuture;
 
 use crate::{
-    named, Directory, DirectoryRenderer, FilesError, HttpService, MimeOverride, NamedFile,
-    PathBufWrap, PathFilter,
+    named, spa::SpaFallback, CompressedCache, Directory, DirectoryRenderer, FileSystem,
+    FilesError, HttpService, MimeOverride, NamedFile, PathBufWrap, PathFilter, PathPattern,
+    SymlinkPolicy,
 };
 
 /// Assembled file serving service.
@@ -28,7 +37,7 @@ impl Deref for FilesService {
 }
 
 pub struct FilesServiceInner {
-    pub(crate) directory: PathBuf,
+    pub(crate) fs: Rc<dyn FileSystem>,
     pub(crate) index: Option<String>,
     pub(crate) show_index: bool,
     pub(crate) redirect_to_slash: bool,
@@ -39,6 +48,12 @@ pub struct FilesServiceInner {
     pub(crate) file_flags: named::Flags,
     pub(crate) guards: Option<Rc<dyn Guard>>,
     pub(crate) hidden_files: bool,
+    pub(crate) spa: Rc<SpaFallback>,
+    pub(crate) cache_rules: Vec<(PathPattern, CacheControl)>,
+    pub(crate) allow_writes: bool,
+    pub(crate) upload_limit: u64,
+    pub(crate) symlink_policy: SymlinkPolicy,
+    pub(crate) compressed_cache: Option<CompressedCache>,
 }
 
 impl fmt::Debug for FilesServiceInner {
@@ -55,6 +70,19 @@ impl FilesService {
     ) -> Result<ServiceResponse, Error> {
         log::debug!("error handling {}: {}", req.path(), err);
 
+        if let Some(ref file) = self.spa.file {
+            if err.kind() == io::ErrorKind::NotFound
+                && self.spa.applies_to(req.match_info().path())
+            {
+                match self.open(file).await {
+                    Ok(named_file) => {
+                        return Ok(self.serve_named_file(req, named_file, file).await)
+                    }
+                    Err(err) => log::error!("unable to open SPA fallback file: {}", err),
+                }
+            }
+        }
+
         if let Some(ref default) = self.default {
             default.call(req).await
         } else {
@@ -62,24 +90,102 @@ impl FilesService {
         }
     }
 
-    fn serve_named_file(
+    /// Serves file found at `path`, relative to the root of the file system.
+    async fn serve_named_file(
         &self,
         req: ServiceRequest,
         mut named_file: NamedFile,
+        path: &Path,
     ) -> ServiceResponse {
+        named_file.flags = self.file_flags;
+
+        // sidecar files are opened by the named file and are subject to the same symlink policy
+        if self.symlink_policy != SymlinkPolicy::Follow {
+            named_file.symlink_check = self
+                .fs
+                .local_path(Path::new(""))
+                .map(|root| (self.symlink_policy, root, path.to_owned()));
+        }
+
+        if self.file_flags.contains(named::Flags::SNIFF) {
+            named_file.sniff_content_type();
+        }
+
         if let Some(ref mime_override) = self.mime_override {
             let new_disposition = mime_override(&named_file.content_type.type_());
             named_file.content_disposition.disposition = new_disposition;
         }
-        named_file.flags = self.file_flags;
+
+        if let Some((_, cache_control)) = self
+            .cache_rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(path))
+        {
+            named_file.cache_control = Some(cache_control.clone());
+        }
 
         let (req, _) = req.into_parts();
+
+        if let Some(ref cache) = self.compressed_cache {
+            // sidecar files take precedence
+            let has_sidecar = named_file.flags.contains(named::Flags::PRECOMPRESSED)
+                && named_file.has_precompressed_sidecar();
+
+            if let (false, true, Some(local_path)) = (
+                has_sidecar,
+                cache.is_eligible(&named_file),
+                self.fs.local_path(path),
+            ) {
+                named_file.flags.insert(named::Flags::COMPRESSED_CACHE);
+
+                if let Some((encoding, variant)) =
+                    cache.variant(&req, &named_file, &local_path).await
+                {
+                    named_file.use_compressed_variant(encoding, variant);
+                }
+            }
+        }
+
         let res = named_file.into_response(&req);
         ServiceResponse::new(req, res)
     }
 
+    /// Checks that `path`, relative to the root of the file system, is allowed by symlink policy.
+    fn check_symlinks(&self, path: &Path) -> Result<(), FilesError> {
+        match self.fs.local_path(Path::new("")) {
+            Some(root) => self.symlink_policy.check(&root, path),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens file at `path`, relative to the root of the file system, and checks that the opened
+    /// file is allowed by symlink policy.
+    async fn open(&self, path: &Path) -> io::Result<NamedFile> {
+        let named_file = self.fs.open(path).await?;
+
+        if self.symlink_policy == SymlinkPolicy::Follow {
+            return Ok(named_file);
+        }
+
+        if let (Some(root), Some(md)) = (
+            self.fs.local_path(Path::new("")),
+            named_file.disk_metadata(),
+        ) {
+            self.symlink_policy
+                .check_opened(&root, path, &md?)
+                .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err.to_string()))?;
+        }
+
+        Ok(named_file)
+    }
+
     fn show_index(&self, req: ServiceRequest, path: PathBuf) -> ServiceResponse {
-        let dir = Directory::new(self.directory.clone(), path);
+        let dir = Directory::from_fs(
+            self.fs.clone(),
+            path,
+            self.path_filter.clone(),
+            self.symlink_policy,
+        );
 
         let (req, _) = req.into_parts();
 
@@ -107,6 +213,7 @@ impl Service<ServiceRequest> for FilesService {
         } else {
             // default behavior
             matches!(*req.method(), Method::HEAD | Method::GET)
+                || (self.allow_writes && matches!(*req.method(), Method::PUT | Method::DELETE))
         };
 
         let this = self.clone();
@@ -138,13 +245,21 @@ impl Service<ServiceRequest> for FilesService {
                 }
             }
 
-            // full file path
-            let path = this.directory.join(&real_path);
-            if let Err(err) = path.canonicalize() {
-                return this.handle_err(err, req).await;
+            if let Err(err) = this.check_symlinks(real_path.as_ref()) {
+                return Ok(req.error_response(err));
+            }
+
+            if this.allow_writes && matches!(*req.method(), Method::PUT | Method::DELETE) {
+                return this.handle_write(req, real_path.as_ref()).await;
             }
 
-            if path.is_dir() {
+            let path = real_path.as_ref().to_path_buf();
+            let md = match this.fs.metadata(&path) {
+                Ok(md) => md,
+                Err(err) => return this.handle_err(err, req).await,
+            };
+
+            if md.is_dir {
                 if this.redirect_to_slash
                     && !req.path().ends_with('/')
                     && (this.index.is_some() || this.show_index)
@@ -161,32 +276,26 @@ impl Service<ServiceRequest> for FilesService {
                 match this.index {
                     Some(ref index) => {
                         let named_path = path.join(index);
-                        match NamedFile::open_async(named_path).await {
-                            Ok(named_file) => Ok(this.serve_named_file(req, named_file)),
+                        match this.open(&named_path).await {
+                            Ok(named_file) => {
+                                Ok(this.serve_named_file(req, named_file, &named_path).await)
+                            }
                             Err(_) if this.show_index => Ok(this.show_index(req, path)),
                             Err(err) => this.handle_err(err, req).await,
                         }
                     }
                     None if this.show_index => Ok(this.show_index(req, path)),
+                    None if this.spa.file.is_some() => {
+                        this.handle_err(io::ErrorKind::NotFound.into(), req).await
+                    }
                     _ => Ok(ServiceResponse::from_err(
                         FilesError::IsDirectory,
                         req.into_parts().0,
                     )),
                 }
             } else {
-                match NamedFile::open_async(&path).await {
-                    Ok(mut named_file) => {
-                        if let Some(ref mime_override) = this.mime_override {
-                            let new_disposition =
-                                mime_override(&named_file.content_type.type_());
-                            named_file.content_disposition.disposition = new_disposition;
-                        }
-                        named_file.flags = this.file_flags;
-
-                        let (req, _) = req.into_parts();
-                        let res = named_file.into_response(&req);
-                        Ok(ServiceResponse::new(req, res))
-                    }
+                match this.open(&path).await {
+                    Ok(named_file) => Ok(this.serve_named_file(req, named_file, &path).await),
                     Err(err) => this.handle_err(err, req).await,
                 }
             }
@@ -0,0 +1,157 @@
+use std::str;
+
+/// Number of bytes at the start of a file that are inspected by [`sniff`].
+pub(crate) const SNIFF_LEN: usize = 512;
+
+/// Leading bytes of common binary formats.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"\x00\x00\x01\x00", "image/x-icon"),
+    (b"%PDF-", "application/pdf"),
+];
+
+/// Tags that start HTML documents; must be followed by a space or `>`.
+const HTML_TAGS: &[&[u8]] = &[b"<!doctype html", b"<html", b"<head", b"<body"];
+
+/// Detects MIME type from the first bytes of a file's contents.
+///
+/// Recognizes common image formats, PDF, HTML, JSON and UTF-8 text. Returns `None` for empty or
+/// unrecognized contents.
+pub(crate) fn sniff(data: &[u8]) -> Option<mime::Mime> {
+    let data = &data[..data.len().min(SNIFF_LEN)];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| data.starts_with(sig)) {
+        return mime.parse().ok();
+    }
+
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return "image/webp".parse().ok();
+    }
+
+    // reserved header fields are zero, which also tells bitmaps apart from text starting with "BM"
+    if data.len() >= 14 && &data[..2] == b"BM" && data[6..10] == [0; 4] {
+        return Some(mime::IMAGE_BMP);
+    }
+
+    if !is_text(data) {
+        return None;
+    }
+
+    let trimmed = trim_start(data);
+
+    if is_html(trimmed) {
+        Some(mime::TEXT_HTML)
+    } else if is_json(trimmed) {
+        Some(mime::APPLICATION_JSON)
+    } else {
+        Some(mime::TEXT_PLAIN_UTF_8)
+    }
+}
+
+/// Returns true if `data` is non-empty UTF-8 text without binary control characters.
+///
+/// A multi-byte character cut off at the end of `data` is allowed.
+fn is_text(data: &[u8]) -> bool {
+    let valid = match str::from_utf8(data) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    };
+
+    valid
+        && !data.is_empty()
+        && !data
+            .iter()
+            .any(|&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+}
+
+fn is_html(data: &[u8]) -> bool {
+    HTML_TAGS.iter().any(|tag| {
+        data.len() > tag.len()
+            && data[..tag.len()].eq_ignore_ascii_case(tag)
+            && matches!(data[tag.len()], b' ' | b'>')
+    })
+}
+
+fn is_json(data: &[u8]) -> bool {
+    match data.split_first() {
+        Some((b'{', rest)) => matches!(trim_start(rest).first(), Some(b'"') | Some(b'}')),
+        Some((b'[', rest)) => {
+            let rest = trim_start(rest);
+
+            rest.first()
+                .map_or(false, |b| b"{[\"-]".contains(b) || b.is_ascii_digit())
+                || [&b"true"[..], b"false", b"null"]
+                    .iter()
+                    .any(|literal| rest.starts_with(literal))
+        }
+        _ => false,
+    }
+}
+
+fn trim_start(data: &[u8]) -> &[u8] {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+
+    &data[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_formats() {
+        let sniffed = |data: &[u8]| sniff(data).map(|mime| mime.to_string());
+
+        assert_eq!(
+            sniffed(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            sniffed(b"\xff\xd8\xff\xe0\0\x10JFIF").as_deref(),
+            Some("image/jpeg")
+        );
+        assert_eq!(sniffed(b"GIF89a\x01\0").as_deref(), Some("image/gif"));
+        assert_eq!(
+            sniffed(b"RIFF\x24\0\0\0WEBPVP8 ").as_deref(),
+            Some("image/webp")
+        );
+        assert_eq!(sniffed(b"%PDF-1.7\n").as_deref(), Some("application/pdf"));
+        assert_eq!(
+            sniffed(b"BM\x36\0\x0c\0\0\0\0\0\x36\0\0\0").as_deref(),
+            Some("image/bmp")
+        );
+        assert_eq!(sniffed(b"\x7fELF\x02\x01\x01\0"), None);
+        assert_eq!(sniffed(b""), None);
+    }
+
+    #[test]
+    fn text_formats() {
+        assert_eq!(sniff(b"  <!DOCTYPE html>\n<html>"), Some(mime::TEXT_HTML));
+        assert_eq!(sniff(b"<html lang=\"en\">"), Some(mime::TEXT_HTML));
+        assert_eq!(sniff(b"<htmlx>"), Some(mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(sniff(b"{\"key\": 1}"), Some(mime::APPLICATION_JSON));
+        assert_eq!(sniff(b"\n[1, 2, 3]"), Some(mime::APPLICATION_JSON));
+        assert_eq!(sniff(b"[true, false]"), Some(mime::APPLICATION_JSON));
+        assert_eq!(sniff(b"[section]\nkey = 1"), Some(mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(sniff(b"[tool.cargo]\n"), Some(mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(sniff(b"BMW service notes"), Some(mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(
+            sniff("# Überblick\n".as_bytes()),
+            Some(mime::TEXT_PLAIN_UTF_8)
+        );
+
+        // multi-byte character cut off by sniffing limit
+        let mut data = vec![b'a'; SNIFF_LEN - 1];
+        data.extend_from_slice("é".as_bytes());
+        assert_eq!(sniff(&data), Some(mime::TEXT_PLAIN_UTF_8));
+
+        assert_eq!(sniff(b"text\0with nul"), None);
+        assert_eq!(sniff(b"\xc3\x28 invalid"), None);
+    }
+}
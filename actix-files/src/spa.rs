@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+/// Single-page application fallback configuration.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SpaFallback {
+    /// File served for unmatched paths, relative to the served directory. Fallback is disabled
+    /// when `None`.
+    pub(crate) file: Option<PathBuf>,
+
+    /// Extensions of asset requests that are never answered with the fallback file. When `None`,
+    /// any path whose last segment has an extension is considered an asset request.
+    pub(crate) asset_extensions: Option<Vec<String>>,
+
+    /// Path prefixes, relative to the mount path, that are never answered with the fallback file.
+    pub(crate) excluded_prefixes: Vec<String>,
+}
+
+impl SpaFallback {
+    /// Returns true if fallback file should be served for unmatched request `path`.
+    ///
+    /// The `path` is the request path relative to the mount path.
+    pub(crate) fn applies_to(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+
+        let excluded = self.excluded_prefixes.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+        });
+
+        !excluded && !self.is_asset(path)
+    }
+
+    fn is_asset(&self, path: &str) -> bool {
+        let segment = path.rsplit('/').next().unwrap_or_default();
+
+        let ext = match Path::new(segment).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => ext,
+            None => return false,
+        };
+
+        match self.asset_extensions {
+            Some(ref exts) => exts.iter().any(|asset| asset.eq_ignore_ascii_case(ext)),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_to() {
+        let mut spa = SpaFallback {
+            file: Some(PathBuf::from("index.html")),
+            ..SpaFallback::default()
+        };
+
+        assert!(spa.applies_to("/"));
+        assert!(spa.applies_to("/users/42"));
+        assert!(spa.applies_to("/users/42/"));
+        assert!(!spa.applies_to("/app.js"));
+        assert!(!spa.applies_to("/users/john.doe"));
+
+        spa.asset_extensions = Some(vec!["js".to_owned(), "CSS".to_owned()]);
+        spa.excluded_prefixes = vec!["api".to_owned()];
+
+        assert!(spa.applies_to("/users/john.doe"));
+        assert!(!spa.applies_to("/static/app.JS"));
+        assert!(!spa.applies_to("/style.css"));
+        assert!(!spa.applies_to("/api"));
+        assert!(!spa.applies_to("/api/users"));
+        assert!(spa.applies_to("/apiary"));
+    }
+}
@@ -0,0 +1,510 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    convert::TryFrom,
+    ffi::OsString,
+    fmt,
+    hash::Hasher as _,
+    io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::web::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use crate::NamedFile;
+
+/// A file system that files are served from.
+///
+/// Paths given to the methods are relative to the root of the file system and have already been
+/// checked for path traversal by the caller. The empty path refers to the root directory.
+///
+/// Implementations are provided for files on disk ([`DiskFileSystem`]) and for files held in
+/// memory, e.g. embedded in the binary or loaded from a tar archive ([`MemoryFileSystem`]).
+pub trait FileSystem {
+    /// Returns metadata of the file or directory at `path`.
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Returns entries of the directory at `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsDirEntry>>;
+
+    /// Opens the file at `path` for serving.
+    fn open(&self, path: &Path) -> LocalBoxFuture<'static, io::Result<NamedFile>>;
+
+    /// Returns location of `path` on disk, if the file system is backed by a directory on disk.
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        let _ = path;
+        None
+    }
+}
+
+/// Conversion into a [`FileSystem`] that [`Files`](crate::Files) serves from.
+///
+/// Paths are converted into a [`DiskFileSystem`]. Custom file systems can be used by passing them
+/// as `Box<dyn FileSystem>`.
+pub trait IntoFileSystem {
+    /// Converts value into a shared file system.
+    fn into_file_system(self) -> Rc<dyn FileSystem>;
+}
+
+impl<T: Into<PathBuf>> IntoFileSystem for T {
+    fn into_file_system(self) -> Rc<dyn FileSystem> {
+        Rc::new(DiskFileSystem::new(self))
+    }
+}
+
+impl IntoFileSystem for DiskFileSystem {
+    fn into_file_system(self) -> Rc<dyn FileSystem> {
+        Rc::new(self)
+    }
+}
+
+impl IntoFileSystem for MemoryFileSystem {
+    fn into_file_system(self) -> Rc<dyn FileSystem> {
+        Rc::new(self)
+    }
+}
+
+impl IntoFileSystem for Box<dyn FileSystem> {
+    fn into_file_system(self) -> Rc<dyn FileSystem> {
+        self.into()
+    }
+}
+
+/// Metadata of a file or directory in a [`FileSystem`].
+#[derive(Debug, Clone)]
+pub struct FsMetadata {
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+
+    /// Size of the file in bytes.
+    pub len: u64,
+
+    /// Last modification time, if known.
+    pub modified: Option<SystemTime>,
+}
+
+impl From<std::fs::Metadata> for FsMetadata {
+    fn from(md: std::fs::Metadata) -> Self {
+        FsMetadata {
+            is_dir: md.is_dir(),
+            len: md.len(),
+            modified: md.modified().ok(),
+        }
+    }
+}
+
+/// Entry of a directory in a [`FileSystem`].
+#[derive(Debug, Clone)]
+pub struct FsDirEntry {
+    /// Name of the entry within its directory.
+    pub name: OsString,
+
+    /// Metadata of the entry.
+    pub metadata: FsMetadata,
+}
+
+/// File system serving files from a directory on disk.
+#[derive(Debug, Clone)]
+pub struct DiskFileSystem {
+    root: PathBuf,
+}
+
+impl DiskFileSystem {
+    /// Constructs file system serving files from the `root` directory.
+    ///
+    /// `root` can be a relative path; it is resolved to an absolute path here.
+    pub fn new<T: Into<PathBuf>>(root: T) -> Self {
+        let orig_dir = root.into();
+        let root = match orig_dir.canonicalize() {
+            Ok(canon_dir) => canon_dir,
+            Err(_) => {
+                log::error!("Specified path is not a directory: {:?}", orig_dir);
+                PathBuf::new()
+            }
+        };
+
+        DiskFileSystem { root }
+    }
+
+    fn join(&self, path: &Path) -> PathBuf {
+        if path.as_os_str().is_empty() {
+            self.root.clone()
+        } else {
+            self.root.join(path)
+        }
+    }
+}
+
+impl FileSystem for DiskFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        std::fs::metadata(self.join(path)).map(FsMetadata::from)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsDirEntry>> {
+        let mut entries = Vec::new();
+
+        for entry in self.join(path).read_dir()? {
+            let entry = entry?;
+            let md = entry.metadata()?;
+
+            // skip sockets, devices, etc.
+            let ft = md.file_type();
+            if !(ft.is_dir() || ft.is_file() || ft.is_symlink()) {
+                continue;
+            }
+
+            entries.push(FsDirEntry {
+                name: entry.file_name(),
+                metadata: md.into(),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn open(&self, path: &Path) -> LocalBoxFuture<'static, io::Result<NamedFile>> {
+        let path = self.join(path);
+        Box::pin(async move { NamedFile::open_async(path).await })
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(self.join(path))
+    }
+}
+
+/// File system serving files held in memory.
+///
+/// Files can be embedded in the binary or loaded from an uncompressed tar archive. Directories are
+/// implied by the paths of the files they contain.
+///
+/// # Examples
+/// ```
+/// use actix_files::{Files, MemoryFileSystem};
+///
+/// let fs = MemoryFileSystem::new()
+///     .with_file("index.html", &b"<h1>Hello</h1>"[..])
+///     .with_file("assets/app.js", &b"console.log('hello')"[..]);
+///
+/// let files_service = Files::new("/", fs).index_file("index.html");
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryFileSystem {
+    files: BTreeMap<PathBuf, MemoryFile>,
+}
+
+#[derive(Clone)]
+pub(crate) struct MemoryFile {
+    pub(crate) data: Bytes,
+    pub(crate) modified: SystemTime,
+    pub(crate) hash: u64,
+}
+
+impl MemoryFile {
+    pub(crate) fn new(data: Bytes, modified: SystemTime) -> Self {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&data);
+
+        MemoryFile {
+            data,
+            modified,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+impl fmt::Debug for MemoryFileSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryFileSystem")
+            .field("files", &self.files.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl MemoryFileSystem {
+    /// Constructs empty file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds file with contents `data` at `path`, replacing any file already at that path.
+    ///
+    /// The modification time of the file is the time it was added.
+    pub fn with_file<P, B>(self, path: P, data: B) -> Self
+    where
+        P: AsRef<Path>,
+        B: Into<Bytes>,
+    {
+        self.with_modified_file(path, data, SystemTime::now())
+    }
+
+    /// Adds file with contents `data` and modification time `modified` at `path`, replacing any
+    /// file already at that path.
+    pub fn with_modified_file<P, B>(mut self, path: P, data: B, modified: SystemTime) -> Self
+    where
+        P: AsRef<Path>,
+        B: Into<Bytes>,
+    {
+        let path = normalize(path.as_ref());
+        self.files
+            .insert(path, MemoryFile::new(data.into(), modified));
+        self
+    }
+
+    /// Constructs file system from the regular files in an uncompressed tar archive.
+    ///
+    /// File contents are not copied out of the archive. Modification times are taken from the
+    /// archive. Entries other than regular files and directories, such as links, are skipped.
+    ///
+    /// # Errors
+    /// Errors if the archive is malformed.
+    pub fn from_tar<B: Into<Bytes>>(archive: B) -> io::Result<Self> {
+        let archive = archive.into();
+        let mut fs = MemoryFileSystem::new();
+        let mut long_name = None;
+        let mut pos = 0;
+
+        while pos + TAR_BLOCK <= archive.len() {
+            let header = &archive[pos..pos + TAR_BLOCK];
+            pos += TAR_BLOCK;
+
+            // archive ends with zero blocks
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+
+            let size = usize::try_from(tar_octal(&header[124..136])?)
+                .map_err(|_| tar_error("entry is too large"))?;
+            let modified = UNIX_EPOCH + Duration::from_secs(tar_octal(&header[136..148])?);
+
+            let end = pos
+                .checked_add(size)
+                .filter(|&end| end <= archive.len())
+                .ok_or_else(|| tar_error("entry is truncated"))?;
+            let data = archive.slice(pos..end);
+            pos += (size + TAR_BLOCK - 1) / TAR_BLOCK * TAR_BLOCK;
+
+            let name = match long_name.take() {
+                Some(name) => name,
+                None => {
+                    let mut name = tar_str(&header[0..100]).to_owned();
+
+                    // ustar archives split long paths into a prefix and a name
+                    if &header[257..262] == b"ustar" {
+                        let prefix = tar_str(&header[345..500]);
+                        if !prefix.is_empty() {
+                            name = format!("{}/{}", prefix, name);
+                        }
+                    }
+
+                    name
+                }
+            };
+
+            match header[156] {
+                b'0' | b'\0' | b'7' => {
+                    fs = fs.with_modified_file(name, data, modified);
+                }
+
+                // GNU long name of the next entry
+                b'L' => long_name = Some(tar_str(&data).to_owned()),
+
+                // directories are implied by files, other entries are not served
+                _ => {}
+            }
+        }
+
+        Ok(fs)
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        if let Some(file) = self.files.get(path) {
+            return Ok(FsMetadata {
+                is_dir: false,
+                len: file.data.len() as u64,
+                modified: Some(file.modified),
+            });
+        }
+
+        let is_dir = path.as_os_str().is_empty()
+            || self
+                .files
+                .range(path.to_path_buf()..)
+                .next()
+                .map_or(false, |(file, _)| file.starts_with(path));
+
+        if is_dir {
+            Ok(FsMetadata {
+                is_dir: true,
+                len: 0,
+                modified: None,
+            })
+        } else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FsDirEntry>> {
+        if !self.metadata(path)?.is_dir {
+            return Err(io::Error::new(io::ErrorKind::Other, "not a directory"));
+        }
+
+        let mut entries = Vec::new();
+        let mut dirs = BTreeSet::new();
+
+        for file in self.files.keys() {
+            let rest = match file.strip_prefix(path) {
+                Ok(rest) => rest,
+                Err(_) => continue,
+            };
+
+            let mut components = rest.components();
+            let name = match components.next() {
+                Some(name) => name.as_os_str().to_owned(),
+                None => continue,
+            };
+
+            if components.next().is_some() {
+                dirs.insert(name);
+            } else {
+                entries.push(FsDirEntry {
+                    metadata: self.metadata(file)?,
+                    name,
+                });
+            }
+        }
+
+        entries.extend(dirs.into_iter().map(|name| FsDirEntry {
+            name,
+            metadata: FsMetadata {
+                is_dir: true,
+                len: 0,
+                modified: None,
+            },
+        }));
+
+        Ok(entries)
+    }
+
+    fn open(&self, path: &Path) -> LocalBoxFuture<'static, io::Result<NamedFile>> {
+        let res = match self.files.get(path) {
+            Some(file) => NamedFile::from_memory_file(file.clone(), path),
+            None => Err(io::ErrorKind::NotFound.into()),
+        };
+
+        Box::pin(async move { res })
+    }
+}
+
+const TAR_BLOCK: usize = 512;
+
+/// Returns path relative to root without `.` components.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|comp| matches!(comp, Component::Normal(_)))
+        .collect()
+}
+
+fn tar_str(field: &[u8]) -> &str {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    std::str::from_utf8(&field[..end]).unwrap_or_default()
+}
+
+fn tar_octal(field: &[u8]) -> io::Result<u64> {
+    let digits = tar_str(field).trim_matches(|c| c == ' ' || c == '\0');
+
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(digits, 8).map_err(|_| tar_error("invalid number in header"))
+}
+
+fn tar_error(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid tar archive: {}", msg),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_entry(archive: &mut Vec<u8>, name: &str, kind: u8, data: &[u8]) {
+        let mut header = [0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[136..147].copy_from_slice(format!("{:011o}", 1_600_000_000).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize((archive.len() + TAR_BLOCK - 1) / TAR_BLOCK * TAR_BLOCK, 0);
+    }
+
+    #[test]
+    fn memory_fs() {
+        let fs = MemoryFileSystem::new()
+            .with_file("/index.html", "index")
+            .with_file("./assets/app.js", "app")
+            .with_file("assets/img/logo.svg", "logo");
+
+        assert!(fs.metadata(Path::new("")).unwrap().is_dir);
+        assert!(fs.metadata(Path::new("assets")).unwrap().is_dir);
+        assert!(fs.metadata(Path::new("assets/img")).unwrap().is_dir);
+        assert_eq!(fs.metadata(Path::new("assets/app.js")).unwrap().len, 3);
+        assert!(fs.metadata(Path::new("asset")).is_err());
+        assert!(fs.metadata(Path::new("assets/app")).is_err());
+
+        let mut entries = fs
+            .read_dir(Path::new("assets"))
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.metadata.is_dir))
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![("app.js".into(), false), ("img".into(), true)]
+        );
+
+        assert!(fs.read_dir(Path::new("index.html")).is_err());
+    }
+
+    #[test]
+    fn tar_archive() {
+        let long_name = format!("{}/file.txt", "dir".repeat(40));
+
+        let mut archive = Vec::new();
+        tar_entry(&mut archive, "site/", b'5', b"");
+        tar_entry(&mut archive, "site/index.html", b'0', b"<h1>tar</h1>");
+        tar_entry(&mut archive, "site/link", b'2', b"");
+        tar_entry(&mut archive, "././@LongLink", b'L', long_name.as_bytes());
+        tar_entry(&mut archive, "short-name", b'0', b"long");
+        archive.extend_from_slice(&[0; TAR_BLOCK * 2]);
+
+        let fs = MemoryFileSystem::from_tar(archive).unwrap();
+
+        let file = fs.files.get(Path::new("site/index.html")).unwrap();
+        assert_eq!(file.data, "<h1>tar</h1>");
+        assert_eq!(
+            file.modified,
+            UNIX_EPOCH + Duration::from_secs(1_600_000_000)
+        );
+        assert_eq!(fs.files.get(Path::new(&long_name)).unwrap().data, "long");
+        assert!(fs.metadata(Path::new("site/link")).is_err());
+
+        assert!(MemoryFileSystem::from_tar(vec![1; TAR_BLOCK]).is_err());
+
+        // entry size larger than the archive
+        let mut archive = Vec::new();
+        tar_entry(&mut archive, "big", b'0', b"");
+        archive[124..135].copy_from_slice(b"77777777777");
+        assert!(MemoryFileSystem::from_tar(archive).is_err());
+    }
+}
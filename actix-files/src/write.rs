@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use actix_utils::future::poll_fn;
+use actix_web::{
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::Error,
+    http::header::Header as _,
+    http::header::{self, EntityTag},
+    web, HttpResponse,
+};
+use futures_core::Stream as _;
+use rand::Rng as _;
+
+use crate::{FilesService, NamedFile};
+
+/// Default maximum size of files uploaded with `PUT` requests.
+pub(crate) const DEFAULT_UPLOAD_LIMIT: u64 = 64 * 1024 * 1024;
+
+impl FilesService {
+    /// Handles `PUT` and `DELETE` requests for the file at `path`, relative to the root of the file
+    /// system.
+    pub(crate) async fn handle_write(
+        &self,
+        req: ServiceRequest,
+        path: &Path,
+    ) -> Result<ServiceResponse, Error> {
+        let full_path = match self.fs.local_path(path) {
+            Some(full_path) if !path.as_os_str().is_empty() => full_path,
+            _ => {
+                return Ok(req.into_response(
+                    HttpResponse::MethodNotAllowed()
+                        .insert_header(header::ContentType(mime::TEXT_PLAIN_UTF_8))
+                        .body("Files can not be written at this path."),
+                ))
+            }
+        };
+
+        let exists = match fs::metadata(&full_path) {
+            Ok(md) if md.is_dir() => {
+                return Ok(req.into_response(
+                    HttpResponse::Conflict()
+                        .insert_header(header::ContentType(mime::TEXT_PLAIN_UTF_8))
+                        .body("Path is a directory."),
+                ))
+            }
+            Ok(_) => true,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+            Err(err) => return Ok(req.error_response(err)),
+        };
+
+        let etag = if exists {
+            current_etag(&full_path).await
+        } else {
+            None
+        };
+
+        if !preconditions_met(&req, exists, etag.as_ref()) {
+            return Ok(req.into_response(HttpResponse::PreconditionFailed().finish()));
+        }
+
+        if req.method() == actix_web::http::Method::DELETE {
+            if !exists {
+                return Ok(req.into_response(HttpResponse::NotFound().finish()));
+            }
+
+            web::block(move || fs::remove_file(full_path)).await??;
+            return Ok(req.into_response(HttpResponse::NoContent().finish()));
+        }
+
+        self.upload(req, full_path, exists).await
+    }
+
+    /// Streams request payload to temporary file that is then moved to `path`.
+    async fn upload(
+        &self,
+        req: ServiceRequest,
+        path: PathBuf,
+        exists: bool,
+    ) -> Result<ServiceResponse, Error> {
+        let limit = self.upload_limit;
+
+        let content_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<u64>().ok());
+
+        if content_length.map_or(false, |len| len > limit) {
+            return Ok(req.into_response(HttpResponse::PayloadTooLarge().finish()));
+        }
+
+        // temporary file is hidden and next to target so that it can be renamed atomically
+        let dir = path.parent().expect("file path has parent").to_owned();
+        let tmp_name = format!(
+            ".{}.{}.tmp",
+            path.file_name()
+                .expect("file path has file name")
+                .to_string_lossy(),
+            rand::thread_rng()
+                .sample_iter(&rand::distributions::Alphanumeric)
+                .take(16)
+                .map(char::from)
+                .collect::<String>()
+        );
+        let tmp_path = dir.join(tmp_name);
+
+        // guard removes temporary file unless it is moved to `path`, also if this future is dropped
+        let (file, tmp_path) = web::block(move || {
+            fs::create_dir_all(&dir)?;
+            fs::File::create(&tmp_path).map(|file| (file, TempPath(Some(tmp_path))))
+        })
+        .await??;
+
+        let (req, mut payload) = req.into_parts();
+
+        let file = match write_payload(&mut payload, file, limit).await? {
+            Some(file) => file,
+            None => {
+                let res = HttpResponse::PayloadTooLarge().finish();
+                return Ok(ServiceResponse::new(req, res));
+            }
+        };
+
+        {
+            let path = path.clone();
+            web::block(move || {
+                file.sync_all()?;
+                tmp_path.persist(&path)
+            })
+            .await??;
+        }
+
+        let mut res = if exists {
+            HttpResponse::NoContent()
+        } else {
+            HttpResponse::Created()
+        };
+
+        if let Some(etag) = current_etag(&path).await {
+            res.insert_header(header::ETag(etag));
+        }
+
+        Ok(ServiceResponse::new(req, res.finish()))
+    }
+}
+
+/// Path of a temporary file, which is removed on drop if still set.
+struct TempPath(Option<PathBuf>);
+
+impl TempPath {
+    /// Moves the file to `path` so that it is kept.
+    fn persist(mut self, path: &Path) -> io::Result<()> {
+        let tmp_path = self.0.take().expect("temporary file is not persisted");
+
+        fs::rename(&tmp_path, path).map_err(|err| {
+            self.0 = Some(tmp_path);
+            err
+        })
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Writes payload to `file`.
+///
+/// Returns `None` if the payload is larger than `limit` bytes.
+async fn write_payload(
+    payload: &mut Payload,
+    mut file: fs::File,
+    limit: u64,
+) -> Result<Option<fs::File>, Error> {
+    let mut size = 0;
+
+    while let Some(chunk) = poll_fn(|cx| Pin::new(&mut *payload).poll_next(cx)).await {
+        let chunk = chunk?;
+
+        size += chunk.len() as u64;
+        if size > limit {
+            return Ok(None);
+        }
+
+        file = web::block(move || file.write_all(&chunk).map(|_| file)).await??;
+    }
+
+    Ok(Some(file))
+}
+
+/// Returns ETag of the file at `path`, as sent with responses serving it.
+async fn current_etag(path: &Path) -> Option<EntityTag> {
+    NamedFile::open_async(path)
+        .await
+        .ok()
+        .and_then(|file| file.etag())
+}
+
+/// Returns true if `If-Match` and `If-None-Match` preconditions of `req` hold for the current file,
+/// which has `etag` if it `exists`.
+fn preconditions_met(req: &ServiceRequest, exists: bool, etag: Option<&EntityTag>) -> bool {
+    let if_match = !req.headers().contains_key(header::IF_MATCH)
+        || match header::IfMatch::parse(req) {
+            Ok(header::IfMatch::Any) => exists,
+            Ok(header::IfMatch::Items(items)) => {
+                etag.map_or(false, |etag| items.iter().any(|item| item.strong_eq(etag)))
+            }
+            Err(_) => false,
+        };
+
+    let if_none_match = !req.headers().contains_key(header::IF_NONE_MATCH)
+        || match header::IfNoneMatch::parse(req) {
+            Ok(header::IfNoneMatch::Any) => !exists,
+            Ok(header::IfNoneMatch::Items(items)) => {
+                etag.map_or(true, |etag| !items.iter().any(|item| item.weak_eq(etag)))
+            }
+            Err(_) => false,
+        };
+
+    if_match && if_none_match
+}
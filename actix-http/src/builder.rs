@@ -1,17 +1,23 @@
-use std::{fmt, marker::PhantomData, net, rc::Rc};
+use std::{fmt, marker::PhantomData, net, rc::Rc, time::Duration};
 
 use actix_codec::Framed;
 use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::{
     body::{BoxBody, MessageBody},
-    config::{KeepAlive, ServiceConfig},
+    config::{ConnectionConfig, KeepAlive, MinDataRate, ServiceConfig},
     h1::{self, ExpectHandler, H1Service, UpgradeHandler},
     h2::H2Service,
+    header::HeaderValue,
     service::HttpService,
+    shutdown::ShutdownSignal,
+    stats::{ConnectionStats, OnDisconnect},
     ConnectCallback, Extensions, Request, Response,
 };
 
+#[cfg(feature = "http3")]
+use crate::h3::H3Service;
+
 /// A HTTP service builder
 ///
 /// This type can be used to construct an instance of [`HttpService`] through a
@@ -22,6 +28,7 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler> {
     client_disconnect: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    conn: ConnectionConfig,
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
@@ -43,6 +50,7 @@ where
             client_disconnect: 0,
             secure: false,
             local_addr: None,
+            conn: ConnectionConfig::default(),
             expect: ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
@@ -111,6 +119,227 @@ where
         self
     }
 
+    /// Set the maximum number of concurrent HTTP/2 streams the client may open per connection.
+    ///
+    /// By default, the number of concurrent streams is not limited.
+    pub fn h2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.conn.h2.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set the initial HTTP/2 stream-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn h2_initial_window_size(mut self, size: u32) -> Self {
+        self.conn.h2.initial_window_size = Some(size);
+        self
+    }
+
+    /// Set the initial HTTP/2 connection-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn h2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.conn.h2.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set the maximum HTTP/2 frame size in bytes the server is willing to receive.
+    ///
+    /// The value must be between 16,384 and 16,777,215 bytes. By default it is set to 16,384.
+    pub fn h2_max_frame_size(mut self, max: u32) -> Self {
+        self.conn.h2.max_frame_size = Some(max);
+        self
+    }
+
+    /// Set the maximum size in bytes of the HTTP/2 header list the server is willing to accept.
+    ///
+    /// By default it is set to 16MiB.
+    pub fn h2_max_header_list_size(mut self, max: u32) -> Self {
+        self.conn.h2.max_header_list_size = Some(max);
+        self
+    }
+
+    /// Set the maximum number of concurrently reset HTTP/2 streams.
+    ///
+    /// Locally reset streams are tracked for a while in order to handle frames that the peer
+    /// sent before it learned about the reset. By default at most 10 streams are tracked.
+    pub fn h2_max_concurrent_reset_streams(mut self, max: usize) -> Self {
+        self.conn.h2.max_concurrent_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the duration for which locally reset HTTP/2 streams are remembered.
+    ///
+    /// By default reset streams are remembered for 30 seconds.
+    pub fn h2_reset_stream_duration(mut self, dur: Duration) -> Self {
+        self.conn.h2.reset_stream_duration = Some(dur);
+        self
+    }
+
+    /// Set the maximum number of HTTP/2 streams that were reset by the client before they were
+    /// accepted by the server.
+    ///
+    /// Once the limit is reached the connection is closed. This protects against "rapid reset"
+    /// floods. By default the limit is 20.
+    pub fn h2_max_pending_accept_reset_streams(mut self, max: usize) -> Self {
+        self.conn.h2.max_pending_accept_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the maximum number of HTTP/2 streams the server may reset because of client errors.
+    ///
+    /// Once the limit is reached the connection is closed. By default the limit is 1024.
+    pub fn h2_max_local_error_reset_streams(mut self, max: usize) -> Self {
+        self.conn.h2.max_local_error_reset_streams = Some(max);
+        self
+    }
+
+    /// Advertise support for extended CONNECT requests (RFC 8441) on HTTP/2 connections.
+    ///
+    /// Clients use extended CONNECT to open WebSockets over HTTP/2 streams, which
+    /// [`ws::handshake`](crate::ws::handshake) accepts. Only enable this when the service has
+    /// WebSocket routes.
+    ///
+    /// Disabled by default.
+    pub fn h2_enable_connect_protocol(mut self, enabled: bool) -> Self {
+        self.conn.h2.enable_connect_protocol = enabled;
+        self
+    }
+
+    /// Set the signal that starts a graceful shutdown of open connections.
+    ///
+    /// Once the signal is triggered, HTTP/1 connections send `Connection: close` with the next
+    /// response and idle keep-alive connections are closed. HTTP/2 connections send `GOAWAY` and
+    /// are closed after in-flight streams complete.
+    pub fn shutdown_signal(mut self, signal: ShutdownSignal) -> Self {
+        self.conn.shutdown = Some(signal);
+        self
+    }
+
+    /// Enable HTTP/2 over cleartext TCP (h2c) for connections that start out as HTTP/1.
+    ///
+    /// Connections are switched to HTTP/2 when the client sends the HTTP/2 connection preface
+    /// ("prior knowledge") or when it sends an HTTP/1.1 request with `Upgrade: h2c`. Upgrade
+    /// requests with a body are served over HTTP/1.1. Settings in the `HTTP2-Settings` header of
+    /// upgrade requests are ignored; only the client's `SETTINGS` frame is applied.
+    ///
+    /// Only affects services created with [`finish`](Self::finish) and must not be enabled for
+    /// TLS connections, where HTTP/2 is negotiated using ALPN instead.
+    ///
+    /// Disabled by default.
+    pub fn h2c(mut self, enabled: bool) -> Self {
+        self.conn.h2c = enabled;
+        self
+    }
+
+    /// Set the `Alt-Svc` header value that advertises alternative services to clients.
+    ///
+    /// The header is added to HTTP/1 and HTTP/2 responses that do not already contain one and is
+    /// typically used to announce an HTTP/3 endpoint, e.g. `h3=":443"; ma=86400`.
+    pub fn alt_svc(mut self, value: HeaderValue) -> Self {
+        self.conn.alt_svc = Some(value);
+        self
+    }
+
+    /// Enable strict parsing of HTTP/1 requests to protect against request smuggling.
+    ///
+    /// Requests are answered with `400 Bad Request` when they:
+    /// - contain both `Content-Length` and `Transfer-Encoding`;
+    /// - contain multiple or non-numeric `Content-Length` headers;
+    /// - use a `Transfer-Encoding` other than `chunked`;
+    /// - use obsolete line folding (obs-fold) in header values;
+    /// - end lines with a bare LF instead of CRLF;
+    /// - have whitespace between a header name and the colon;
+    /// - contain chunk size lines with whitespace or extensions that are not valid according to
+    ///   RFC 9112.
+    ///
+    /// Useful when the server sits behind proxies that may interpret such requests differently.
+    ///
+    /// Disabled by default.
+    pub fn h1_strict(mut self, enabled: bool) -> Self {
+        self.conn.h1_strict = enabled;
+        self
+    }
+
+    /// Set time limit for receiving the head of each HTTP/1 request.
+    ///
+    /// The deadline starts when the first byte of a request head is read, which, unlike
+    /// [`client_timeout`](Self::client_timeout), also covers requests after the first one on a
+    /// keep-alive connection. Clients that do not send the complete head in time get a
+    /// `408 Request Timeout` response and the connection is closed.
+    ///
+    /// Disabled by default.
+    pub fn h1_header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.conn.h1_header_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set minimum rate at which HTTP/1 request body data must be received.
+    ///
+    /// While a request body is being read, at least `bytes` bytes must arrive within every
+    /// `period`. Periods in which the service does not consume the body fast enough for the
+    /// connection to keep reading are not counted. When the rate is not met, the request payload
+    /// fails with [`PayloadError::Timeout`](crate::error::PayloadError::Timeout), a
+    /// `408 Request Timeout` response is sent if the service has not responded yet and the
+    /// connection is closed.
+    ///
+    /// Disabled by default.
+    pub fn h1_min_body_rate(mut self, bytes: u64, period: Duration) -> Self {
+        self.conn.h1_min_body_rate = Some(MinDataRate { bytes, period });
+        self
+    }
+
+    /// Set maximum number of requests served on a single connection.
+    ///
+    /// The response to the last allowed request on an HTTP/1 connection carries
+    /// `Connection: close`. HTTP/2 connections are sent a GOAWAY frame once the limit is reached;
+    /// in-flight streams are completed before the connection is closed.
+    ///
+    /// Useful behind layer 4 load balancers, where long-lived connections would otherwise pin
+    /// clients to one instance.
+    ///
+    /// By default, the number of requests is not limited. Setting `max` to 0 removes the limit.
+    pub fn max_requests_per_conn(mut self, max: usize) -> Self {
+        self.conn.max_requests = if max == 0 { None } else { Some(max) };
+        self
+    }
+
+    /// Set maximum lifetime of a connection.
+    ///
+    /// Once a connection is older than `lifetime`, idle HTTP/1 connections are closed and the next
+    /// response on busy ones carries `Connection: close`. HTTP/2 connections are sent a GOAWAY
+    /// frame; in-flight streams are completed before the connection is closed.
+    ///
+    /// By default, the lifetime of connections is not limited.
+    pub fn conn_lifetime(mut self, lifetime: Duration) -> Self {
+        self.conn.lifetime = Some(lifetime);
+        self
+    }
+
+    /// Enable collection of per-connection statistics.
+    ///
+    /// When enabled, a [`ConnectionStats`] handle is added to the connection data of each
+    /// connection and can be retrieved in services through `Request::conn_data`.
+    ///
+    /// Disabled by default.
+    pub fn connection_stats(mut self, enabled: bool) -> Self {
+        self.conn.stats = enabled;
+        self
+    }
+
+    /// Sets the callback to be run with the statistics of each connection once it is closed.
+    ///
+    /// The callback runs after the connection has been shut down and all requests received on it
+    /// have been dropped. Setting it enables [connection statistics](Self::connection_stats).
+    pub fn on_disconnect<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ConnectionStats) + 'static,
+    {
+        self.conn.stats = true;
+        self.conn.on_disconnect = Some(OnDisconnect(Rc::new(f)));
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -129,6 +358,7 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            conn: self.conn,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
@@ -153,6 +383,7 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            conn: self.conn,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect_ext: self.on_connect_ext,
@@ -182,12 +413,13 @@ where
         S::InitError: fmt::Debug,
         S::Response: Into<Response<B>>,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_connection_config(
             self.keep_alive,
             self.client_timeout,
             self.client_disconnect,
             self.secure,
             self.local_addr,
+            self.conn,
         );
 
         H1Service::with_config(cfg, service.into_factory())
@@ -206,17 +438,44 @@ where
 
         B: MessageBody + 'static,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_connection_config(
             self.keep_alive,
             self.client_timeout,
             self.client_disconnect,
             self.secure,
             self.local_addr,
+            self.conn,
         );
 
         H2Service::with_config(cfg, service.into_factory()).on_connect_ext(self.on_connect_ext)
     }
 
+    /// Finish service configuration and create a HTTP service for HTTP/3 protocol.
+    ///
+    /// The returned service factory accepts QUIC connections; expect and upgrade services as well
+    /// as the on-connect callback are not used for HTTP/3.
+    #[cfg(feature = "http3")]
+    pub fn h3<F, B>(self, service: F) -> H3Service<S, B>
+    where
+        F: IntoServiceFactory<S, Request>,
+        S::Error: Into<Response<BoxBody>> + 'static,
+        S::InitError: fmt::Debug,
+        S::Response: Into<Response<B>> + 'static,
+
+        B: MessageBody + 'static,
+    {
+        let cfg = ServiceConfig::with_connection_config(
+            self.keep_alive,
+            self.client_timeout,
+            self.client_disconnect,
+            self.secure,
+            self.local_addr,
+            self.conn,
+        );
+
+        H3Service::with_config(cfg, service.into_factory())
+    }
+
     /// Finish service configuration and create `HttpService` instance.
     pub fn finish<F, B>(self, service: F) -> HttpService<T, S, B, X, U>
     where
@@ -227,12 +486,13 @@ where
 
         B: MessageBody + 'static,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_connection_config(
             self.keep_alive,
             self.client_timeout,
             self.client_disconnect,
             self.secure,
             self.local_addr,
+            self.conn,
         );
 
         HttpService::with_config(cfg, service.into_factory())
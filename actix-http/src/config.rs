@@ -12,6 +12,8 @@ use actix_rt::{
 };
 use bytes::BytesMut;
 
+use crate::{header::HeaderValue, shutdown::ShutdownSignal, stats::OnDisconnect};
+
 /// "Sun, 06 Nov 1994 08:49:37 GMT".len()
 pub(crate) const DATE_VALUE_LENGTH: usize = 29;
 
@@ -44,9 +46,92 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+/// HTTP/2 connection settings.
+///
+/// Settings that are not set use the defaults of the `h2` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct H2Config {
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) initial_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+    pub(crate) max_frame_size: Option<u32>,
+    pub(crate) max_header_list_size: Option<u32>,
+    pub(crate) max_concurrent_reset_streams: Option<usize>,
+    pub(crate) reset_stream_duration: Option<Duration>,
+    pub(crate) max_pending_accept_reset_streams: Option<usize>,
+    pub(crate) max_local_error_reset_streams: Option<usize>,
+    pub(crate) enable_connect_protocol: bool,
+}
+
+impl H2Config {
+    /// Create `h2` server builder with these settings applied.
+    pub(crate) fn server_builder(&self) -> h2::server::Builder {
+        let mut builder = h2::server::Builder::new();
+
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(max) = self.max_frame_size {
+            builder.max_frame_size(max);
+        }
+        if let Some(max) = self.max_header_list_size {
+            builder.max_header_list_size(max);
+        }
+        if let Some(max) = self.max_concurrent_reset_streams {
+            builder.max_concurrent_reset_streams(max);
+        }
+        if let Some(dur) = self.reset_stream_duration {
+            builder.reset_stream_duration(dur);
+        }
+        if let Some(max) = self.max_pending_accept_reset_streams {
+            builder.max_pending_accept_reset_streams(max);
+        }
+        if let Some(max) = self.max_local_error_reset_streams {
+            builder.max_local_error_reset_streams(Some(max));
+        }
+        if self.enable_connect_protocol {
+            // allow bootstrapping WebSockets over HTTP/2 streams (RFC 8441)
+            builder.enable_connect_protocol();
+        }
+
+        builder
+    }
+}
+
+/// Minimum rate at which request body data must be received.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MinDataRate {
+    /// Number of bytes that must be received per period.
+    pub(crate) bytes: u64,
+    pub(crate) period: Duration,
+}
+
+/// Connection settings that are only configurable through `HttpServiceBuilder`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionConfig {
+    pub(crate) h2: H2Config,
+    pub(crate) shutdown: Option<ShutdownSignal>,
+    pub(crate) h2c: bool,
+    pub(crate) alt_svc: Option<HeaderValue>,
+    pub(crate) h1_strict: bool,
+    pub(crate) h1_header_read_timeout: Option<Duration>,
+    pub(crate) h1_min_body_rate: Option<MinDataRate>,
+    pub(crate) stats: bool,
+    pub(crate) on_disconnect: Option<OnDisconnect>,
+    pub(crate) max_requests: Option<usize>,
+    pub(crate) lifetime: Option<Duration>,
+}
+
 /// Http service configuration
 pub struct ServiceConfig(Rc<Inner>);
 
+#[derive(Clone)]
 struct Inner {
     keep_alive: Option<Duration>,
     client_timeout: u64,
@@ -54,7 +139,8 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
-    date_service: DateService,
+    conn: ConnectionConfig,
+    date_service: Rc<DateService>,
 }
 
 impl Clone for ServiceConfig {
@@ -77,6 +163,25 @@ impl ServiceConfig {
         client_disconnect: u64,
         secure: bool,
         local_addr: Option<net::SocketAddr>,
+    ) -> ServiceConfig {
+        Self::with_connection_config(
+            keep_alive,
+            client_timeout,
+            client_disconnect,
+            secure,
+            local_addr,
+            ConnectionConfig::default(),
+        )
+    }
+
+    /// Create instance of `ServiceConfig` with custom connection settings.
+    pub(crate) fn with_connection_config(
+        keep_alive: KeepAlive,
+        client_timeout: u64,
+        client_disconnect: u64,
+        secure: bool,
+        local_addr: Option<net::SocketAddr>,
+        conn: ConnectionConfig,
     ) -> ServiceConfig {
         let (keep_alive, ka_enabled) = match keep_alive {
             KeepAlive::Timeout(val) => (val as u64, true),
@@ -96,7 +201,8 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
-            date_service: DateService::new(),
+            conn,
+            date_service: Rc::new(DateService::new()),
         }))
     }
 
@@ -126,6 +232,80 @@ impl ServiceConfig {
         self.0.ka_enabled
     }
 
+    /// HTTP/2 connection settings.
+    #[inline]
+    pub(crate) fn h2(&self) -> &H2Config {
+        &self.0.conn.h2
+    }
+
+    /// Mutable HTTP/2 connection settings; copies the configuration if it is shared.
+    pub(crate) fn h2_mut(&mut self) -> &mut H2Config {
+        &mut Rc::make_mut(&mut self.0).conn.h2
+    }
+
+    /// Signal that notifies dispatchers of a graceful server shutdown.
+    #[inline]
+    pub(crate) fn shutdown_signal(&self) -> Option<&ShutdownSignal> {
+        self.0.conn.shutdown.as_ref()
+    }
+
+    /// Returns true if HTTP/1 connections may switch to HTTP/2 over cleartext (h2c).
+    #[inline]
+    pub(crate) fn h2c_enabled(&self) -> bool {
+        self.0.conn.h2c
+    }
+
+    /// `Alt-Svc` header value that is added to HTTP/1 and HTTP/2 responses.
+    #[inline]
+    pub(crate) fn alt_svc(&self) -> Option<&HeaderValue> {
+        self.0.conn.alt_svc.as_ref()
+    }
+
+    /// Returns true if HTTP/1 requests with ambiguous framing are rejected.
+    #[inline]
+    pub(crate) fn h1_strict(&self) -> bool {
+        self.0.conn.h1_strict
+    }
+
+    /// Time limit for receiving an HTTP/1 request head once its first byte was read.
+    #[inline]
+    pub(crate) fn h1_header_read_timeout(&self) -> Option<Duration> {
+        self.0.conn.h1_header_read_timeout
+    }
+
+    /// Minimum rate at which HTTP/1 request body data must be received.
+    #[inline]
+    pub(crate) fn h1_min_body_rate(&self) -> Option<MinDataRate> {
+        self.0.conn.h1_min_body_rate
+    }
+
+    /// Returns true if statistics are collected for each connection.
+    #[inline]
+    pub(crate) fn connection_stats(&self) -> bool {
+        self.0.conn.stats
+    }
+
+    /// Hook that is run with the statistics of each connection once it is closed.
+    #[inline]
+    pub(crate) fn on_disconnect(&self) -> Option<&OnDisconnect> {
+        self.0.conn.on_disconnect.as_ref()
+    }
+
+    /// Maximum number of requests served on a single connection.
+    #[inline]
+    pub(crate) fn max_requests_per_conn(&self) -> Option<usize> {
+        self.0.conn.max_requests
+    }
+
+    /// Timer that fires once a connection has reached its maximum lifetime.
+    #[inline]
+    pub(crate) fn conn_lifetime_timer(&self) -> Option<Sleep> {
+        self.0
+            .conn
+            .lifetime
+            .map(|lifetime| sleep_until(Instant::now() + lifetime))
+    }
+
     /// Client timeout for first request.
     #[inline]
     pub fn client_timer(&self) -> Option<Sleep> {
@@ -282,6 +282,10 @@ pub enum PayloadError {
     /// Generic I/O error.
     #[display(fmt = "{}", _0)]
     Io(io::Error),
+
+    /// Payload data was not received at the minimum required rate.
+    #[display(fmt = "Payload data was not received in time.")]
+    Timeout,
 }
 
 impl std::error::Error for PayloadError {
@@ -294,6 +298,7 @@ impl std::error::Error for PayloadError {
             PayloadError::UnknownLength => None,
             PayloadError::Http2Payload(err) => Some(err as &dyn std::error::Error),
             PayloadError::Io(err) => Some(err as &dyn std::error::Error),
+            PayloadError::Timeout => None,
         }
     }
 }
@@ -360,6 +365,11 @@ pub enum DispatchError {
     #[display(fmt = "{}", _0)]
     H2(h2::Error),
 
+    /// Http/3 error
+    #[cfg(feature = "http3")]
+    #[display(fmt = "{}", _0)]
+    H3(h3::Error),
+
     /// The first request did not complete within the specified timeout.
     #[display(fmt = "The first request did not complete within the specified timeout")]
     SlowRequestTimeout,
@@ -398,6 +408,35 @@ pub enum ContentTypeError {
     UnknownEncoding,
 }
 
+/// A set of errors that can occur when sending informational (1xx) responses.
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InformationalError {
+    /// Status code is not an informational one or is reserved for protocol use
+    /// (`100 Continue` and `101 Switching Protocols`).
+    #[display(fmt = "Invalid informational status code")]
+    InvalidStatus,
+
+    /// Final response has already been sent.
+    #[display(fmt = "Final response has already been sent")]
+    Closed,
+}
+
+/// A set of errors that can occur when taking over a connection after switching protocols.
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UpgradeError {
+    /// Connection cannot be taken over, e.g. because the request was not received over HTTP/1
+    /// or does not ask for a protocol switch.
+    #[display(fmt = "Connection cannot be taken over")]
+    Unsupported,
+
+    /// Connection was not handed over, either because the response did not switch protocols or
+    /// because the connection was closed first.
+    #[display(fmt = "Connection was not upgraded")]
+    NotUpgraded,
+}
+
 #[cfg(test)]
 mod content_type_test_impls {
     use super::*;
@@ -2,6 +2,9 @@ use std::{io, task::Poll};
 
 use bytes::{Buf as _, Bytes, BytesMut};
 
+/// Maximum length of chunk extensions accepted by strict parsing.
+const MAX_CHUNK_EXTENSION_SIZE: usize = 4096;
+
 macro_rules! byte (
     ($rdr:ident) => ({
         if $rdr.len() > 0 {
@@ -34,11 +37,13 @@ impl ChunkedState {
         body: &mut BytesMut,
         size: &mut u64,
         buf: &mut Option<Bytes>,
+        strict: bool,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         use self::ChunkedState::*;
         match *self {
             Size => ChunkedState::read_size(body, size),
-            SizeLws => ChunkedState::read_size_lws(body),
+            SizeLws => ChunkedState::read_size_lws(body, strict),
+            Extension if strict => ChunkedState::read_extension_strict(body),
             Extension => ChunkedState::read_extension(body),
             SizeLf => ChunkedState::read_size_lf(body, *size),
             Body => ChunkedState::read_body(body, size, buf),
@@ -85,12 +90,16 @@ impl ChunkedState {
         }
     }
 
-    fn read_size_lws(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
+    fn read_size_lws(
+        rdr: &mut BytesMut,
+        strict: bool,
+    ) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             // LWS can follow the chunk size, but no more digits can come
             b'\t' | b' ' => Poll::Ready(Ok(ChunkedState::SizeLws)),
             b';' => Poll::Ready(Ok(ChunkedState::Extension)),
-            b'\r' => Poll::Ready(Ok(ChunkedState::SizeLf)),
+            // whitespace is only allowed in front of extensions (RFC 9112 §7.1.1)
+            b'\r' if !strict => Poll::Ready(Ok(ChunkedState::SizeLf)),
             _ => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Invalid chunk size linear white space",
@@ -108,6 +117,31 @@ impl ChunkedState {
             _ => Poll::Ready(Ok(ChunkedState::Extension)), // no supported extensions
         }
     }
+
+    /// Read and validate the chunk extensions after the first `;` up to the CR of the size line.
+    fn read_extension_strict(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
+        let end = match rdr.iter().position(|&b| b == b'\r') {
+            Some(end) => end,
+            None if rdr.len() > MAX_CHUNK_EXTENSION_SIZE => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Chunk extension is too long",
+                )));
+            }
+            None => return Poll::Pending,
+        };
+
+        if !is_valid_extension(&rdr[..end]) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid chunk extension",
+            )));
+        }
+
+        rdr.advance(end + 1);
+        Poll::Ready(Ok(ChunkedState::SizeLf))
+    }
+
     fn read_size_lf(rdr: &mut BytesMut, size: u64) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             b'\n' if size > 0 => Poll::Ready(Ok(ChunkedState::Body)),
@@ -185,6 +219,87 @@ impl ChunkedState {
     }
 }
 
+/// Returns true if `ext` matches the `chunk-ext` grammar of RFC 9112 with the first `;` removed:
+///
+/// ```text
+/// BWS chunk-ext-name [ BWS "=" BWS chunk-ext-val ] *( BWS ";" BWS chunk-ext-name ... ) BWS
+/// ```
+fn is_valid_extension(ext: &[u8]) -> bool {
+    fn skip_ws(ext: &[u8], pos: &mut usize) {
+        while matches!(ext.get(*pos), Some(b' ') | Some(b'\t')) {
+            *pos += 1;
+        }
+    }
+
+    fn skip_token(ext: &[u8], pos: &mut usize) -> bool {
+        let start = *pos;
+        while ext.get(*pos).map_or(false, |&b| is_token(b)) {
+            *pos += 1;
+        }
+        *pos > start
+    }
+
+    fn skip_quoted(ext: &[u8], pos: &mut usize) -> bool {
+        // opening quote
+        *pos += 1;
+
+        loop {
+            match ext.get(*pos) {
+                Some(b'"') => {
+                    *pos += 1;
+                    return true;
+                }
+                Some(b'\\') => match ext.get(*pos + 1) {
+                    Some(&b) if b == b'\t' || b >= 0x20 && b != 0x7f => *pos += 2,
+                    _ => return false,
+                },
+                Some(&b) if b == b'\t' || b >= 0x20 && b != 0x7f => *pos += 1,
+                _ => return false,
+            }
+        }
+    }
+
+    let mut pos = 0;
+
+    loop {
+        skip_ws(ext, &mut pos);
+
+        if !skip_token(ext, &mut pos) {
+            return false;
+        }
+
+        skip_ws(ext, &mut pos);
+
+        if ext.get(pos) == Some(&b'=') {
+            pos += 1;
+            skip_ws(ext, &mut pos);
+
+            let valid = if ext.get(pos) == Some(&b'"') {
+                skip_quoted(ext, &mut pos)
+            } else {
+                skip_token(ext, &mut pos)
+            };
+
+            if !valid {
+                return false;
+            }
+
+            skip_ws(ext, &mut pos);
+        }
+
+        match ext.get(pos) {
+            None => return true,
+            Some(b';') => pos += 1,
+            Some(_) => return false,
+        }
+    }
+}
+
+/// Returns true if byte is a `tchar` (RFC 9110 §5.6.2).
+fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_codec::Decoder as _;
@@ -59,10 +59,16 @@ impl Codec {
             Flags::empty()
         };
 
+        let decoder = if config.h1_strict() {
+            decoder::MessageDecoder::strict()
+        } else {
+            decoder::MessageDecoder::default()
+        };
+
         Codec {
             config,
             flags,
-            decoder: decoder::MessageDecoder::default(),
+            decoder,
             payload: None,
             version: Version::HTTP_11,
             conn_type: ConnectionType::Close,
@@ -82,6 +88,13 @@ impl Codec {
         self.conn_type == ConnectionType::KeepAlive
     }
 
+    /// Check if last request's method is `HEAD`.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub(crate) fn is_head(&self) -> bool {
+        self.flags.contains(Flags::HEAD)
+    }
+
     /// Check if keep-alive enabled on server level.
     #[inline]
     pub fn keepalive_enabled(&self) -> bool {
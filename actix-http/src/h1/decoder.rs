@@ -18,7 +18,10 @@ pub(crate) const MAX_BUFFER_SIZE: usize = 131_072;
 const MAX_HEADERS: usize = 96;
 
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    strict: bool,
+    _phantom: PhantomData<T>,
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -30,7 +33,23 @@ pub(crate) enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder {
+            strict: false,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    /// Create decoder that rejects ambiguous message framing instead of tolerating it.
+    ///
+    /// See [`HttpServiceBuilder::h1_strict`](crate::HttpServiceBuilder::h1_strict) for the list of
+    /// rejected constructs.
+    pub(crate) fn strict() -> Self {
+        MessageDecoder {
+            strict: true,
+            _phantom: PhantomData,
+        }
     }
 }
 
@@ -39,7 +58,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, self.strict)
     }
 }
 
@@ -56,12 +75,16 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        strict: bool,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade_websocket = false;
@@ -69,6 +92,7 @@ pub(crate) trait MessageType: Sized {
         let mut chunked = false;
         let mut seen_te = false;
         let mut content_length = None;
+        let mut has_content_length = false;
 
         {
             let headers = self.headers_mut();
@@ -85,31 +109,43 @@ pub(crate) trait MessageType: Sized {
                 };
 
                 match name {
-                    header::CONTENT_LENGTH if content_length.is_some() => {
+                    header::CONTENT_LENGTH if has_content_length => {
                         debug!("multiple Content-Length");
                         return Err(ParseError::Header);
                     }
 
-                    header::CONTENT_LENGTH => match value.to_str() {
-                        Ok(s) if s.trim().starts_with('+') => {
-                            debug!("illegal Content-Length: {:?}", s);
-                            return Err(ParseError::Header);
-                        }
-                        Ok(s) => {
-                            if let Ok(len) = s.parse::<u64>() {
-                                if len != 0 {
-                                    content_length = Some(len);
-                                }
-                            } else {
+                    header::CONTENT_LENGTH => {
+                        has_content_length = true;
+
+                        match value.to_str() {
+                            Ok(s)
+                                if strict
+                                    && (s.is_empty()
+                                        || !s.bytes().all(|b| b.is_ascii_digit())) =>
+                            {
+                                debug!("illegal Content-Length in strict mode: {:?}", s);
+                                return Err(ParseError::Header);
+                            }
+                            Ok(s) if s.trim().starts_with('+') => {
                                 debug!("illegal Content-Length: {:?}", s);
                                 return Err(ParseError::Header);
                             }
+                            Ok(s) => {
+                                if let Ok(len) = s.parse::<u64>() {
+                                    if len != 0 {
+                                        content_length = Some(len);
+                                    }
+                                } else {
+                                    debug!("illegal Content-Length: {:?}", s);
+                                    return Err(ParseError::Header);
+                                }
+                            }
+                            Err(_) => {
+                                debug!("illegal Content-Length: {:?}", value);
+                                return Err(ParseError::Header);
+                            }
                         }
-                        Err(_) => {
-                            debug!("illegal Content-Length: {:?}", value);
-                            return Err(ParseError::Header);
-                        }
-                    },
+                    }
 
                     // transfer-encoding
                     header::TRANSFER_ENCODING if seen_te => {
@@ -123,7 +159,7 @@ pub(crate) trait MessageType: Sized {
                         if let Ok(s) = value.to_str().map(str::trim) {
                             if s.eq_ignore_ascii_case("chunked") {
                                 chunked = true;
-                            } else if s.eq_ignore_ascii_case("identity") {
+                            } else if !strict && s.eq_ignore_ascii_case("identity") {
                                 // allow silently since multiple TE headers are already checked
                             } else {
                                 debug!("illegal Transfer-Encoding: {:?}", s);
@@ -168,6 +204,13 @@ pub(crate) trait MessageType: Sized {
                 headers.append(name, value);
             }
         }
+
+        // https://datatracker.ietf.org/doc/html/rfc9112#section-6.1
+        if strict && seen_te && has_content_length {
+            debug!("both Content-Length and Transfer-Encoding not allowed in strict mode");
+            return Err(ParseError::Header);
+        }
+
         self.set_connection_type(ka);
         if expect {
             self.set_expect()
@@ -176,9 +219,13 @@ pub(crate) trait MessageType: Sized {
         // https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3
         if chunked {
             // Chunked encoding
-            Ok(PayloadLength::Payload(PayloadType::Payload(
-                PayloadDecoder::chunked(),
-            )))
+            let decoder = if strict {
+                PayloadDecoder::chunked_strict()
+            } else {
+                PayloadDecoder::chunked()
+            };
+
+            Ok(PayloadLength::Payload(PayloadType::Payload(decoder)))
         } else if has_upgrade_websocket {
             Ok(PayloadLength::UpgradeWebSocket)
         } else if let Some(len) = content_length {
@@ -207,7 +254,10 @@ impl MessageType for Request {
         &mut self.head_mut().headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
 
         let (len, method, uri, ver, h_len) = {
@@ -247,10 +297,14 @@ impl MessageType for Request {
             }
         };
 
+        if strict {
+            check_strict_head(&src[..len], &headers[..h_len])?;
+        }
+
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], strict)?;
 
         // payload decoder
         let decoder = match length {
@@ -290,7 +344,10 @@ impl MessageType for ResponseHead {
         &mut self.headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        strict: bool,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
 
         let (len, ver, status, h_len) = {
@@ -321,11 +378,15 @@ impl MessageType for ResponseHead {
             }
         };
 
+        if strict {
+            check_strict_head(&src[..len], &headers[..h_len])?;
+        }
+
         let mut msg = ResponseHead::new(status);
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], strict)?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -347,6 +408,38 @@ impl MessageType for ResponseHead {
     }
 }
 
+/// Check message head for constructs that parsers disagree on and that can be used for request
+/// smuggling; these are tolerated, or rejected only by some `httparse` configurations, otherwise.
+fn check_strict_head(head: &[u8], headers: &[HeaderIndex]) -> Result<(), ParseError> {
+    for (idx, &b) in head.iter().enumerate() {
+        if b != b'\n' {
+            continue;
+        }
+
+        // bare LF line ending
+        if idx == 0 || head[idx - 1] != b'\r' {
+            debug!("bare LF line ending not allowed in strict mode");
+            return Err(ParseError::Header);
+        }
+
+        // obsolete line folding
+        if matches!(head.get(idx + 1), Some(b' ') | Some(b'\t')) {
+            debug!("obs-fold not allowed in strict mode");
+            return Err(ParseError::Header);
+        }
+    }
+
+    // whitespace between field name and colon
+    for idx in headers {
+        if head.get(idx.name.1) != Some(&b':') {
+            debug!("whitespace before colon not allowed in strict mode");
+            return Err(ParseError::Header);
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct HeaderIndex {
     pub(crate) name: (usize, usize),
@@ -407,7 +500,14 @@ impl PayloadDecoder {
 
     pub fn chunked() -> PayloadDecoder {
         PayloadDecoder {
-            kind: Kind::Chunked(ChunkedState::Size, 0),
+            kind: Kind::Chunked(ChunkedState::Size, 0, false),
+        }
+    }
+
+    /// Chunked decoder that only accepts chunk extensions that are valid according to RFC 9112.
+    pub fn chunked_strict() -> PayloadDecoder {
+        PayloadDecoder {
+            kind: Kind::Chunked(ChunkedState::Size, 0, true),
         }
     }
 
@@ -421,8 +521,9 @@ enum Kind {
     /// A Reader used when a Content-Length header is passed with a positive
     /// integer.
     Length(u64),
-    /// A Reader used when Transfer-Encoding is `chunked`.
-    Chunked(ChunkedState, u64),
+    /// A Reader used when Transfer-Encoding is `chunked`. The flag enables strict parsing of the
+    /// chunk size lines.
+    Chunked(ChunkedState, u64, bool),
     /// A Reader used for responses that don't indicate a length or chunked.
     ///
     /// Note: This should only used for `Response`s. It is illegal for a
@@ -466,12 +567,12 @@ impl Decoder for PayloadDecoder {
                     Ok(Some(PayloadItem::Chunk(buf)))
                 }
             }
-            Kind::Chunked(ref mut state, ref mut size) => {
+            Kind::Chunked(ref mut state, ref mut size, strict) => {
                 loop {
                     let mut buf = None;
 
                     // advances the chunked state
-                    *state = match state.step(src, size, &mut buf) {
+                    *state = match state.step(src, size, &mut buf, strict) {
                         Poll::Pending => return Ok(None),
                         Poll::Ready(Ok(state)) => state,
                         Poll::Ready(Err(e)) => return Err(e),
@@ -1034,4 +1135,216 @@ mod tests {
         let chunk = pl.decode(&mut buf).unwrap().unwrap();
         assert_eq!(chunk, PayloadItem::Chunk(Bytes::from_static(b"0\r\n")));
     }
+
+    #[derive(Debug, PartialEq)]
+    enum Strict {
+        Accepted,
+        Rejected,
+        Incomplete,
+    }
+
+    /// Decode message head and payload using strict parsing.
+    fn decode_strict(raw: &str) -> Strict {
+        let mut buf = BytesMut::from(raw);
+
+        let pl = match MessageDecoder::<Request>::strict().decode(&mut buf) {
+            Ok(Some((_, pl))) => pl,
+            Ok(None) => return Strict::Incomplete,
+            Err(_) => return Strict::Rejected,
+        };
+
+        if let PayloadType::Payload(mut pl) = pl {
+            loop {
+                match pl.decode(&mut buf) {
+                    Ok(Some(PayloadItem::Eof)) => break,
+                    Ok(Some(PayloadItem::Chunk(_))) => {}
+                    Ok(None) => return Strict::Incomplete,
+                    Err(_) => return Strict::Rejected,
+                }
+            }
+        }
+
+        Strict::Accepted
+    }
+
+    /// Known request smuggling vectors; all of them are rejected in strict mode.
+    const HRS_CORPUS: &[(&str, &str)] = &[
+        (
+            "CL.TE",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 13\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nSMUGGLED",
+        ),
+        (
+            "TE.CL",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n8\r\nSMUGGLED\r\n0\r\n\r\n",
+        ),
+        (
+            "CL.TE with zero length",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "duplicate CL",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nhello",
+        ),
+        (
+            "conflicting CL",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!",
+        ),
+        (
+            "conflicting CL starting with zero",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0\r\nContent-Length: 5\r\n\r\nhello",
+        ),
+        (
+            "CL list",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5, 5\r\n\r\nhello",
+        ),
+        (
+            "CL with sign",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: +5\r\n\r\nhello",
+        ),
+        (
+            "CL in hex",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0x5\r\n\r\nhello",
+        ),
+        (
+            "TE identity",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: identity\r\nContent-Length: 5\r\n\r\nhello",
+        ),
+        (
+            "TE chunked not final",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked, identity\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "TE gzip then chunked",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip, chunked\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "TE chunked twice",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "TE obs-fold",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding:\r\n chunked\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "obs-fold with tab",
+            "GET / HTTP/1.1\r\nHost: a\r\nX-Foo: bar\r\n\tbaz\r\n\r\n",
+        ),
+        (
+            "bare LF request line",
+            "GET / HTTP/1.1\nHost: a\r\n\r\n",
+        ),
+        (
+            "bare LF header line",
+            "GET / HTTP/1.1\r\nHost: a\nX-Foo: bar\r\n\r\n",
+        ),
+        (
+            "bare LF end of head",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\nhello",
+        ),
+        (
+            "space before colon",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding : chunked\r\n\r\n0\r\n\r\n",
+        ),
+        (
+            "tab before colon",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length\t: 5\r\n\r\nhello",
+        ),
+        (
+            "chunk size with trailing whitespace",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5 \r\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk extension without name",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;\r\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk extension with space in name",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;a b\r\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk extension with unterminated quote",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;a=\"b\r\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk extension with invalid value",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;a=b=c\r\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk extension with bare LF",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;a\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk size with bare LF",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\nhello\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk data without CRLF",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhelloX\r\n0\r\n\r\n",
+        ),
+        (
+            "chunk data with bare LF",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\n0\r\n\r\n",
+        ),
+    ];
+
+    #[test]
+    fn hrs_strict_corpus() {
+        for (name, raw) in HRS_CORPUS {
+            assert_eq!(decode_strict(raw), Strict::Rejected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn strict_accepts_valid_requests() {
+        let valid = [
+            "GET / HTTP/1.1\r\nHost: a\r\nX-Foo: bar\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length:5\r\n\r\nhello",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: Chunked\r\n\r\n5;a\r\nhello\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+             5 ; a = b ;c=\"d \\\"e\\\"\"\r\nhello\r\n0;last\r\n\r\n",
+        ];
+
+        for raw in valid {
+            assert_eq!(decode_strict(raw), Strict::Accepted, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn strict_chunk_extension_partial() {
+        let mut buf = BytesMut::from(
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;name=val",
+        );
+
+        let (_, pl) = MessageDecoder::<Request>::strict()
+            .decode(&mut buf)
+            .unwrap()
+            .unwrap();
+        let mut pl = pl.unwrap();
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(b"ue\r\nhello\r\n");
+        let chunk = pl.decode(&mut buf).unwrap().unwrap().chunk();
+        assert_eq!(chunk, Bytes::from_static(b"hello"));
+
+        // unbounded extensions are rejected
+        buf.extend(b"5;");
+        buf.extend(vec![b'a'; 8192]);
+        assert!(pl.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn hrs_multiple_content_length_with_zero() {
+        let mut buf = BytesMut::from(
+            "GET / HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Content-Length: 0\r\n\
+            Content-Length: 5\r\n\
+            \r\n\
+            hello",
+        );
+
+        expect_parse_err!(&mut buf);
+    }
 }
@@ -21,8 +21,15 @@ use crate::{
     body::{BodySize, BoxBody, MessageBody},
     config::ServiceConfig,
     error::{DispatchError, ParseError, PayloadError},
+    h2::{self, Preface},
+    header::ALT_SVC,
+    informational::{Informational, InformationalQueue},
+    message::ConnectionType,
     service::HttpFlow,
-    Extensions, OnConnectData, Request, Response, StatusCode,
+    shutdown::ShutdownListener,
+    upgrade::{PendingUpgrade, Upgrade, Upgraded},
+    ConnectionStats, Extensions, HttpMessage as _, InformationalSender, Method, OnConnectData,
+    Request, Response, StatusCode, Version,
 };
 
 use super::{
@@ -43,6 +50,9 @@ bitflags! {
         const SHUTDOWN           = 0b0000_0100;
         const READ_DISCONNECT    = 0b0000_1000;
         const WRITE_DISCONNECT   = 0b0001_0000;
+        const DRAINING           = 0b0010_0000;
+        const H2C                = 0b0100_0000;
+        const UPGRADE            = 0b1000_0000;
     }
 }
 
@@ -104,6 +114,7 @@ where
     flags: Flags,
     peer_addr: Option<net::SocketAddr>,
     conn_data: Option<Rc<Extensions>>,
+    stats: Option<ConnectionStats>,
     error: Option<DispatchError>,
 
     #[pin]
@@ -115,12 +126,79 @@ where
     #[pin]
     ka_timer: Option<Sleep>,
 
+    shutdown: Option<ShutdownListener>,
+    /// Fires once the connection has reached its maximum lifetime.
+    #[pin]
+    lifetime_timer: Option<Sleep>,
+    /// Number of requests received on the connection.
+    requests: usize,
+    h2c: Option<H2cSwitch>,
+    tunnel: Option<Tunnel>,
+    informational: Rc<InformationalQueue>,
+
+    /// Deadline of the request head being read or end of the current body data rate period.
+    #[pin]
+    slow_timer: Option<Sleep>,
+    slow_read: SlowRead,
+    /// Request body bytes received in the current data rate period.
+    body_read: u64,
+
     io: Option<T>,
     read_buf: BytesMut,
     write_buf: BytesMut,
     codec: Codec,
 }
 
+/// Part of a request that is being read under a slow client deadline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SlowRead {
+    None,
+    Head,
+    Body,
+}
+
+/// Pending switch of the connection to HTTP/2 over cleartext (h2c).
+enum H2cSwitch {
+    /// Client sent the HTTP/2 connection preface.
+    PriorKnowledge,
+
+    /// Client asked to upgrade with this request; it is answered on HTTP/2 stream 1.
+    Upgrade(Request),
+
+    /// `101 Switching Protocols` response has been written for the upgrade request.
+    Switched(Request),
+}
+
+/// Connection take-over requested through an [`Upgrade`] handle.
+enum Tunnel {
+    /// Request that may switch protocols is being handled.
+    Pending(PendingUpgrade),
+
+    /// Switching response has been written; the connection is handed over once it is flushed.
+    Switching(PendingUpgrade),
+}
+
+impl Tunnel {
+    /// Returns true if the service asked to take over the connection.
+    fn is_requested(&self) -> bool {
+        match self {
+            Tunnel::Pending(pending) => pending.is_requested(),
+            Tunnel::Switching(_) => true,
+        }
+    }
+}
+
+/// Connection handed over to the HTTP/2 dispatcher after switching to h2c.
+pub(crate) struct H2cHandover<T, S, X, U> {
+    pub(crate) io: T,
+    pub(crate) read_buf: BytesMut,
+    pub(crate) req: Option<Request>,
+    pub(crate) flow: Rc<HttpFlow<S, X, U>>,
+    pub(crate) config: ServiceConfig,
+    pub(crate) conn_data: Option<Rc<Extensions>>,
+    pub(crate) peer_addr: Option<net::SocketAddr>,
+}
+
 enum DispatcherMessage {
     Item(Request),
     Upgrade(Request),
@@ -140,6 +218,8 @@ where
     ServiceCall(#[pin] S::Future),
     SendPayload(#[pin] B),
     SendErrorPayload(#[pin] BoxBody),
+    #[cfg(target_os = "linux")]
+    SendFile(crate::SendFile),
 }
 
 impl<S, B, X> State<S, B, X>
@@ -197,11 +277,14 @@ where
             None => (config.now(), None),
         };
 
+        let shutdown = config.shutdown_signal().map(|signal| signal.listener());
+
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
                 flow,
                 flags,
                 peer_addr,
+                stats: conn_data.stats(),
                 conn_data: conn_data.0.map(Rc::new),
                 error: None,
 
@@ -212,6 +295,17 @@ where
                 ka_expire,
                 ka_timer,
 
+                shutdown,
+                lifetime_timer: config.conn_lifetime_timer(),
+                requests: 0,
+                h2c: None,
+                tunnel: None,
+                informational: InformationalQueue::new(),
+
+                slow_timer: None,
+                slow_read: SlowRead::None,
+                body_read: 0,
+
                 io: Some(io),
                 read_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
                 write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
@@ -222,11 +316,46 @@ where
             poll_count: 0,
         }
     }
+
+    /// Allow switching the connection to HTTP/2 over cleartext (h2c).
+    ///
+    /// Must only be used on plaintext connections that can be served by the HTTP/2 dispatcher.
+    pub(crate) fn h2c(mut self) -> Self {
+        if let DispatcherState::Normal(ref mut inner) = self.inner {
+            inner.flags.insert(Flags::H2C);
+        }
+
+        self
+    }
+
+    /// Take connection from a dispatcher that resolved after switching to h2c.
+    pub(crate) fn take_h2c(self: Pin<&mut Self>) -> Option<H2cHandover<T, S, X, U>> {
+        let inner = match self.project().inner.project() {
+            DispatcherStateProj::Normal(inner) => inner.project(),
+            DispatcherStateProj::Upgrade(_) => return None,
+        };
+
+        let req = match inner.h2c.take()? {
+            H2cSwitch::PriorKnowledge => None,
+            H2cSwitch::Switched(req) => Some(req),
+            H2cSwitch::Upgrade(_) => return None,
+        };
+
+        Some(H2cHandover {
+            io: inner.io.take()?,
+            read_buf: mem::take(inner.read_buf),
+            req,
+            flow: Rc::clone(inner.flow),
+            config: inner.codec.config().clone(),
+            conn_data: inner.conn_data.take(),
+            peer_addr: *inner.peer_addr,
+        })
+    }
 }
 
 impl<T, S, B, X, U> InnerDispatcher<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>>,
@@ -261,7 +390,12 @@ where
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        let InnerDispatcherProj { io, write_buf, .. } = self.project();
+        let InnerDispatcherProj {
+            io,
+            write_buf,
+            stats,
+            ..
+        } = self.project();
         let mut io = Pin::new(io.as_mut().unwrap());
 
         let len = write_buf.len();
@@ -272,7 +406,13 @@ where
                 Poll::Ready(0) => {
                     return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "")))
                 }
-                Poll::Ready(n) => written += n,
+                Poll::Ready(n) => {
+                    written += n;
+
+                    if let Some(stats) = stats {
+                        stats.add_written(n);
+                    }
+                }
                 Poll::Pending => {
                     write_buf.advance(written);
                     return Poll::Pending;
@@ -289,11 +429,43 @@ where
 
     fn send_response_inner(
         self: Pin<&mut Self>,
-        message: Response<()>,
+        mut message: Response<()>,
         body: &impl MessageBody,
     ) -> Result<BodySize, DispatchError> {
         let size = body.size();
         let this = self.project();
+
+        // informational responses must precede the final response
+        write_informational(this.informational, this.write_buf);
+        this.informational.close_active();
+
+        // server is shutting down or connection limits are reached; tell client this is the last
+        // response on the connection
+        if this.flags.contains(Flags::DRAINING) {
+            message
+                .head_mut()
+                .set_connection_type(ConnectionType::Close);
+        }
+
+        if let Some(alt_svc) = this.codec.config().alt_svc() {
+            if !message.headers().contains_key(ALT_SVC) {
+                message.headers_mut().insert(ALT_SVC, alt_svc.clone());
+            }
+        }
+
+        // response to a request that may switch protocols; body is not sent when switching
+        let size = match this.tunnel.take() {
+            Some(Tunnel::Pending(pending)) if pending.switches(message.status()) => {
+                *this.tunnel = Some(Tunnel::Switching(pending));
+                BodySize::None
+            }
+            Some(_) => {
+                this.flags.remove(Flags::UPGRADE);
+                size
+            }
+            None => size,
+        };
+
         this.codec
             .encode(Message::Item((message, size)), this.write_buf)
             .map_err(|err| {
@@ -313,7 +485,23 @@ where
         message: Response<()>,
         body: B,
     ) -> Result<(), DispatchError> {
+        #[cfg(target_os = "linux")]
+        let send_file = message.head().extensions_mut().remove::<crate::SendFile>();
+
         let size = self.as_mut().send_response_inner(message, &body)?;
+
+        // copy file straight to the socket when the body was not transformed on the way
+        #[cfg(target_os = "linux")]
+        if let Some(send_file) = send_file {
+            if size == BodySize::Sized(send_file.len())
+                && !self.codec.is_head()
+                && tcp_stream(&self.io).is_some()
+            {
+                self.project().state.set(State::SendFile(send_file));
+                return Ok(());
+            }
+        }
+
         let state = match size {
             BodySize::None | BodySize::Sized(0) => State::None,
             _ => State::SendPayload(body),
@@ -353,6 +541,13 @@ where
                 StateProj::None => match this.messages.pop_front() {
                     // handle request message.
                     Some(DispatcherMessage::Item(req)) => {
+                        this.informational
+                            .activate(req.extensions().get::<InformationalSender>());
+                        *this.tunnel = req
+                            .extensions()
+                            .get::<Upgrade>()
+                            .map(|upgrade| Tunnel::Pending(upgrade.pending()));
+
                         // Handle `EXPECT: 100-Continue` header
                         if req.head().expect() {
                             // set InnerDispatcher state and continue loop to poll it.
@@ -469,6 +664,30 @@ where
                     return Ok(PollResponse::DrainWriteBuf);
                 }
 
+                #[cfg(target_os = "linux")]
+                StateProj::SendFile(send_file) => {
+                    // response head must be written before file data
+                    if !this.write_buf.is_empty() {
+                        return Ok(PollResponse::DrainWriteBuf);
+                    }
+
+                    let io = tcp_stream(this.io).expect("sendfile is only used on TCP streams");
+
+                    while send_file.len() > 0 {
+                        match send_file.poll_send(cx, io) {
+                            Poll::Ready(Ok(n)) => {
+                                if let Some(stats) = this.stats {
+                                    stats.add_written(n);
+                                }
+                            }
+                            Poll::Ready(Err(err)) => return Err(DispatchError::Io(err)),
+                            Poll::Pending => return Ok(PollResponse::DoNothing),
+                        }
+                    }
+
+                    this.state.set(State::None);
+                }
+
                 StateProj::ExpectCall(fut) => match fut.poll(cx) {
                     // expect resolved. write continue to buffer and set InnerDispatcher state
                     // to service call.
@@ -500,6 +719,12 @@ where
     ) -> Result<(), DispatchError> {
         // Handle `EXPECT: 100-Continue` header
         let mut this = self.as_mut().project();
+        this.informational
+            .activate(req.extensions().get::<InformationalSender>());
+        *this.tunnel = req
+            .extensions()
+            .get::<Upgrade>()
+            .map(|upgrade| Tunnel::Pending(upgrade.pending()));
         if req.head().expect() {
             // set dispatcher state so the future is pinned.
             let task = this.flow.expect.call(req);
@@ -575,9 +800,44 @@ where
             return Ok(false);
         }
 
+        // remaining data belongs to the HTTP/2 connection
+        if self.h2c.is_some() {
+            return Ok(false);
+        }
+
         let mut updated = false;
         let mut this = self.as_mut().project();
+
+        // HTTP/2 connection preface is only valid as the first bytes sent on the connection
+        if this.flags.contains(Flags::H2C) && !this.flags.contains(Flags::STARTED) {
+            match h2::check_preface(this.read_buf) {
+                Preface::Complete => {
+                    trace!("HTTP/2 connection preface received, switching to h2c");
+                    this.flags.insert(Flags::STARTED);
+                    *this.h2c = Some(H2cSwitch::PriorKnowledge);
+                    return Ok(true);
+                }
+                Preface::Partial => return Ok(false),
+                Preface::Missing => {}
+            }
+        }
+
         loop {
+            // wait for answer to request that may switch protocols before reading data that
+            // follows its head; once the request is handled, its body is read until the service
+            // asks to take over the connection, since the service may read it before responding
+            if this.flags.contains(Flags::UPGRADE) {
+                let payload = &*this.payload;
+                let paused = this
+                    .tunnel
+                    .as_ref()
+                    .map_or(true, |tunnel| payload.is_none() || tunnel.is_requested());
+
+                if paused {
+                    break;
+                }
+            }
+
             match this.codec.decode(this.read_buf) {
                 Ok(Some(msg)) => {
                     updated = true;
@@ -585,10 +845,52 @@ where
 
                     match msg {
                         Message::Item(mut req) => {
+                            *this.requests += 1;
+
+                            match this.codec.config().max_requests_per_conn() {
+                                // pipelined request after the last allowed one; the response to
+                                // that carries `Connection: close`, so stop reading
+                                Some(max) if *this.requests > max => {
+                                    trace!("Ignoring request over per-connection limit");
+                                    this.flags.insert(Flags::READ_DISCONNECT);
+                                    break;
+                                }
+
+                                Some(max) if *this.requests == max => {
+                                    trace!("Per-connection request limit reached");
+                                    this.flags.insert(Flags::DRAINING);
+                                }
+
+                                _ => {}
+                            }
+
                             req.head_mut().peer_addr = *this.peer_addr;
 
                             req.conn_data = this.conn_data.as_ref().map(Rc::clone);
 
+                            if let Some(stats) = this.stats {
+                                stats.add_request();
+                            }
+
+                            // informational responses are not understood by HTTP/1.0 clients
+                            let informational = if req.head().version >= Version::HTTP_11 {
+                                InformationalSender::new(Rc::clone(this.informational), 0)
+                            } else {
+                                InformationalSender::disabled()
+                            };
+                            req.extensions_mut().insert(informational);
+
+                            // switch to h2c after answering any pipelined requests; upgrade
+                            // is ignored for requests with a body
+                            if this.flags.contains(Flags::H2C)
+                                && this.codec.message_type() == MessageType::None
+                                && h2::is_upgrade_request(&req)
+                            {
+                                trace!("Upgrade to h2c requested");
+                                *this.h2c = Some(H2cSwitch::Upgrade(req));
+                                break;
+                            }
+
                             match this.codec.message_type() {
                                 // Request is upgradable. add upgrade message and break.
                                 // everything remain in read buffer would be handed to
@@ -612,12 +914,23 @@ where
                                     let (req1, _) = req.replace_payload(crate::Payload::H1(pl));
                                     req = req1;
                                     *this.payload = Some(ps);
+
+                                    // start a new data rate period for this body
+                                    *this.body_read = 0;
+                                    *this.slow_read = SlowRead::None;
                                 }
 
                                 // Request has no payload.
                                 MessageType::None => {}
                             }
 
+                            // allow services to take over the connection after switching protocols
+                            if req.upgrade() {
+                                let connect = req.head().method == Method::CONNECT;
+                                req.extensions_mut().insert(Upgrade::new(connect));
+                                this.flags.insert(Flags::UPGRADE);
+                            }
+
                             // handle request early when no future in InnerDispatcher state.
                             if this.state.is_empty() {
                                 self.as_mut().handle_request(req, cx)?;
@@ -628,6 +941,7 @@ where
                         }
                         Message::Chunk(Some(chunk)) => {
                             if let Some(ref mut payload) = this.payload {
+                                *this.body_read += chunk.len() as u64;
                                 payload.feed_data(chunk);
                             } else {
                                 error!("Internal server error: unexpected payload chunk");
@@ -773,6 +1087,139 @@ where
         Ok(())
     }
 
+    /// Enforce request head read deadline and minimum request body data rate.
+    fn poll_slow_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Result<(), DispatchError> {
+        let mut this = self.as_mut().project();
+        let config = this.codec.config().clone();
+
+        let reading = if this
+            .flags
+            .intersects(Flags::READ_DISCONNECT | Flags::SHUTDOWN)
+            || this.h2c.is_some()
+        {
+            SlowRead::None
+        } else if let Some(ref payload) = this.payload {
+            // client is not to blame while it waits for `100 Continue` or while the service
+            // does not consume the body
+            if config.h1_min_body_rate().is_none()
+                || matches!(*this.state, State::ExpectCall(_))
+                || payload.need_read(cx) != PayloadStatus::Read
+            {
+                SlowRead::None
+            } else {
+                SlowRead::Body
+            }
+        } else if config.h1_header_read_timeout().is_some()
+            && !this.read_buf.is_empty()
+            && this.messages.len() < MAX_PIPELINED_MESSAGES
+        {
+            // remaining bytes are an incomplete request head
+            SlowRead::Head
+        } else {
+            SlowRead::None
+        };
+
+        if reading != *this.slow_read {
+            *this.slow_read = reading;
+
+            let timeout = match reading {
+                SlowRead::None => None,
+                SlowRead::Head => config.h1_header_read_timeout(),
+                SlowRead::Body => config.h1_min_body_rate().map(|rate| rate.period),
+            };
+
+            this.slow_timer
+                .set(timeout.map(|timeout| sleep_until(Instant::now() + timeout)));
+        }
+
+        match this.slow_timer.as_mut().as_pin_mut() {
+            Some(mut timer) => {
+                if timer.as_mut().poll(cx).is_pending() {
+                    return Ok(());
+                }
+
+                // enough body data was received in this period; start the next one
+                if let (SlowRead::Body, Some(rate)) = (reading, config.h1_min_body_rate()) {
+                    if *this.body_read >= rate.bytes {
+                        *this.body_read = 0;
+                        timer.as_mut().reset(Instant::now() + rate.period);
+                        let _ = timer.poll(cx);
+                        return Ok(());
+                    }
+                }
+            }
+            None => return Ok(()),
+        }
+
+        *this.slow_read = SlowRead::None;
+        this.slow_timer.set(None);
+        this.flags.insert(Flags::READ_DISCONNECT);
+        *this.error = Some(DispatchError::SlowRequestTimeout);
+
+        match reading {
+            SlowRead::Head => {
+                trace!("Request head read timeout");
+                this.messages
+                    .push_back(DispatcherMessage::Error(Response::with_body(
+                        StatusCode::REQUEST_TIMEOUT,
+                        (),
+                    )));
+            }
+
+            SlowRead::Body => {
+                trace!("Request body data rate too low");
+
+                if let Some(mut payload) = this.payload.take() {
+                    payload.set_error(PayloadError::Timeout);
+                }
+
+                // respond in place of service that is still waiting for the body
+                if matches!(*this.state, State::ServiceCall(_)) {
+                    this.state.set(State::None);
+
+                    let mut res = Response::with_body(StatusCode::REQUEST_TIMEOUT, ());
+                    res.head_mut().set_connection_type(ConnectionType::Close);
+                    self.send_error_response(res, BoxBody::new(()))?;
+                }
+            }
+
+            SlowRead::None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Stop keep-alive handling once the server begins a graceful shutdown.
+    fn poll_shutdown_signal(self: Pin<&mut Self>, cx: &mut Context<'_>) {
+        let this = self.project();
+
+        if let Some(listener) = this.shutdown.as_ref() {
+            if listener.poll_triggered(cx).is_ready() {
+                trace!("Server is shutting down, draining connection");
+                this.flags.insert(Flags::DRAINING);
+                this.flags.remove(Flags::KEEPALIVE);
+                *this.shutdown = None;
+            }
+        }
+    }
+
+    /// Stop keep-alive handling once the connection has reached its maximum lifetime.
+    fn poll_lifetime(self: Pin<&mut Self>, cx: &mut Context<'_>) {
+        let mut this = self.project();
+
+        if let Some(timer) = this.lifetime_timer.as_mut().as_pin_mut() {
+            if timer.poll(cx).is_ready() {
+                trace!("Connection lifetime reached, draining connection");
+                this.flags.insert(Flags::DRAINING);
+                this.flags.remove(Flags::KEEPALIVE);
+                this.lifetime_timer.set(None);
+            }
+        }
+    }
+
     /// Returns true when io stream can be disconnected after write to it.
     ///
     /// It covers these conditions:
@@ -845,6 +1292,11 @@ where
                     if n == 0 {
                         return Ok(true);
                     }
+
+                    if let Some(stats) = this.stats {
+                        stats.add_read(n);
+                    }
+
                     read_some = true;
                 }
                 Poll::Pending => return Ok(false),
@@ -859,6 +1311,54 @@ where
         }
     }
 
+    /// Answer h2c upgrade request with `101 Switching Protocols` and flush write buffer before
+    /// the connection is handed over to the HTTP/2 dispatcher.
+    fn poll_h2c_switch(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), DispatchError>> {
+        let this = self.as_mut().project();
+
+        *this.h2c = match this.h2c.take() {
+            Some(H2cSwitch::Upgrade(req)) => {
+                this.write_buf.extend_from_slice(
+                    b"HTTP/1.1 101 Switching Protocols\r\nconnection: upgrade\r\nupgrade: h2c\r\n\r\n",
+                );
+                Some(H2cSwitch::Switched(req))
+            }
+            h2c => h2c,
+        };
+
+        ready!(self.poll_flush(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Flush switching response and hand connection over to the service that asked for it.
+    fn poll_tunnel_switch(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), DispatchError>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+
+        let this = self.project();
+
+        let pending = match this.tunnel.take() {
+            Some(Tunnel::Switching(pending)) => pending,
+            _ => unreachable!("connection is not switching protocols"),
+        };
+
+        // remaining data belongs to the new protocol
+        if let Some(mut payload) = this.payload.take() {
+            payload.feed_eof();
+        }
+        let read_buf = this.read_buf.split();
+
+        trace!("Handing connection over after switching protocols");
+        pending.complete(Upgraded::new(this.io.take().unwrap(), read_buf));
+
+        Poll::Ready(Ok(()))
+    }
+
     /// call upgrade service with request.
     fn upgrade(self: Pin<&mut Self>, req: Request) -> U::Future {
         let this = self.project();
@@ -875,7 +1375,7 @@ where
 
 impl<T, S, B, X, U> Future for Dispatcher<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>>,
@@ -902,6 +1402,8 @@ where
 
         match this.inner.project() {
             DispatcherStateProj::Normal(mut inner) => {
+                inner.as_mut().poll_shutdown_signal(cx);
+                inner.as_mut().poll_lifetime(cx);
                 inner.as_mut().poll_keepalive(cx)?;
 
                 if inner.flags.contains(Flags::SHUTDOWN) {
@@ -919,6 +1421,7 @@ where
                     let should_disconnect = inner.as_mut().read_available(cx)?;
 
                     inner.as_mut().poll_request(cx)?;
+                    inner.as_mut().poll_slow_read(cx)?;
 
                     // io stream should to be closed.
                     if should_disconnect {
@@ -946,6 +1449,13 @@ where
                             }
                         };
 
+                        // write informational responses sent while service call is pending
+                        {
+                            let inner = inner.as_mut().project();
+                            inner.informational.register(cx.waker());
+                            write_informational(inner.informational, inner.write_buf);
+                        }
+
                         // we didn't get WouldBlock from write operation,
                         // so data get written to kernel completely (macOS)
                         // and we have to write again otherwise response can get stuck
@@ -962,6 +1472,21 @@ where
                         return Poll::Ready(Ok(()));
                     }
 
+                    // switching response is written; hand connection over
+                    if matches!(inner.tunnel, Some(Tunnel::Switching(_)))
+                        && inner.state.is_empty()
+                    {
+                        return inner.poll_tunnel_switch(cx);
+                    }
+
+                    // all prior requests are answered; hand connection over to HTTP/2
+                    if inner.h2c.is_some()
+                        && inner.state.is_empty()
+                        && inner.messages.is_empty()
+                    {
+                        return inner.poll_h2c_switch(cx);
+                    }
+
                     let is_empty = inner.state.is_empty();
 
                     let inner_p = inner.as_mut().project();
@@ -985,6 +1510,13 @@ where
                         // disconnect if shutdown
                         else if inner_p.flags.contains(Flags::SHUTDOWN) {
                             self.poll(cx)
+                        }
+                        // disconnect idle connection if server is shutting down
+                        else if inner_p.flags.contains(Flags::DRAINING)
+                            && inner_p.read_buf.is_empty()
+                        {
+                            inner_p.flags.insert(Flags::SHUTDOWN);
+                            self.poll(cx)
                         } else {
                             Poll::Pending
                         }
@@ -1001,6 +1533,41 @@ where
     }
 }
 
+/// Returns plaintext TCP stream of the connection, if that is what it is served over.
+#[cfg(target_os = "linux")]
+fn tcp_stream<T: 'static>(io: &Option<T>) -> Option<&actix_rt::net::TcpStream> {
+    io.as_ref()
+        .and_then(|io| (io as &dyn std::any::Any).downcast_ref())
+}
+
+/// Write queued informational responses to buffer.
+fn write_informational(queue: &InformationalQueue, buf: &mut BytesMut) {
+    while let Some(Informational {
+        status, headers, ..
+    }) = queue.pop()
+    {
+        let reason = match status.as_u16() {
+            103 => "Early Hints",
+            _ => status.canonical_reason().unwrap_or(""),
+        };
+
+        buf.extend_from_slice(b"HTTP/1.1 ");
+        buf.extend_from_slice(status.as_str().as_bytes());
+        buf.extend_from_slice(b" ");
+        buf.extend_from_slice(reason.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+
+        for (name, value) in headers.iter() {
+            buf.extend_from_slice(name.as_str().as_bytes());
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+
+        buf.extend_from_slice(b"\r\n");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str;
@@ -1012,10 +1579,11 @@ mod tests {
 
     use super::*;
     use crate::{
+        config::ConnectionConfig,
         error::Error,
         h1::{ExpectHandler, UpgradeHandler},
         test::{TestBuffer, TestSeqBuffer},
-        HttpMessage, KeepAlive, Method,
+        HttpMessage, KeepAlive, Method, ShutdownSignal,
     };
 
     fn find_slice(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
@@ -1393,4 +1961,99 @@ mod tests {
         })
         .await;
     }
+
+    #[actix_rt::test]
+    async fn test_graceful_shutdown() {
+        let signal = ShutdownSignal::new();
+
+        lazy(|cx| {
+            let mut buf = TestSeqBuffer::empty();
+            let cfg = ServiceConfig::with_connection_config(
+                KeepAlive::Timeout(5),
+                0,
+                0,
+                false,
+                None,
+                ConnectionConfig {
+                    shutdown: Some(signal.clone()),
+                    ..Default::default()
+                },
+            );
+
+            let services = HttpFlow::new(echo_path_service(), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf.clone(),
+                services,
+                cfg,
+                None,
+                OnConnectData::default(),
+            );
+
+            actix_rt::pin!(h1);
+
+            buf.extend_read_buf("GET /abcd HTTP/1.1\r\n\r\n");
+            assert!(h1.as_mut().poll(cx).is_pending());
+            assert!(find_slice(&buf.write_buf(), b"connection: close", 0).is_none());
+
+            // in-flight request is answered and the connection is closed afterwards
+            signal.trigger();
+            buf.extend_read_buf("GET /def HTTP/1.1\r\n\r\n");
+
+            match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("connection should be closed after draining"),
+                Poll::Ready(res) => assert!(res.is_ok()),
+            }
+
+            let mut res = buf.write_buf()[..].to_owned();
+            stabilize_date_header(&mut res);
+
+            let exp = b"\
+                HTTP/1.1 200 OK\r\n\
+                content-length: 5\r\n\
+                date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+                /abcd\
+                HTTP/1.1 200 OK\r\n\
+                content-length: 4\r\n\
+                connection: close\r\n\
+                date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+                /def\
+                ";
+
+            assert_eq!(res, exp.to_vec());
+        })
+        .await;
+
+        lazy(|cx| {
+            // idle keep-alive connections are closed right away
+            let buf = TestSeqBuffer::empty();
+            let cfg = ServiceConfig::with_connection_config(
+                KeepAlive::Timeout(5),
+                0,
+                0,
+                false,
+                None,
+                ConnectionConfig {
+                    shutdown: Some(signal.clone()),
+                    ..Default::default()
+                },
+            );
+
+            let services = HttpFlow::new(ok_service(), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf.clone(),
+                services,
+                cfg,
+                None,
+                OnConnectData::default(),
+            );
+
+            actix_rt::pin!(h1);
+
+            assert!(h1.as_mut().poll(cx).is_ready());
+            assert!(buf.write_buf().is_empty());
+        })
+        .await;
+    }
 }
@@ -207,6 +207,7 @@ impl Inner {
     #[inline]
     fn set_error(&mut self, err: PayloadError) {
         self.err = Some(err);
+        self.wake();
     }
 
     #[inline]
@@ -273,4 +274,16 @@ mod tests {
             poll_fn(|cx| payload.readany(cx)).await.unwrap().unwrap()
         );
     }
+
+    #[actix_rt::test]
+    async fn test_set_error_wakes_reader() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        let reader = actix_rt::spawn(async move { poll_fn(|cx| payload.readany(cx)).await });
+        actix_rt::task::yield_now().await;
+
+        sender.set_error(PayloadError::Timeout);
+        let res = reader.await.unwrap();
+        assert!(matches!(res, Some(Err(PayloadError::Timeout))));
+    }
 }
@@ -19,7 +19,7 @@ use crate::{
     config::ServiceConfig,
     error::DispatchError,
     service::HttpServiceHandler,
-    ConnectCallback, OnConnectData, Request, Response,
+    ConnectCallback, OnConnectData, Protocol, Request, Response,
 };
 
 use super::{codec::Codec, dispatcher::Dispatcher, ExpectHandler, UpgradeHandler};
@@ -143,6 +143,13 @@ mod openssl {
             Error = TlsError<SslError, DispatchError>,
             InitError = (),
         > {
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::openssl_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(acceptor)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -152,7 +159,7 @@ mod openssl {
                     let peer_addr = io.get_ref().peer_addr().ok();
                     (io, peer_addr)
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -205,6 +212,13 @@ mod rustls {
             Error = TlsError<io::Error, DispatchError>,
             InitError = (),
         > {
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::rustls_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(config)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -214,7 +228,7 @@ mod rustls {
                     let peer_addr = io.get_ref().0.peer_addr().ok();
                     (io, peer_addr)
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -337,7 +351,7 @@ pub type H1ServiceHandler<T, S, B, X, U> = HttpServiceHandler<T, S, B, X, U>;
 
 impl<T, S, B, X, U> Service<(T, Option<net::SocketAddr>)> for HttpServiceHandler<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>>,
@@ -363,7 +377,11 @@ where
     }
 
     fn call(&self, (io, addr): (T, Option<net::SocketAddr>)) -> Self::Future {
-        let conn_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref());
+        let conn_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref()).with_stats(
+            &self.cfg,
+            Protocol::Http1,
+            addr,
+        );
         Dispatcher::new(io, self.flow.clone(), self.cfg.clone(), addr, conn_data)
     }
 }
@@ -25,22 +25,31 @@ use pin_project_lite::pin_project;
 use crate::{
     body::{BodySize, BoxBody, MessageBody},
     config::ServiceConfig,
-    header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING},
+    header::{HeaderValue, ALT_SVC, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING},
+    informational::InformationalQueue,
     service::HttpFlow,
-    Extensions, OnConnectData, Payload, Request, Response, ResponseHead,
+    shutdown::ShutdownListener,
+    ConnectionStats, Extensions, HttpMessage as _, InformationalSender, Payload, Request,
+    Response, ResponseHead,
 };
 
+use super::{informational::InformationalIo, ServerConnection};
+
 const CHUNK_SIZE: usize = 16_384;
 
 pin_project! {
     /// Dispatcher for HTTP/2 protocol.
     pub struct Dispatcher<T, S, B, X, U> {
         flow: Rc<HttpFlow<S, X, U>>,
-        connection: Connection<T, Bytes>,
+        connection: Connection<InformationalIo<T>, Bytes>,
+        informational: Rc<InformationalQueue>,
         conn_data: Option<Rc<Extensions>>,
         config: ServiceConfig,
         peer_addr: Option<net::SocketAddr>,
         ping_pong: Option<H2PingPong>,
+        shutdown: Option<ShutdownListener>,
+        lifetime_timer: Option<Pin<Box<Sleep>>>,
+        requests: usize,
         _phantom: PhantomData<B>
     }
 }
@@ -50,13 +59,18 @@ where
     T: AsyncRead + AsyncWrite + Unpin,
 {
     pub(crate) fn new(
-        mut conn: Connection<T, Bytes>,
+        conn: ServerConnection<T>,
         flow: Rc<HttpFlow<S, X, U>>,
         config: ServiceConfig,
         peer_addr: Option<net::SocketAddr>,
-        conn_data: OnConnectData,
+        conn_data: Option<Rc<Extensions>>,
         timer: Option<Pin<Box<Sleep>>>,
     ) -> Self {
+        let ServerConnection {
+            conn: mut connection,
+            informational,
+        } = conn;
+
         let ping_pong = config.keep_alive().map(|dur| H2PingPong {
             timer: timer
                 .map(|mut timer| {
@@ -66,16 +80,23 @@ where
                 })
                 .unwrap_or_else(|| Box::pin(sleep(dur))),
             on_flight: false,
-            ping_pong: conn.ping_pong().unwrap(),
+            ping_pong: connection.ping_pong().unwrap(),
         });
 
+        let shutdown = config.shutdown_signal().map(|signal| signal.listener());
+        let lifetime_timer = config.conn_lifetime_timer().map(Box::pin);
+
         Self {
             flow,
             config,
             peer_addr,
-            connection: conn,
-            conn_data: conn_data.0.map(Rc::new),
+            connection,
+            informational,
+            conn_data,
             ping_pong,
+            shutdown,
+            lifetime_timer,
+            requests: 0,
             _phantom: PhantomData,
         }
     }
@@ -104,10 +125,37 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        // wake connection when informational responses are sent from request tasks
+        this.informational.register(cx.waker());
+
+        if let Some(listener) = this.shutdown.as_ref() {
+            if listener.poll_triggered(cx).is_ready() {
+                // send GOAWAY and keep driving the connection until in-flight streams complete
+                trace!("Server is shutting down, sending HTTP/2 GOAWAY");
+                this.connection.graceful_shutdown();
+                this.shutdown = None;
+            }
+        }
+
+        if let Some(timer) = this.lifetime_timer.as_mut() {
+            if timer.as_mut().poll(cx).is_ready() {
+                trace!("Connection lifetime reached, sending HTTP/2 GOAWAY");
+                this.connection.graceful_shutdown();
+                this.lifetime_timer = None;
+            }
+        }
+
         loop {
             match Pin::new(&mut this.connection).poll_accept(cx)? {
                 Poll::Ready(Some((req, tx))) => {
-                    let (parts, body) = req.into_parts();
+                    this.requests += 1;
+
+                    if this.config.max_requests_per_conn() == Some(this.requests) {
+                        trace!("Per-connection request limit reached, sending HTTP/2 GOAWAY");
+                        this.connection.graceful_shutdown();
+                    }
+
+                    let (mut parts, body) = req.into_parts();
                     let pl = crate::h2::Payload::new(body);
                     let pl = Payload::H2(pl);
                     let mut req = Request::with_payload(pl);
@@ -119,15 +167,37 @@ where
                     head.headers = parts.headers.into();
                     head.peer_addr = this.peer_addr;
 
+                    // extended CONNECT request (RFC 8441)
+                    if let Some(protocol) = parts.extensions.remove::<h2::ext::Protocol>() {
+                        head.set_protocol(Some(protocol.as_str()));
+                    }
+
                     req.conn_data = this.conn_data.as_ref().map(Rc::clone);
 
+                    if let Some(stats) = this
+                        .conn_data
+                        .as_deref()
+                        .and_then(Extensions::get::<ConnectionStats>)
+                    {
+                        stats.add_request();
+                    }
+
+                    let informational = InformationalSender::new(
+                        Rc::clone(&this.informational),
+                        tx.stream_id().as_u32(),
+                    );
+                    req.extensions_mut().insert(informational.clone());
+
                     let fut = this.flow.service.call(req);
                     let config = this.config.clone();
 
                     // multiplex request handling with spawn task
                     actix_rt::spawn(async move {
                         // resolve service call and send response.
-                        let res = match fut.await {
+                        let res = fut.await;
+                        informational.close();
+
+                        let res = match res {
                             Ok(res) => handle_response(res.into(), tx, config).await,
                             Err(err) => {
                                 let res: Response<BoxBody> = err.into();
@@ -205,9 +275,16 @@ where
 
     // prepare response.
     let mut size = body.size();
-    let res = prepare_response(config, res.head(), &mut size);
+    let mut res = prepare_response(&config, res.head(), &mut size);
     let eof = size.is_eof();
 
+    // advertise alternative services
+    if let Some(alt_svc) = config.alt_svc() {
+        if !res.headers().contains_key(ALT_SVC) {
+            res.headers_mut().insert(ALT_SVC, alt_svc.clone());
+        }
+    }
+
     // send response head and return on eof.
     let mut stream = tx
         .send_response(res, eof)
@@ -261,8 +338,8 @@ where
     Ok(())
 }
 
-fn prepare_response(
-    config: ServiceConfig,
+pub(crate) fn prepare_response(
+    config: &ServiceConfig,
     head: &ResponseHead,
     size: &mut BodySize,
 ) -> http::Response<()> {
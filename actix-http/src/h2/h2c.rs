@@ -0,0 +1,456 @@
+//! HTTP/2 over cleartext TCP (h2c).
+//!
+//! Connections start out on the HTTP/1 dispatcher and are switched to HTTP/2 when either:
+//! - the client sends the HTTP/2 connection preface straight away ("prior knowledge"); or
+//! - the client sends an HTTP/1.1 request with `Upgrade: h2c` (RFC 7540 §3.2).
+//!
+//! In the upgrade case the request that carried the `Upgrade` header must be answered on HTTP/2
+//! stream 1. This is done by injecting a synthesized `HEADERS` frame for that request into the
+//! inbound byte stream, directly after the client's connection preface and first `SETTINGS`
+//! frame, so the HTTP/2 server sees it as an ordinary request.
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::ready;
+
+use crate::{
+    header::{self, HeaderMap, HeaderName},
+    ConnectionStats, Request, Version,
+};
+
+/// HTTP/2 client connection preface.
+pub(crate) const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+pub(super) const FRAME_HEADER_LEN: usize = 9;
+pub(super) const FRAME_TYPE_HEADERS: u8 = 0x1;
+pub(super) const FRAME_TYPE_SETTINGS: u8 = 0x4;
+pub(super) const FRAME_TYPE_CONTINUATION: u8 = 0x9;
+const FLAG_END_STREAM: u8 = 0x1;
+pub(super) const FLAG_END_HEADERS: u8 = 0x4;
+pub(super) const DEFAULT_MAX_FRAME_SIZE: usize = 16_384;
+
+/// Result of checking a read buffer for the HTTP/2 connection preface.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Preface {
+    /// Buffer starts with the full preface.
+    Complete,
+    /// Buffer is a strict prefix of the preface; more data is needed.
+    Partial,
+    /// Buffer does not start with the preface.
+    Missing,
+}
+
+pub(crate) fn check_preface(buf: &[u8]) -> Preface {
+    if buf.starts_with(PREFACE) {
+        Preface::Complete
+    } else if !buf.is_empty() && PREFACE.starts_with(buf) {
+        Preface::Partial
+    } else {
+        Preface::Missing
+    }
+}
+
+/// Returns true if request asks for a switch to h2c and may be upgraded.
+///
+/// The request must be HTTP/1.1, list `h2c` in its `Upgrade` header and carry exactly one valid
+/// `HTTP2-Settings` header. Requests with a body are never upgraded; this is checked by the
+/// caller.
+///
+/// The `HTTP2-Settings` payload is only validated and is otherwise ignored: the HTTP/2 server
+/// only applies settings from the `SETTINGS` frame that the client must send after the connection
+/// preface. Parameters that are only sent in the header keep their default values.
+pub(crate) fn is_upgrade_request(req: &Request) -> bool {
+    let head = req.head();
+
+    if head.version != Version::HTTP_11 || !has_token(&head.headers, &header::UPGRADE, "h2c") {
+        return false;
+    }
+
+    let mut settings = head.headers.get_all("http2-settings");
+
+    match (settings.next(), settings.next()) {
+        (Some(val), None) => base64::decode_config(val.as_bytes(), base64::URL_SAFE_NO_PAD)
+            // settings payload is a sequence of 6 byte id-value pairs
+            .map_or(false, |payload| payload.len() % 6 == 0),
+        _ => false,
+    }
+}
+
+fn has_token(headers: &HeaderMap, name: &HeaderName, token: &str) -> bool {
+    headers.get_all(name).any(|val| {
+        val.to_str().map_or(false, |val| {
+            val.split(',').any(|t| t.trim().eq_ignore_ascii_case(token))
+        })
+    })
+}
+
+/// Encodes the upgraded request as a `HEADERS` frame (plus `CONTINUATION` frames if needed) on
+/// stream 1 that also ends the stream.
+///
+/// Header fields are encoded as HPACK literals without indexing so the connection's HPACK state
+/// is left untouched.
+pub(crate) fn encode_upgrade_request(req: &Request, max_frame_size: Option<u32>) -> Bytes {
+    let head = req.head();
+
+    let mut block = BytesMut::new();
+
+    encode_field(&mut block, b":method", head.method.as_str().as_bytes());
+    encode_field(&mut block, b":scheme", b"http");
+
+    let authority = head
+        .headers
+        .get(header::HOST)
+        .map(|host| host.as_bytes())
+        .or_else(|| head.uri.authority().map(|auth| auth.as_str().as_bytes()));
+
+    if let Some(authority) = authority {
+        encode_field(&mut block, b":authority", authority);
+    }
+
+    let path = head.uri.path_and_query().map_or("/", |path| path.as_str());
+
+    encode_field(&mut block, b":path", path.as_bytes());
+
+    for (name, value) in head.headers.iter() {
+        match *name {
+            // connection-specific headers are not allowed in HTTP/2
+            header::CONNECTION
+            | header::UPGRADE
+            | header::HOST
+            | header::TE
+            | header::TRANSFER_ENCODING => continue,
+            _ if name == "http2-settings"
+                || name == "keep-alive"
+                || name == "proxy-connection" =>
+            {
+                continue
+            }
+            _ => encode_field(&mut block, name.as_str().as_bytes(), value.as_bytes()),
+        }
+    }
+
+    let max_frame_size = max_frame_size.map_or(DEFAULT_MAX_FRAME_SIZE, |size| size as usize);
+
+    let mut buf = BytesMut::new();
+    encode_header_block(&mut buf, block.freeze(), 1, true, max_frame_size);
+    buf.freeze()
+}
+
+/// Writes header block as a `HEADERS` frame followed by `CONTINUATION` frames as needed.
+pub(super) fn encode_header_block(
+    buf: &mut BytesMut,
+    mut block: Bytes,
+    stream_id: u32,
+    end_stream: bool,
+    max_frame_size: usize,
+) {
+    let frames = cmp::max(1, (block.len() + max_frame_size - 1) / max_frame_size);
+    buf.reserve(block.len() + frames * FRAME_HEADER_LEN);
+
+    let mut kind = FRAME_TYPE_HEADERS;
+    let mut flags = if end_stream { FLAG_END_STREAM } else { 0 };
+
+    loop {
+        let chunk = block.split_to(cmp::min(block.len(), max_frame_size));
+
+        if block.is_empty() {
+            flags |= FLAG_END_HEADERS;
+        }
+
+        buf.put_uint(chunk.len() as u64, 3);
+        buf.put_u8(kind);
+        buf.put_u8(flags);
+        buf.put_u32(stream_id);
+        buf.put_slice(&chunk);
+
+        if block.is_empty() {
+            break;
+        }
+
+        kind = FRAME_TYPE_CONTINUATION;
+        flags = 0;
+    }
+}
+
+/// Encodes a literal header field without indexing and with a new name (RFC 7541 §6.2.2).
+pub(super) fn encode_field(buf: &mut BytesMut, name: &[u8], value: &[u8]) {
+    buf.put_u8(0);
+    encode_str(buf, name);
+    encode_str(buf, value);
+}
+
+/// Encodes a dynamic table size update (RFC 7541 §6.3).
+pub(super) fn encode_size_update(buf: &mut BytesMut, size: usize) {
+    let start = buf.len();
+    encode_int(buf, size, 5);
+    buf[start] |= 0x20;
+}
+
+/// Encodes a string literal without Huffman coding (RFC 7541 §5.2).
+fn encode_str(buf: &mut BytesMut, val: &[u8]) {
+    encode_int(buf, val.len(), 7);
+    buf.put_slice(val);
+}
+
+/// Encodes an integer with an N-bit prefix (RFC 7541 §5.1).
+fn encode_int(buf: &mut BytesMut, mut val: usize, prefix_bits: u8) {
+    let max = (1 << prefix_bits) - 1;
+
+    if val < max {
+        buf.put_u8(val as u8);
+        return;
+    }
+
+    buf.put_u8(max as u8);
+    val -= max;
+
+    while val >= 128 {
+        buf.put_u8((val % 128) as u8 | 0x80);
+        val /= 128;
+    }
+
+    buf.put_u8(val as u8);
+}
+
+/// I/O wrapper used for connections switched from HTTP/1 to h2c.
+///
+/// Replays bytes already read by the HTTP/1 dispatcher and, for upgraded connections, injects
+/// the encoded upgrade request after the client's first `SETTINGS` frame.
+pub(crate) struct H2cIo<T> {
+    io: T,
+    read_buf: BytesMut,
+    upgrade: Option<Bytes>,
+    stats: Option<ConnectionStats>,
+}
+
+impl<T> H2cIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    pub(crate) fn new(
+        io: T,
+        read_buf: BytesMut,
+        upgrade: Option<Bytes>,
+        stats: Option<ConnectionStats>,
+    ) -> Self {
+        Self {
+            io,
+            read_buf,
+            upgrade,
+            stats,
+        }
+    }
+
+    fn count_read(&self, n: usize) {
+        if let Some(ref stats) = self.stats {
+            stats.add_read(n);
+        }
+    }
+
+    fn count_written(&self, n: usize) {
+        if let Some(ref stats) = self.stats {
+            stats.add_written(n);
+        }
+    }
+
+    /// Buffers client preface and first frame then injects the upgrade request after them.
+    fn poll_inject(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let head_len = PREFACE.len() + FRAME_HEADER_LEN;
+
+        loop {
+            if self.read_buf.len() >= PREFACE.len() && !self.read_buf.starts_with(PREFACE) {
+                // not an HTTP/2 client; let the h2 handshake reject it
+                self.upgrade = None;
+                return Poll::Ready(Ok(()));
+            }
+
+            if self.read_buf.len() >= head_len {
+                let header = &self.read_buf[PREFACE.len()..head_len];
+                let len =
+                    (header[0] as usize) << 16 | (header[1] as usize) << 8 | header[2] as usize;
+
+                if header[3] != FRAME_TYPE_SETTINGS || len > DEFAULT_MAX_FRAME_SIZE {
+                    // protocol error; let the h2 connection deal with it
+                    self.upgrade = None;
+                    return Poll::Ready(Ok(()));
+                }
+
+                if self.read_buf.len() >= head_len + len {
+                    let rest = self.read_buf.split_off(head_len + len);
+                    self.read_buf
+                        .extend_from_slice(&self.upgrade.take().unwrap());
+                    self.read_buf.extend_from_slice(&rest);
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            self.read_buf.reserve(DEFAULT_MAX_FRAME_SIZE);
+
+            let n = ready!(actix_codec::poll_read_buf(
+                Pin::new(&mut self.io),
+                cx,
+                &mut self.read_buf
+            ))?;
+
+            self.count_read(n);
+
+            if n == 0 {
+                self.upgrade = None;
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl<T> AsyncRead for H2cIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.upgrade.is_some() {
+            ready!(this.poll_inject(cx))?;
+        }
+
+        if !this.read_buf.is_empty() {
+            let n = cmp::min(buf.remaining(), this.read_buf.len());
+            buf.put_slice(&this.read_buf.split_to(n));
+            return Poll::Ready(Ok(()));
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.io).poll_read(cx, buf))?;
+        this.count_read(buf.filled().len() - filled);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for H2cIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = ready!(Pin::new(&mut this.io).poll_write(cx, buf))?;
+        this.count_written(n);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = ready!(Pin::new(&mut this.io).poll_write_vectored(cx, bufs))?;
+        this.count_written(n);
+        Poll::Ready(Ok(n))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn preface_detection() {
+        assert_eq!(check_preface(PREFACE), Preface::Complete);
+        assert_eq!(
+            check_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0"),
+            Preface::Complete
+        );
+        assert_eq!(check_preface(b"PRI * HT"), Preface::Partial);
+        assert_eq!(check_preface(b""), Preface::Missing);
+        assert_eq!(check_preface(b"GET / HTTP/1.1\r\n"), Preface::Missing);
+    }
+
+    #[test]
+    fn upgrade_request_detection() {
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "h2c"))
+            .insert_header(("http2-settings", "AAMAAABkAAQAAP__"))
+            .finish();
+        assert!(is_upgrade_request(&req));
+
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header(("http2-settings", "AAMAAABkAAQAAP__"))
+            .finish();
+        assert!(!is_upgrade_request(&req));
+
+        // missing settings
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "h2c"))
+            .finish();
+        assert!(!is_upgrade_request(&req));
+
+        // invalid settings payload
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "h2c"))
+            .insert_header(("http2-settings", "AAMA"))
+            .finish();
+        assert!(!is_upgrade_request(&req));
+
+        let req = TestRequest::default()
+            .version(Version::HTTP_10)
+            .insert_header((header::UPGRADE, "h2c"))
+            .insert_header(("http2-settings", "AAMAAABkAAQAAP__"))
+            .finish();
+        assert!(!is_upgrade_request(&req));
+    }
+
+    #[test]
+    fn upgrade_request_frames() {
+        let req = TestRequest::with_uri("/test?q=1")
+            .insert_header((header::HOST, "example.com"))
+            .insert_header((header::UPGRADE, "h2c"))
+            .insert_header(("x-test", "a".repeat(200)))
+            .finish();
+
+        let frame = encode_upgrade_request(&req, None);
+        assert_eq!(frame[3], FRAME_TYPE_HEADERS);
+        assert_eq!(frame[4], FLAG_END_STREAM | FLAG_END_HEADERS);
+        assert_eq!(&frame[5..9], &[0, 0, 0, 1]);
+
+        let len = (frame[0] as usize) << 16 | (frame[1] as usize) << 8 | frame[2] as usize;
+        assert_eq!(frame.len(), FRAME_HEADER_LEN + len);
+
+        // long header value uses multi-byte length prefix
+        assert!(frame.windows(3).any(|w| w == [0x7f, 200 - 127, b'a']));
+
+        // split over continuation frames
+        let frames = encode_upgrade_request(&req, Some(64));
+        assert_eq!(frames[3], FRAME_TYPE_HEADERS);
+        assert_eq!(frames[4], FLAG_END_STREAM);
+        assert_eq!(frames[FRAME_HEADER_LEN + 64 + 3], FRAME_TYPE_CONTINUATION);
+    }
+}
@@ -0,0 +1,518 @@
+//! Informational (1xx) responses over HTTP/2.
+//!
+//! The `h2` crate only allows a single response per stream. Informational responses are instead
+//! encoded as separate `HEADERS` frames and injected into the outbound byte stream in between
+//! the frames written by `h2`:
+//! - frames are only injected in between header blocks, never in between a `HEADERS` or
+//!   `PUSH_PROMISE` frame and its `CONTINUATION` frames (RFC 7540 §6.10);
+//! - header fields are encoded as HPACK literals without indexing, so the dynamic table that
+//!   `h2`'s encoder shares with the client is left untouched;
+//! - changes of the client's `SETTINGS_HEADER_TABLE_SIZE` are read from the inbound byte stream
+//!   and, until `h2` writes its next header block, signalled at the start of injected blocks,
+//!   which then are the first header blocks following the change (RFC 7541 §4.2).
+
+use std::{
+    cmp,
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+use bytes::{Buf as _, BytesMut};
+use futures_core::ready;
+
+use crate::{
+    header,
+    informational::{Informational, InformationalQueue},
+    ConnectionStats,
+};
+
+use super::h2c::{
+    encode_field, encode_header_block, encode_size_update, DEFAULT_MAX_FRAME_SIZE,
+    FLAG_END_HEADERS, FRAME_HEADER_LEN, FRAME_TYPE_CONTINUATION, FRAME_TYPE_HEADERS,
+    FRAME_TYPE_SETTINGS, PREFACE,
+};
+
+const FRAME_TYPE_PUSH_PROMISE: u8 = 0x5;
+const FLAG_ACK: u8 = 0x1;
+const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+const SETTING_LEN: usize = 6;
+
+/// Type and flags of an HTTP/2 frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameHead {
+    kind: u8,
+    flags: u8,
+}
+
+/// Tracks frame boundaries of an HTTP/2 byte stream.
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameTracker {
+    header: [u8; FRAME_HEADER_LEN],
+    header_len: usize,
+    remaining: usize,
+    /// A header block was started without `END_HEADERS`; `CONTINUATION` frames follow.
+    in_header_block: bool,
+}
+
+impl FrameTracker {
+    /// Returns true if no frame or header block is partially written.
+    fn at_boundary(&self) -> bool {
+        self.at_frame_end() && !self.in_header_block
+    }
+
+    fn at_frame_end(&self) -> bool {
+        self.header_len == 0 && self.remaining == 0
+    }
+
+    /// Consumes bytes up to the end of the current frame header or payload and returns the
+    /// number consumed, along with the head of the frame once its header is complete.
+    fn consume(&mut self, buf: &[u8]) -> (usize, Option<FrameHead>) {
+        if self.remaining > 0 {
+            let n = cmp::min(self.remaining, buf.len());
+            self.remaining -= n;
+            return (n, None);
+        }
+
+        let n = cmp::min(FRAME_HEADER_LEN - self.header_len, buf.len());
+        self.header[self.header_len..self.header_len + n].copy_from_slice(&buf[..n]);
+        self.header_len += n;
+
+        if self.header_len < FRAME_HEADER_LEN {
+            return (n, None);
+        }
+
+        self.header_len = 0;
+        self.remaining = (self.header[0] as usize) << 16
+            | (self.header[1] as usize) << 8
+            | self.header[2] as usize;
+
+        let head = FrameHead {
+            kind: self.header[3],
+            flags: self.header[4],
+        };
+
+        if let FRAME_TYPE_HEADERS | FRAME_TYPE_PUSH_PROMISE | FRAME_TYPE_CONTINUATION =
+            head.kind
+        {
+            self.in_header_block = head.flags & FLAG_END_HEADERS == 0;
+        }
+
+        (n, Some(head))
+    }
+
+    /// Returns the number of bytes up to the end of the current frame.
+    fn frame_len(mut self, buf: &[u8]) -> usize {
+        let mut pos = 0;
+
+        loop {
+            pos += self.consume(&buf[pos..]).0;
+
+            if pos == buf.len() || self.at_frame_end() {
+                return pos;
+            }
+        }
+    }
+}
+
+/// Tracks the client's `SETTINGS_HEADER_TABLE_SIZE` changes that `h2` has applied to its HPACK
+/// encoder but not yet signalled in a header block.
+#[derive(Debug, Default)]
+struct TableSize {
+    /// Connection preface bytes still to be read.
+    preface: usize,
+    frames: FrameTracker,
+    /// Payload of the inbound `SETTINGS` frame being read.
+    settings: Option<BytesMut>,
+    /// Header table size of each inbound `SETTINGS` frame not yet acknowledged, in order.
+    unacked: VecDeque<Option<usize>>,
+    /// Smallest and last header table size applied since `h2` wrote its last header block.
+    pending: Option<(usize, usize)>,
+}
+
+impl TableSize {
+    fn new() -> Self {
+        Self {
+            preface: PREFACE.len(),
+            ..Self::default()
+        }
+    }
+
+    /// Reads `SETTINGS` frames from inbound bytes.
+    fn received(&mut self, mut buf: &[u8]) {
+        let n = cmp::min(self.preface, buf.len());
+        self.preface -= n;
+        buf = &buf[n..];
+
+        while !buf.is_empty() {
+            let (n, head) = self.frames.consume(buf);
+
+            if let Some(ref mut settings) = self.settings {
+                if head.is_none() {
+                    settings.extend_from_slice(&buf[..n]);
+                }
+            }
+
+            if let Some(head) = head {
+                if head.kind == FRAME_TYPE_SETTINGS && head.flags & FLAG_ACK == 0 {
+                    self.settings = Some(BytesMut::new());
+                }
+            }
+
+            if self.frames.at_frame_end() {
+                if let Some(settings) = self.settings.take() {
+                    self.unacked.push_back(header_table_size(&settings));
+                }
+            }
+
+            buf = &buf[n..];
+        }
+    }
+
+    /// Observes head of a frame written by `h2`.
+    fn sent(&mut self, head: FrameHead) {
+        match head.kind {
+            // `h2` applies settings to its encoder when acknowledging them
+            FRAME_TYPE_SETTINGS if head.flags & FLAG_ACK != 0 => {
+                if let Some(Some(size)) = self.unacked.pop_front() {
+                    self.pending = Some(match self.pending {
+                        Some((min, _)) => (cmp::min(min, size), size),
+                        None => (size, size),
+                    });
+                }
+            }
+
+            // header block carries the encoder's own size updates
+            FRAME_TYPE_HEADERS | FRAME_TYPE_PUSH_PROMISE => self.pending = None,
+
+            _ => {}
+        }
+    }
+
+    /// Writes dynamic table size updates that must start the next header block.
+    fn encode(&self, buf: &mut BytesMut) {
+        if let Some((min, last)) = self.pending {
+            if min < last {
+                encode_size_update(buf, min);
+            }
+
+            encode_size_update(buf, last);
+        }
+    }
+}
+
+/// Returns the last `SETTINGS_HEADER_TABLE_SIZE` value of a `SETTINGS` frame payload.
+fn header_table_size(mut payload: &[u8]) -> Option<usize> {
+    let mut size = None;
+
+    while payload.len() >= SETTING_LEN {
+        if payload.get_u16() == SETTINGS_HEADER_TABLE_SIZE {
+            size = Some(payload.get_u32() as usize);
+        } else {
+            payload.advance(4);
+        }
+    }
+
+    size
+}
+
+/// Encodes informational response as `HEADERS` frame(s) on its stream.
+fn encode_informational(buf: &mut BytesMut, item: Informational, table_size: &TableSize) {
+    let mut block = BytesMut::new();
+
+    table_size.encode(&mut block);
+    encode_field(&mut block, b":status", item.status.as_str().as_bytes());
+
+    for (name, value) in item.headers.iter() {
+        match *name {
+            // connection-specific headers are not allowed in HTTP/2
+            header::CONNECTION | header::TRANSFER_ENCODING | header::UPGRADE => continue,
+            _ => encode_field(&mut block, name.as_str().as_bytes(), value.as_bytes()),
+        }
+    }
+
+    encode_header_block(
+        buf,
+        block.freeze(),
+        item.stream_id,
+        false,
+        DEFAULT_MAX_FRAME_SIZE,
+    );
+}
+
+/// I/O wrapper that writes queued informational responses in between outbound HTTP/2 frames.
+pub(crate) struct InformationalIo<T> {
+    io: T,
+    queue: Rc<InformationalQueue>,
+    pending: BytesMut,
+    frames: FrameTracker,
+    table_size: TableSize,
+    stats: Option<ConnectionStats>,
+}
+
+impl<T> InformationalIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    pub(crate) fn new(
+        io: T,
+        queue: Rc<InformationalQueue>,
+        stats: Option<ConnectionStats>,
+    ) -> Self {
+        Self {
+            io,
+            queue,
+            pending: BytesMut::new(),
+            frames: FrameTracker::default(),
+            table_size: TableSize::new(),
+            stats,
+        }
+    }
+
+    /// Writes queued informational responses. Must only be called in between header blocks.
+    fn poll_inject(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while let Some(item) = self.queue.pop() {
+            encode_informational(&mut self.pending, item, &self.table_size);
+        }
+
+        while !self.pending.is_empty() {
+            let n = ready!(Pin::new(&mut self.io).poll_write(cx, &self.pending))?;
+
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write informational response to socket",
+                )));
+            }
+
+            self.pending.advance(n);
+
+            if let Some(ref stats) = self.stats {
+                stats.add_written(n);
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncRead for InformationalIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+
+        ready!(Pin::new(&mut this.io).poll_read(cx, buf))?;
+
+        let read = &buf.filled()[filled..];
+        this.table_size.received(read);
+
+        if let Some(ref stats) = this.stats {
+            stats.add_read(read.len());
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for InformationalIo<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.frames.at_boundary() {
+            ready!(this.poll_inject(cx))?;
+        }
+
+        // stop at end of current frame when informational responses are waiting
+        let len = if this.queue.is_empty() {
+            buf.len()
+        } else {
+            this.frames.frame_len(buf)
+        };
+
+        let n = ready!(Pin::new(&mut this.io).poll_write(cx, &buf[..len]))?;
+
+        if let Some(ref stats) = this.stats {
+            stats.add_written(n);
+        }
+
+        let mut written = &buf[..n];
+        while !written.is_empty() {
+            let (consumed, head) = this.frames.consume(written);
+
+            if let Some(head) = head {
+                this.table_size.sent(head);
+            }
+
+            written = &written[consumed..];
+        }
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.frames.at_boundary() {
+            ready!(this.poll_inject(cx))?;
+        }
+
+        Pin::new(&mut this.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{header::HeaderMap, StatusCode};
+
+    /// Consumes whole buffer, returning heads of frames it completes.
+    fn consume_all(frames: &mut FrameTracker, mut buf: &[u8]) -> Vec<FrameHead> {
+        let mut heads = Vec::new();
+
+        while !buf.is_empty() {
+            let (n, head) = frames.consume(buf);
+            heads.extend(head);
+            buf = &buf[n..];
+        }
+
+        heads
+    }
+
+    #[test]
+    fn frame_boundaries() {
+        let mut frames = FrameTracker::default();
+        assert!(frames.at_boundary());
+
+        // frame with 3 byte payload followed by empty frame
+        let buf = [
+            0, 0, 3, 0, 0, 0, 0, 0, 1, b'a', b'b', b'c', 0, 0, 0, 4, 1, 0, 0, 0, 0,
+        ];
+
+        // partial header
+        assert_eq!(frames.consume(&buf[..4]), (4, None));
+        assert!(!frames.at_boundary());
+
+        // rest of first frame stops at its end
+        assert_eq!(frames.frame_len(&buf[4..]), 8);
+        let heads = consume_all(&mut frames, &buf[4..12]);
+        assert_eq!(heads, vec![FrameHead { kind: 0, flags: 0 }]);
+        assert!(frames.at_boundary());
+
+        // empty frame
+        assert_eq!(frames.frame_len(&buf[12..]), 9);
+        consume_all(&mut frames, &buf[12..]);
+        assert!(frames.at_boundary());
+    }
+
+    #[test]
+    fn header_block_boundaries() {
+        let mut frames = FrameTracker::default();
+
+        // HEADERS without END_HEADERS, followed by CONTINUATION frames
+        consume_all(&mut frames, &[0, 0, 1, 0x1, 0, 0, 0, 0, 1, 0]);
+        assert!(!frames.at_boundary());
+
+        consume_all(&mut frames, &[0, 0, 1, 0x9, 0, 0, 0, 0, 1, 0]);
+        assert!(!frames.at_boundary());
+
+        consume_all(&mut frames, &[0, 0, 1, 0x9, 0x4, 0, 0, 0, 1, 0]);
+        assert!(frames.at_boundary());
+    }
+
+    #[test]
+    fn table_size_updates() {
+        let mut table_size = TableSize::new();
+
+        // client preface and SETTINGS frames lowering, then raising the header table size
+        let settings = |size: u8| [0, 0, 6, 0x4, 0, 0, 0, 0, 0, 0, 0x1, 0, 0, 0x10, size];
+        let inbound = [PREFACE, &settings(0), &settings(0x80)].concat();
+
+        // split reads
+        table_size.received(&inbound[..30]);
+        table_size.received(&inbound[30..]);
+        assert_eq!(table_size.unacked.len(), 2);
+        assert!(table_size.pending.is_none());
+
+        let ack = FrameHead {
+            kind: FRAME_TYPE_SETTINGS,
+            flags: FLAG_ACK,
+        };
+
+        table_size.sent(ack);
+        table_size.sent(ack);
+        assert_eq!(table_size.pending, Some((0x1000, 0x1080)));
+
+        let mut buf = BytesMut::new();
+        table_size.encode(&mut buf);
+        assert_eq!(&buf[..], &[0x3f, 0xe1, 0x1f, 0x3f, 0xe1, 0x20]);
+
+        // h2 signalled the changes itself
+        table_size.sent(FrameHead {
+            kind: FRAME_TYPE_HEADERS,
+            flags: FLAG_END_HEADERS,
+        });
+
+        let mut buf = BytesMut::new();
+        table_size.encode(&mut buf);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn informational_frame() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::LINK, header::HeaderValue::from_static("</a>"));
+        headers.insert(
+            header::CONNECTION,
+            header::HeaderValue::from_static("close"),
+        );
+
+        let mut buf = BytesMut::new();
+        encode_informational(
+            &mut buf,
+            Informational {
+                stream_id: 5,
+                status: StatusCode::PROCESSING,
+                headers,
+            },
+            &TableSize::new(),
+        );
+
+        let block = [
+            &[0, 7][..],
+            b":status",
+            &[3],
+            b"102",
+            &[0, 4],
+            b"link",
+            &[4],
+            b"</a>",
+        ]
+        .concat();
+
+        assert_eq!(&buf[..3], &[0, 0, block.len() as u8]);
+        // HEADERS with END_HEADERS but not END_STREAM
+        assert_eq!(&buf[3..9], &[0x1, 0x4, 0, 0, 0, 5]);
+        assert_eq!(&buf[9..], &block[..]);
+    }
+}
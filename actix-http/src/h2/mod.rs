@@ -3,6 +3,7 @@
 use std::{
     future::Future,
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
 };
 
@@ -11,21 +12,33 @@ use actix_rt::time::Sleep;
 use bytes::Bytes;
 use futures_core::{ready, Stream};
 use h2::{
-    server::{handshake, Connection, Handshake},
+    server::{Connection, Handshake},
     RecvStream,
 };
 
 mod dispatcher;
+mod h2c;
+mod informational;
 mod service;
 
 pub use self::dispatcher::Dispatcher;
 pub use self::service::H2Service;
 
+#[cfg(feature = "http3")]
+pub(crate) use self::dispatcher::prepare_response;
+pub(crate) use self::h2c::{
+    check_preface, encode_upgrade_request, is_upgrade_request, H2cIo, Preface,
+};
+
 use crate::{
     config::ServiceConfig,
     error::{DispatchError, PayloadError},
+    informational::InformationalQueue,
+    ConnectionStats,
 };
 
+use self::informational::InformationalIo;
+
 /// HTTP/2 peer stream.
 pub struct Payload {
     stream: RecvStream,
@@ -61,33 +74,52 @@ impl Stream for Payload {
 pub(crate) fn handshake_with_timeout<T>(
     io: T,
     config: &ServiceConfig,
+    stats: Option<ConnectionStats>,
 ) -> HandshakeWithTimeout<T>
 where
     T: AsyncRead + AsyncWrite + Unpin,
 {
+    let informational = InformationalQueue::new();
+    let io = InformationalIo::new(io, Rc::clone(&informational), stats);
+
     HandshakeWithTimeout {
-        handshake: handshake(io),
+        handshake: config.h2().server_builder().handshake(io),
         timer: config.client_timer().map(Box::pin),
+        informational: Some(informational),
     }
 }
 
+/// HTTP/2 server connection along with its queue of informational responses.
+pub(crate) struct ServerConnection<T> {
+    pub(crate) conn: Connection<InformationalIo<T>, Bytes>,
+    pub(crate) informational: Rc<InformationalQueue>,
+}
+
 pub(crate) struct HandshakeWithTimeout<T: AsyncRead + AsyncWrite + Unpin> {
-    handshake: Handshake<T>,
+    handshake: Handshake<InformationalIo<T>>,
     timer: Option<Pin<Box<Sleep>>>,
+    informational: Option<Rc<InformationalQueue>>,
 }
 
 impl<T> Future for HandshakeWithTimeout<T>
 where
     T: AsyncRead + AsyncWrite + Unpin,
 {
-    type Output = Result<(Connection<T, Bytes>, Option<Pin<Box<Sleep>>>), DispatchError>;
+    type Output = Result<(ServerConnection<T>, Option<Pin<Box<Sleep>>>), DispatchError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
         match Pin::new(&mut this.handshake).poll(cx)? {
             // return the timer on success handshake. It can be re-used for h2 ping-pong.
-            Poll::Ready(conn) => Poll::Ready(Ok((conn, this.timer.take()))),
+            Poll::Ready(conn) => {
+                let conn = ServerConnection {
+                    conn,
+                    informational: this.informational.take().unwrap(),
+                };
+
+                Poll::Ready(Ok((conn, this.timer.take())))
+            }
             Poll::Pending => match this.timer.as_mut() {
                 Some(timer) => {
                     ready!(timer.as_mut().poll(cx));
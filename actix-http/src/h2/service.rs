@@ -5,6 +5,7 @@ use std::{
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use actix_codec::{AsyncRead, AsyncWrite};
@@ -21,7 +22,7 @@ use crate::{
     config::ServiceConfig,
     error::DispatchError,
     service::HttpFlow,
-    ConnectCallback, OnConnectData, Request, Response,
+    ConnectCallback, OnConnectData, Protocol, Request, Response,
 };
 
 use super::{dispatcher::Dispatcher, handshake_with_timeout, HandshakeWithTimeout};
@@ -61,6 +62,90 @@ where
         self.on_connect_ext = f;
         self
     }
+
+    /// Set the maximum number of concurrent streams the client may open per connection.
+    ///
+    /// By default, the number of concurrent streams is not limited.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.cfg.h2_mut().max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set the initial stream-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn initial_window_size(mut self, size: u32) -> Self {
+        self.cfg.h2_mut().initial_window_size = Some(size);
+        self
+    }
+
+    /// Set the initial connection-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        self.cfg.h2_mut().initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set the maximum frame size in bytes the server is willing to receive.
+    ///
+    /// The value must be between 16,384 and 16,777,215 bytes. By default it is set to 16,384.
+    pub fn max_frame_size(mut self, max: u32) -> Self {
+        self.cfg.h2_mut().max_frame_size = Some(max);
+        self
+    }
+
+    /// Set the maximum size in bytes of the header list the server is willing to accept.
+    ///
+    /// By default it is set to 16MiB.
+    pub fn max_header_list_size(mut self, max: u32) -> Self {
+        self.cfg.h2_mut().max_header_list_size = Some(max);
+        self
+    }
+
+    /// Set the maximum number of concurrently reset streams.
+    ///
+    /// Locally reset streams are tracked for a while in order to handle frames that the peer
+    /// sent before it learned about the reset. By default at most 10 streams are tracked.
+    pub fn max_concurrent_reset_streams(mut self, max: usize) -> Self {
+        self.cfg.h2_mut().max_concurrent_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the duration for which locally reset streams are remembered.
+    ///
+    /// By default reset streams are remembered for 30 seconds.
+    pub fn reset_stream_duration(mut self, dur: Duration) -> Self {
+        self.cfg.h2_mut().reset_stream_duration = Some(dur);
+        self
+    }
+
+    /// Set the maximum number of streams that were reset by the client before they were
+    /// accepted by the server.
+    ///
+    /// Once the limit is reached the connection is closed. This protects against "rapid reset"
+    /// floods. By default the limit is 20.
+    pub fn max_pending_accept_reset_streams(mut self, max: usize) -> Self {
+        self.cfg.h2_mut().max_pending_accept_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the maximum number of streams the server may reset because of client errors.
+    ///
+    /// Once the limit is reached the connection is closed. By default the limit is 1024.
+    pub fn max_local_error_reset_streams(mut self, max: usize) -> Self {
+        self.cfg.h2_mut().max_local_error_reset_streams = Some(max);
+        self
+    }
+
+    /// Advertise support for extended CONNECT requests (RFC 8441), used by clients to open
+    /// WebSockets over HTTP/2 streams.
+    ///
+    /// Disabled by default.
+    pub fn enable_connect_protocol(mut self, enabled: bool) -> Self {
+        self.cfg.h2_mut().enable_connect_protocol = enabled;
+        self
+    }
 }
 
 impl<S, B> H2Service<TcpStream, S, B>
@@ -127,6 +212,13 @@ mod openssl {
             Error = TlsError<SslError, DispatchError>,
             InitError = S::InitError,
         > {
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::openssl_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(acceptor)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -136,7 +228,7 @@ mod openssl {
                     let peer_addr = io.get_ref().peer_addr().ok();
                     (io, peer_addr)
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -178,6 +270,13 @@ mod rustls {
             protos.extend_from_slice(&config.alpn_protocols);
             config.alpn_protocols = protos;
 
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::rustls_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(config)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -187,7 +286,7 @@ mod rustls {
                     let peer_addr = io.get_ref().0.peer_addr().ok();
                     (io, peer_addr)
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -278,7 +377,9 @@ where
     }
 
     fn call(&self, (io, addr): (T, Option<net::SocketAddr>)) -> Self::Future {
-        let on_connect_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref());
+        let on_connect_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref())
+            .with_stats(&self.cfg, Protocol::Http2, addr);
+        let stats = on_connect_data.stats();
 
         H2ServiceHandlerResponse {
             state: State::Handshake(
@@ -286,7 +387,7 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect_data,
-                handshake_with_timeout(io, &self.cfg),
+                handshake_with_timeout(io, &self.cfg, stats),
             ),
         }
     }
@@ -348,7 +449,7 @@ where
                         srv.take().unwrap(),
                         config.take().unwrap(),
                         *peer_addr,
-                        on_connect_data,
+                        on_connect_data.0.map(Rc::new),
                         timer,
                     ));
 
@@ -0,0 +1,160 @@
+use std::{error::Error as StdError, future::Future as _, rc::Rc, task::Poll};
+
+use actix_service::Service;
+use actix_utils::future::poll_fn;
+use bytes::Bytes;
+use h3::{
+    error::ErrorLevel,
+    server::{Connection, RequestStream},
+};
+use log::{error, trace};
+use quinn::Connecting;
+
+use crate::{
+    body::{BoxBody, MessageBody},
+    config::ServiceConfig,
+    error::DispatchError,
+    service::HttpFlow,
+    Payload, Request, Response,
+};
+
+/// Serve HTTP/3 requests of a single QUIC connection until it is closed.
+pub(crate) async fn dispatch<S, B>(
+    connecting: Connecting,
+    flow: Rc<HttpFlow<S, (), ()>>,
+    config: ServiceConfig,
+) -> Result<(), DispatchError>
+where
+    S: Service<Request>,
+    S::Error: Into<Response<BoxBody>>,
+    S::Future: 'static,
+    S::Response: Into<Response<B>>,
+
+    B: MessageBody + 'static,
+{
+    let conn = connecting
+        .await
+        .map_err(|err| DispatchError::Io(err.into()))?;
+
+    let peer_addr = Some(conn.remote_address());
+
+    let mut conn = Connection::<_, Bytes>::new(h3_quinn::Connection::new(conn)).await?;
+    let mut shutdown = config.shutdown_signal().map(|signal| signal.listener());
+
+    loop {
+        let next = {
+            let accept = conn.accept();
+            actix_rt::pin!(accept);
+
+            poll_fn(|cx| {
+                if let Some(listener) = shutdown.as_ref() {
+                    if listener.poll_triggered(cx).is_ready() {
+                        return Poll::Ready(None);
+                    }
+                }
+
+                accept.as_mut().poll(cx).map(Some)
+            })
+            .await
+        };
+
+        let (req, stream) = match next {
+            // server is shutting down; send GOAWAY and keep accepting until in-flight
+            // requests complete
+            None => {
+                trace!("Server is shutting down, sending HTTP/3 GOAWAY");
+                shutdown = None;
+                conn.shutdown(0).await?;
+                continue;
+            }
+
+            Some(Ok(Some(accepted))) => accepted,
+            Some(Ok(None)) => return Ok(()),
+
+            Some(Err(err)) => match err.get_error_level() {
+                ErrorLevel::StreamError => {
+                    trace!("HTTP/3 stream error: {}", err);
+                    continue;
+                }
+                ErrorLevel::ConnectionError => return Err(err.into()),
+            },
+        };
+
+        let (tx, rx) = stream.split();
+        let (parts, ()) = req.into_parts();
+
+        let pl = Payload::Stream(Box::pin(super::payload(rx)));
+        let mut req = Request::with_payload(pl);
+
+        let head = req.head_mut();
+        head.uri = parts.uri;
+        head.method = parts.method;
+        head.version = parts.version;
+        head.headers = parts.headers.into();
+        head.peer_addr = peer_addr;
+
+        let fut = flow.service.call(req);
+        let config = config.clone();
+
+        // multiplex request handling with spawn task
+        actix_rt::spawn(async move {
+            // resolve service call and send response.
+            let res = match fut.await {
+                Ok(res) => handle_response(res.into(), tx, config).await,
+                Err(err) => {
+                    let res: Response<BoxBody> = err.into();
+                    handle_response(res, tx, config).await
+                }
+            };
+
+            // log error.
+            if let Err(err) = res {
+                match err {
+                    H3DispatchError::Send(err) => {
+                        trace!("Error sending HTTP/3 response: {}", err)
+                    }
+                    H3DispatchError::ResponseBody(err) => {
+                        error!("Response payload stream error: {:?}", err)
+                    }
+                }
+            }
+        });
+    }
+}
+
+enum H3DispatchError {
+    Send(h3::Error),
+    ResponseBody(Box<dyn StdError>),
+}
+
+async fn handle_response<B>(
+    res: Response<B>,
+    mut tx: RequestStream<h3_quinn::SendStream<Bytes>, Bytes>,
+    config: ServiceConfig,
+) -> Result<(), H3DispatchError>
+where
+    B: MessageBody,
+{
+    let (res, body) = res.replace_body(());
+
+    // prepare response.
+    let mut size = body.size();
+    let mut res = crate::h2::prepare_response(&config, res.head(), &mut size);
+    *res.version_mut() = http::Version::HTTP_3;
+
+    // send response head and return on eof.
+    tx.send_response(res).await.map_err(H3DispatchError::Send)?;
+
+    if !size.is_eof() {
+        // poll response body and send chunks to client
+        actix_rt::pin!(body);
+
+        while let Some(res) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+            let chunk = res.map_err(|err| H3DispatchError::ResponseBody(err.into()))?;
+            tx.send_data(chunk).await.map_err(H3DispatchError::Send)?;
+        }
+    }
+
+    // response finished. close sending side of stream.
+    tx.finish().await.map_err(H3DispatchError::Send)
+}
@@ -0,0 +1,47 @@
+//! HTTP/3 protocol.
+//!
+//! HTTP/3 runs on top of QUIC connections provided by [quinn]. Requires the `http3` feature.
+//!
+//! [quinn]: https://crates.io/crates/quinn
+
+use std::io;
+
+use bytes::{Buf as _, Bytes};
+use futures_core::Stream;
+use h3::server::RequestStream;
+
+mod dispatcher;
+mod service;
+
+pub use self::service::{H3Service, H3ServiceHandler};
+
+/// Re-exports of the QUIC implementation types used by the HTTP/3 transport.
+pub mod reexports {
+    pub use quinn::{Connecting, Endpoint, EndpointConfig, ServerConfig};
+}
+
+use crate::error::PayloadError;
+
+/// ALPN protocol identifier of HTTP/3.
+pub const ALPN: &[u8] = b"h3";
+
+/// Create request payload stream from the receiving half of an HTTP/3 request stream.
+fn payload(
+    stream: RequestStream<h3_quinn::RecvStream, Bytes>,
+) -> impl Stream<Item = Result<Bytes, PayloadError>> {
+    futures_util::stream::unfold(Some(stream), |stream| async move {
+        let mut stream = stream?;
+
+        match stream.recv_data().await {
+            Ok(Some(mut buf)) => {
+                let chunk = buf.copy_to_bytes(buf.remaining());
+                Some((Ok(chunk), Some(stream)))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                let err = io::Error::new(io::ErrorKind::Other, err);
+                Some((Err(PayloadError::Io(err)), None))
+            }
+        }
+    })
+}
@@ -0,0 +1,136 @@
+use std::{
+    marker::PhantomData,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_service::{IntoServiceFactory, Service, ServiceFactory};
+use futures_core::future::LocalBoxFuture;
+use log::error;
+use quinn::Connecting;
+
+use crate::{
+    body::{BoxBody, MessageBody},
+    config::ServiceConfig,
+    error::DispatchError,
+    service::HttpFlow,
+    Request, Response,
+};
+
+use super::dispatcher;
+
+/// `ServiceFactory` implementation for HTTP/3 transport.
+///
+/// Accepts incoming QUIC connections, as yielded by [`Endpoint`](quinn::Endpoint), and serves
+/// HTTP/3 requests on them until the connection is closed.
+pub struct H3Service<S, B> {
+    srv: S,
+    cfg: ServiceConfig,
+    _phantom: PhantomData<B>,
+}
+
+impl<S, B> H3Service<S, B>
+where
+    S: ServiceFactory<Request, Config = ()>,
+    S::Error: Into<Response<BoxBody>> + 'static,
+    S::Response: Into<Response<B>> + 'static,
+    <S::Service as Service<Request>>::Future: 'static,
+
+    B: MessageBody + 'static,
+{
+    /// Create new `H3Service` instance with config.
+    pub(crate) fn with_config<F: IntoServiceFactory<S, Request>>(
+        cfg: ServiceConfig,
+        service: F,
+    ) -> Self {
+        H3Service {
+            cfg,
+            srv: service.into_factory(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, B> ServiceFactory<Connecting> for H3Service<S, B>
+where
+    S: ServiceFactory<Request, Config = ()>,
+    S::Future: 'static,
+    S::Error: Into<Response<BoxBody>> + 'static,
+    S::Response: Into<Response<B>> + 'static,
+    <S::Service as Service<Request>>::Future: 'static,
+
+    B: MessageBody + 'static,
+{
+    type Response = ();
+    type Error = DispatchError;
+    type Config = ();
+    type Service = H3ServiceHandler<S::Service, B>;
+    type InitError = S::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let service = self.srv.new_service(());
+        let cfg = self.cfg.clone();
+
+        Box::pin(async move {
+            let service = service.await?;
+            Ok(H3ServiceHandler::new(cfg, service))
+        })
+    }
+}
+
+/// `Service` implementation for HTTP/3 transport.
+pub struct H3ServiceHandler<S, B>
+where
+    S: Service<Request>,
+{
+    flow: Rc<HttpFlow<S, (), ()>>,
+    cfg: ServiceConfig,
+    _phantom: PhantomData<B>,
+}
+
+impl<S, B> H3ServiceHandler<S, B>
+where
+    S: Service<Request>,
+    S::Error: Into<Response<BoxBody>> + 'static,
+    S::Future: 'static,
+    S::Response: Into<Response<B>> + 'static,
+    B: MessageBody + 'static,
+{
+    fn new(cfg: ServiceConfig, service: S) -> H3ServiceHandler<S, B> {
+        H3ServiceHandler {
+            flow: HttpFlow::new(service, (), None),
+            cfg,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, B> Service<Connecting> for H3ServiceHandler<S, B>
+where
+    S: Service<Request> + 'static,
+    S::Error: Into<Response<BoxBody>> + 'static,
+    S::Future: 'static,
+    S::Response: Into<Response<B>> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ();
+    type Error = DispatchError;
+    type Future = LocalBoxFuture<'static, Result<(), DispatchError>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.flow.service.poll_ready(cx).map_err(|e| {
+            let e = e.into();
+            error!("Service readiness error: {:?}", e);
+            DispatchError::Service(e)
+        })
+    }
+
+    fn call(&self, conn: Connecting) -> Self::Future {
+        Box::pin(dispatcher::dispatch(
+            conn,
+            Rc::clone(&self.flow),
+            self.cfg.clone(),
+        ))
+    }
+}
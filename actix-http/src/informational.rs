@@ -0,0 +1,278 @@
+//! Informational (1xx) responses sent ahead of the final response.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt,
+    rc::{Rc, Weak},
+    task::Waker,
+};
+
+use crate::{
+    error::InformationalError,
+    header::{HeaderMap, HeaderValue, LINK},
+    StatusCode,
+};
+
+/// Handle for sending informational (1xx) responses before the final response of a request.
+///
+/// A handle is stored in the extensions of every request served over HTTP/1.x and HTTP/2.
+/// Informational responses are written to the client as soon as the connection gets to them,
+/// while the service is still busy producing the final response; for example, to send
+/// `103 Early Hints` with `Link` headers so clients can start preloading resources.
+///
+/// HTTP/1.0 clients do not understand informational responses. Requests from such clients get
+/// a handle for which sending is a no-op, as do requests that are not served by a connection
+/// dispatcher (see [`is_supported`](Self::is_supported)).
+///
+/// ```
+/// use actix_http::{header::HeaderValue, HttpMessage as _, InformationalSender, Request};
+///
+/// fn preload(req: &Request) {
+///     if let Some(sender) = req.extensions().get::<InformationalSender>() {
+///         let link = HeaderValue::from_static("</style.css>; rel=preload; as=style");
+///         sender.send_early_hints(vec![link]).unwrap();
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct InformationalSender {
+    inner: Option<Rc<Inner>>,
+}
+
+struct Inner {
+    queue: Rc<InformationalQueue>,
+    stream_id: u32,
+    closed: Cell<bool>,
+}
+
+impl InformationalSender {
+    /// Create handle that sends informational responses through a connection's queue.
+    pub(crate) fn new(queue: Rc<InformationalQueue>, stream_id: u32) -> Self {
+        Self {
+            inner: Some(Rc::new(Inner {
+                queue,
+                stream_id,
+                closed: Cell::new(false),
+            })),
+        }
+    }
+
+    /// Create handle for which sending is a no-op.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if informational responses sent through this handle reach the client.
+    pub fn is_supported(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Send informational response with given status code and headers.
+    ///
+    /// # Errors
+    /// Returns an error if the status code is not informational, is `100 Continue` or
+    /// `101 Switching Protocols` (which are handled by the connection dispatchers), or if the
+    /// final response has already been sent.
+    pub fn send(
+        &self,
+        status: StatusCode,
+        headers: HeaderMap,
+    ) -> Result<(), InformationalError> {
+        if !status.is_informational()
+            || status == StatusCode::CONTINUE
+            || status == StatusCode::SWITCHING_PROTOCOLS
+        {
+            return Err(InformationalError::InvalidStatus);
+        }
+
+        let inner = match self.inner {
+            Some(ref inner) => inner,
+            None => return Ok(()),
+        };
+
+        if inner.closed.get() {
+            return Err(InformationalError::Closed);
+        }
+
+        inner.queue.push(Informational {
+            stream_id: inner.stream_id,
+            status,
+            headers,
+        });
+
+        Ok(())
+    }
+
+    /// Send `103 Early Hints` response with one `Link` header per given value.
+    pub fn send_early_hints<I>(&self, links: I) -> Result<(), InformationalError>
+    where
+        I: IntoIterator<Item = HeaderValue>,
+    {
+        let mut headers = HeaderMap::new();
+
+        for link in links {
+            headers.append(LINK, link);
+        }
+
+        self.send(early_hints(), headers)
+    }
+
+    /// Reject further informational responses; called once the final response is sent.
+    pub(crate) fn close(&self) {
+        if let Some(ref inner) = self.inner {
+            inner.closed.set(true);
+        }
+    }
+}
+
+impl fmt::Debug for InformationalSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InformationalSender")
+            .field("supported", &self.is_supported())
+            .field(
+                "closed",
+                &self
+                    .inner
+                    .as_ref()
+                    .map_or(false, |inner| inner.closed.get()),
+            )
+            .finish()
+    }
+}
+
+/// `103 Early Hints` status code (RFC 8297).
+fn early_hints() -> StatusCode {
+    StatusCode::from_u16(103).unwrap()
+}
+
+/// Informational response waiting to be written by a connection dispatcher.
+pub(crate) struct Informational {
+    /// HTTP/2 stream the response belongs to; unused for HTTP/1.
+    pub(crate) stream_id: u32,
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+}
+
+/// Per-connection queue of informational responses.
+#[derive(Default)]
+pub(crate) struct InformationalQueue {
+    items: RefCell<VecDeque<Informational>>,
+    waker: Cell<Option<Waker>>,
+
+    /// Handle of the HTTP/1 request currently being answered.
+    active: RefCell<Option<Weak<Inner>>>,
+}
+
+impl InformationalQueue {
+    pub(crate) fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    fn push(&self, item: Informational) {
+        self.items.borrow_mut().push_back(item);
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Take the next queued response.
+    pub(crate) fn pop(&self) -> Option<Informational> {
+        self.items.borrow_mut().pop_front()
+    }
+
+    /// Returns true if no responses are queued.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.borrow().is_empty()
+    }
+
+    /// Register task of the connection dispatcher that writes queued responses.
+    pub(crate) fn register(&self, waker: &Waker) {
+        match self.waker.take() {
+            Some(current) if current.will_wake(waker) => self.waker.set(Some(current)),
+            _ => self.waker.set(Some(waker.clone())),
+        }
+    }
+
+    /// Mark handle as the one of the HTTP/1 request currently being answered.
+    pub(crate) fn activate(&self, sender: Option<&InformationalSender>) {
+        *self.active.borrow_mut() = sender
+            .and_then(|sender| sender.inner.as_ref())
+            .map(Rc::downgrade);
+    }
+
+    /// Close handle of the HTTP/1 request currently being answered.
+    pub(crate) fn close_active(&self) {
+        if let Some(inner) = self
+            .active
+            .borrow_mut()
+            .take()
+            .and_then(|inner| inner.upgrade())
+        {
+            inner.closed.set(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_and_close() {
+        let queue = InformationalQueue::new();
+        let sender = InformationalSender::new(Rc::clone(&queue), 3);
+        assert!(sender.is_supported());
+
+        sender
+            .send_early_hints(vec![HeaderValue::from_static("</a.css>; rel=preload")])
+            .unwrap();
+
+        let item = queue.pop().unwrap();
+        assert_eq!(item.stream_id, 3);
+        assert_eq!(item.status, early_hints());
+        assert_eq!(item.headers.get(LINK).unwrap(), "</a.css>; rel=preload");
+        assert!(queue.is_empty());
+
+        queue.activate(Some(&sender));
+        queue.close_active();
+
+        assert_eq!(
+            sender.send(early_hints(), HeaderMap::new()),
+            Err(InformationalError::Closed)
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn invalid_status() {
+        let sender = InformationalSender::new(InformationalQueue::new(), 1);
+
+        for &status in &[
+            StatusCode::OK,
+            StatusCode::CONTINUE,
+            StatusCode::SWITCHING_PROTOCOLS,
+        ] {
+            assert_eq!(
+                sender.send(status, HeaderMap::new()),
+                Err(InformationalError::InvalidStatus)
+            );
+        }
+
+        assert!(sender
+            .send(StatusCode::PROCESSING, HeaderMap::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn disabled_is_noop() {
+        let sender = InformationalSender::disabled();
+        assert!(!sender.is_supported());
+        assert!(sender.send_early_hints(None).is_ok());
+        assert_eq!(
+            sender.send(StatusCode::OK, HeaderMap::new()),
+            Err(InformationalError::InvalidStatus)
+        );
+    }
+}
@@ -8,11 +8,14 @@
 //! | `compress-brotli`   | Payload compression support: Brotli.        |
 //! | `compress-gzip`     | Payload compression support: Deflate, Gzip. |
 //! | `compress-zstd`     | Payload compression support: Zstd.          |
+//! | `http3`             | HTTP/3 transport via [quinn] and [h3].      |
 //! | `trust-dns`         | Use [trust-dns] as the client DNS resolver. |
 //!
 //! [OpenSSL]: https://crates.io/crates/openssl
 //! [rustls]: https://crates.io/crates/rustls
 //! [trust-dns]: https://crates.io/crates/trust-dns
+//! [quinn]: https://crates.io/crates/quinn
+//! [h3]: https://crates.io/crates/h3
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![warn(future_incompatible)]
@@ -38,17 +41,25 @@ mod extensions;
 pub mod header;
 mod helpers;
 mod http_message;
+mod informational;
 mod message;
 mod payload;
 mod request;
 mod response;
 mod response_builder;
+#[cfg(target_os = "linux")]
+mod sendfile;
 mod service;
+mod shutdown;
+mod stats;
 
 pub mod error;
 pub mod h1;
 pub mod h2;
+#[cfg(feature = "http3")]
+pub mod h3;
 pub mod test;
+pub mod upgrade;
 pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
@@ -57,13 +68,18 @@ pub use self::error::Error;
 pub use self::extensions::Extensions;
 pub use self::header::ContentEncoding;
 pub use self::http_message::HttpMessage;
+pub use self::informational::InformationalSender;
 pub use self::message::ConnectionType;
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
 pub use self::payload::{Payload, PayloadStream};
 pub use self::request::Request;
 pub use self::response::Response;
 pub use self::response_builder::ResponseBuilder;
+#[cfg(target_os = "linux")]
+pub use self::sendfile::SendFile;
 pub use self::service::HttpService;
+pub use self::shutdown::ShutdownSignal;
+pub use self::stats::{ConnectionStats, TlsInfo};
 
 pub use ::http::{uri, uri::Uri};
 pub use ::http::{Method, StatusCode, Version};
@@ -97,4 +113,34 @@ impl OnConnectData {
 
         Self(ext)
     }
+
+    /// Add connection statistics and close hook if enabled in service config.
+    ///
+    /// Must be called after [`from_io`](Self::from_io) so that TLS details recorded by the
+    /// on-connect callback are picked up.
+    pub(crate) fn with_stats(
+        self,
+        config: &ServiceConfig,
+        protocol: Protocol,
+        peer_addr: Option<std::net::SocketAddr>,
+    ) -> Self {
+        if !config.connection_stats() {
+            return self;
+        }
+
+        let mut ext = self.0.unwrap_or_default();
+        let stats = ConnectionStats::new(protocol, peer_addr, ext.remove::<TlsInfo>());
+        ext.insert(stats::DisconnectGuard::new(
+            stats.clone(),
+            config.on_disconnect().cloned(),
+        ));
+        ext.insert(stats);
+
+        Self(Some(ext))
+    }
+
+    /// Returns connection statistics handle, if statistics are enabled.
+    pub(crate) fn stats(&self) -> Option<ConnectionStats> {
+        self.0.as_ref()?.get::<ConnectionStats>().cloned()
+    }
 }
@@ -51,6 +51,7 @@ pub struct RequestHead {
     pub version: Version,
     pub headers: HeaderMap,
     pub peer_addr: Option<net::SocketAddr>,
+    protocol: Option<Box<str>>,
     flags: Flags,
 }
 
@@ -62,6 +63,7 @@ impl Default for RequestHead {
             version: Version::HTTP_11,
             headers: HeaderMap::with_capacity(16),
             peer_addr: None,
+            protocol: None,
             flags: Flags::empty(),
         }
     }
@@ -70,6 +72,7 @@ impl Default for RequestHead {
 impl Head for RequestHead {
     fn clear(&mut self) {
         self.flags = Flags::empty();
+        self.protocol = None;
         self.headers.clear();
     }
 
@@ -174,6 +177,21 @@ impl RequestHead {
     pub(crate) fn set_expect(&mut self) {
         self.flags.insert(Flags::EXPECT);
     }
+
+    /// Protocol of an HTTP/2 extended CONNECT request.
+    ///
+    /// This is the value of the `:protocol` pseudo-header (RFC 8441), e.g. `websocket` when
+    /// bootstrapping a WebSocket over an HTTP/2 stream. Always `None` for HTTP/1.x requests.
+    #[inline]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Set protocol of an HTTP/2 extended CONNECT request.
+    #[inline]
+    pub fn set_protocol(&mut self, protocol: Option<&str>) {
+        self.protocol = protocol.map(Into::into);
+    }
 }
 
 #[derive(Debug)]
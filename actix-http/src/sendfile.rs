@@ -0,0 +1,117 @@
+//! Zero-copy file bodies for plaintext HTTP/1 connections.
+
+use std::{
+    cmp,
+    convert::TryFrom as _,
+    fmt,
+    fs::File,
+    io,
+    os::unix::io::AsRawFd as _,
+    task::{Context, Poll},
+};
+
+use actix_rt::net::TcpStream;
+use futures_core::ready;
+use tokio::io::Interest;
+
+/// Largest number of bytes the kernel transfers in a single `sendfile(2)` call.
+const MAX_SENDFILE_CHUNK: u64 = 0x7fff_f000;
+
+/// Region of a file that can be sent as a response body with `sendfile(2)`.
+///
+/// Insert into the extensions of a response whose body yields the same bytes as the file region.
+/// When that response is written to a plaintext HTTP/1 connection and its body size is still
+/// `len` (i.e., it was not compressed or otherwise replaced by middleware), the file data is
+/// copied to the socket by the kernel and the body is dropped without being polled. In every
+/// other case, including `HEAD` requests, TLS connections, and HTTP/2, the body is sent as usual.
+///
+/// Only available on Linux.
+///
+/// ```
+/// use std::fs::{self, File};
+///
+/// use actix_http::{Response, SendFile};
+///
+/// let file = File::open("Cargo.toml").unwrap();
+/// let data = fs::read("Cargo.toml").unwrap();
+/// let len = data.len() as u64;
+///
+/// // body is sent when the file region can not be
+/// let mut res = Response::ok().set_body(data);
+/// res.extensions_mut().insert(SendFile::new(file, 0, len));
+/// ```
+pub struct SendFile {
+    file: File,
+    offset: u64,
+    len: u64,
+}
+
+impl SendFile {
+    /// Constructs file region of `len` bytes starting at `offset`.
+    pub fn new(file: File, offset: u64, len: u64) -> Self {
+        Self { file, offset, len }
+    }
+
+    /// Returns number of bytes left to send.
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Copy file data to socket until the socket would block.
+    ///
+    /// Resolves with number of bytes written by one `sendfile(2)` call.
+    pub(crate) fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        io: &TcpStream,
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            ready!(io.poll_write_ready(cx))?;
+
+            match io.try_io(Interest::WRITABLE, || self.send(io)) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+
+    fn send(&mut self, io: &TcpStream) -> io::Result<usize> {
+        let mut offset = libc::off_t::try_from(self.offset).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "file offset too large")
+        })?;
+        let count = cmp::min(self.len, MAX_SENDFILE_CHUNK) as usize;
+
+        // SAFETY: both descriptors stay open for the duration of the call and `offset` points to
+        // a valid `off_t`
+        let n = unsafe {
+            libc::sendfile(io.as_raw_fd(), self.file.as_raw_fd(), &mut offset, count)
+        };
+
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // file was truncated after response length was determined
+        if n == 0 && self.len > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file is shorter than response body",
+            ));
+        }
+
+        let n = n as usize;
+        self.offset += n as u64;
+        self.len -= n as u64;
+
+        Ok(n)
+    }
+}
+
+impl fmt::Debug for SendFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendFile")
+            .field("offset", &self.offset)
+            .field("len", &self.len)
+            .finish()
+    }
+}
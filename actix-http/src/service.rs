@@ -21,7 +21,8 @@ use crate::{
     builder::HttpServiceBuilder,
     config::{KeepAlive, ServiceConfig},
     error::DispatchError,
-    h1, h2, ConnectCallback, OnConnectData, Protocol, Request, Response,
+    h1, h2, ConnectCallback, ConnectionStats, Extensions, OnConnectData, Protocol, Request,
+    Response,
 };
 
 /// A `ServiceFactory` for HTTP/1.1 or HTTP/2 protocol.
@@ -233,6 +234,13 @@ mod openssl {
             Error = TlsError<SslError, DispatchError>,
             InitError = (),
         > {
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::openssl_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(acceptor)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -252,7 +260,7 @@ mod openssl {
                     let peer_addr = io.get_ref().peer_addr().ok();
                     (io, proto, peer_addr)
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -309,6 +317,13 @@ mod rustls {
             protos.extend_from_slice(&config.alpn_protocols);
             config.alpn_protocols = protos;
 
+            let on_connect_ext = crate::stats::with_tls_info(
+                &self.cfg,
+                self.on_connect_ext.clone(),
+                crate::stats::rustls_tls_info,
+            );
+            let service = self.on_connect_ext(on_connect_ext);
+
             Acceptor::new(config)
                 .map_init_err(|_| {
                     unreachable!("TLS acceptor service factory does not error on init")
@@ -327,7 +342,7 @@ mod rustls {
                     let peer_addr = io.get_ref().0.peer_addr().ok();
                     Ok((io, proto, peer_addr))
                 })
-                .and_then(self.map_err(TlsError::Service))
+                .and_then(service.map_err(TlsError::Service))
         }
     }
 }
@@ -473,7 +488,7 @@ impl<S, X, U> HttpFlow<S, X, U> {
 impl<T, S, B, X, U> Service<(T, Protocol, Option<net::SocketAddr>)>
     for HttpServiceHandler<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>> + 'static,
@@ -503,13 +518,14 @@ where
         &self,
         (io, proto, peer_addr): (T, Protocol, Option<net::SocketAddr>),
     ) -> Self::Future {
-        let conn_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref());
+        let conn_data = OnConnectData::from_io(&io, self.on_connect_ext.as_deref())
+            .with_stats(&self.cfg, proto, peer_addr);
 
         match proto {
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake {
                     handshake: Some((
-                        h2::handshake_with_timeout(io, &self.cfg),
+                        h2::handshake_with_timeout(io, &self.cfg, conn_data.stats()),
                         self.cfg.clone(),
                         self.flow.clone(),
                         conn_data,
@@ -518,17 +534,25 @@ where
                 },
             },
 
-            Protocol::Http1 => HttpServiceHandlerResponse {
-                state: State::H1 {
-                    dispatcher: h1::Dispatcher::new(
-                        io,
-                        self.flow.clone(),
-                        self.cfg.clone(),
-                        peer_addr,
-                        conn_data,
-                    ),
-                },
-            },
+            Protocol::Http1 => {
+                let dispatcher = h1::Dispatcher::new(
+                    io,
+                    self.flow.clone(),
+                    self.cfg.clone(),
+                    peer_addr,
+                    conn_data,
+                );
+
+                let dispatcher = if self.cfg.h2c_enabled() {
+                    dispatcher.h2c()
+                } else {
+                    dispatcher
+                };
+
+                HttpServiceHandlerResponse {
+                    state: State::H1 { dispatcher },
+                }
+            }
 
             proto => unimplemented!("Unsupported HTTP version: {:?}.", proto),
         }
@@ -566,6 +590,16 @@ pin_project! {
                 Option<net::SocketAddr>,
             )>,
         },
+        H2c { #[pin] dispatcher: h2::Dispatcher<h2::H2cIo<T>, S, B, X, U> },
+        H2cHandshake {
+            handshake: Option<(
+                h2::HandshakeWithTimeout<h2::H2cIo<T>>,
+                ServiceConfig,
+                Rc<HttpFlow<S, X, U>>,
+                Option<Rc<Extensions>>,
+                Option<net::SocketAddr>,
+            )>,
+        },
     }
 }
 
@@ -598,7 +632,7 @@ pin_project! {
 
 impl<T, S, B, X, U> Future for HttpServiceHandlerResponse<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 
     S: Service<Request>,
     S::Error: Into<Response<BoxBody>> + 'static,
@@ -617,8 +651,68 @@ where
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.as_mut().project().state.project() {
-            StateProj::H1 { dispatcher } => dispatcher.poll(cx),
+            StateProj::H1 { mut dispatcher } => {
+                ready!(dispatcher.as_mut().poll(cx))?;
+
+                match dispatcher.take_h2c() {
+                    Some(h1::H2cHandover {
+                        io,
+                        read_buf,
+                        req,
+                        flow,
+                        config,
+                        conn_data,
+                        peer_addr,
+                    }) => {
+                        let upgrade = req.as_ref().map(|req| {
+                            h2::encode_upgrade_request(req, config.h2().max_frame_size)
+                        });
+                        let stats = conn_data
+                            .as_deref()
+                            .and_then(Extensions::get::<ConnectionStats>)
+                            .cloned();
+
+                        if let Some(ref stats) = stats {
+                            stats.set_protocol(Protocol::Http2);
+                        }
+
+                        // bytes are counted below the replay buffer of the h2c wrapper
+                        let io = h2::H2cIo::new(io, read_buf, upgrade, stats);
+
+                        self.as_mut().project().state.set(State::H2cHandshake {
+                            handshake: Some((
+                                h2::handshake_with_timeout(io, &config, None),
+                                config,
+                                flow,
+                                conn_data,
+                                peer_addr,
+                            )),
+                        });
+                        self.poll(cx)
+                    }
+                    None => Poll::Ready(Ok(())),
+                }
+            }
             StateProj::H2 { dispatcher } => dispatcher.poll(cx),
+            StateProj::H2c { dispatcher } => dispatcher.poll(cx),
+            StateProj::H2cHandshake { handshake: data } => {
+                match ready!(Pin::new(&mut data.as_mut().unwrap().0).poll(cx)) {
+                    Ok((conn, timer)) => {
+                        let (_, config, flow, conn_data, peer_addr) = data.take().unwrap();
+
+                        self.as_mut().project().state.set(State::H2c {
+                            dispatcher: h2::Dispatcher::new(
+                                conn, flow, config, peer_addr, conn_data, timer,
+                            ),
+                        });
+                        self.poll(cx)
+                    }
+                    Err(err) => {
+                        trace!("h2c handshake error: {}", err);
+                        Poll::Ready(Err(err))
+                    }
+                }
+            }
             StateProj::H2Handshake { handshake: data } => {
                 match ready!(Pin::new(&mut data.as_mut().unwrap().0).poll(cx)) {
                     Ok((conn, timer)) => {
@@ -626,7 +720,12 @@ where
 
                         self.as_mut().project().state.set(State::H2 {
                             dispatcher: h2::Dispatcher::new(
-                                conn, flow, config, peer_addr, conn_data, timer,
+                                conn,
+                                flow,
+                                config,
+                                peer_addr,
+                                conn_data.0.map(Rc::new),
+                                timer,
                             ),
                         });
                         self.poll(cx)
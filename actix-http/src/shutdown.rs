@@ -0,0 +1,184 @@
+//! Graceful shutdown notification for connection dispatchers.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll},
+};
+
+use actix_utils::future::poll_fn;
+use futures_util::task::AtomicWaker;
+
+/// Signal that notifies connection dispatchers of a graceful server shutdown.
+///
+/// Once triggered, dispatchers stop accepting new work on their connections while in-flight
+/// requests are allowed to finish:
+/// - HTTP/1 connections send `Connection: close` on the next response and idle keep-alive
+///   connections are closed immediately.
+/// - HTTP/2 connections send a `GOAWAY` frame with the last processed stream ID and are closed
+///   once all open streams are complete.
+///
+/// The signal is cheap to clone and can be shared across worker threads.
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    triggered: AtomicBool,
+    listeners: Mutex<Vec<Weak<AtomicWaker>>>,
+}
+
+impl ShutdownSignal {
+    /// Create new, untriggered shutdown signal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trigger the signal, waking all connection dispatchers that listen to it.
+    ///
+    /// Triggering an already triggered signal has no effect.
+    pub fn trigger(&self) {
+        if self.inner.triggered.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let listeners = std::mem::take(&mut *self.inner.listeners.lock().unwrap());
+
+        for waker in listeners.iter().filter_map(Weak::upgrade) {
+            waker.wake();
+        }
+    }
+
+    /// Returns true if the signal has been triggered.
+    pub fn is_triggered(&self) -> bool {
+        self.inner.triggered.load(Ordering::Acquire)
+    }
+
+    /// Wait until the signal is triggered.
+    ///
+    /// Resolves immediately if the signal has already been triggered.
+    pub async fn triggered(&self) {
+        let listener = self.listener();
+        poll_fn(|cx| listener.poll_triggered(cx)).await
+    }
+
+    /// Create listener for a single connection.
+    pub(crate) fn listener(&self) -> ShutdownListener {
+        let waker = Arc::new(AtomicWaker::new());
+
+        if !self.is_triggered() {
+            let mut listeners = self.inner.listeners.lock().unwrap();
+
+            // drop listeners of closed connections before the list would grow
+            if listeners.len() == listeners.capacity() {
+                listeners.retain(|waker| waker.strong_count() > 0);
+            }
+
+            listeners.push(Arc::downgrade(&waker));
+        }
+
+        ShutdownListener {
+            inner: Arc::clone(&self.inner),
+            waker,
+        }
+    }
+}
+
+impl fmt::Debug for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownSignal")
+            .field("triggered", &self.is_triggered())
+            .finish()
+    }
+}
+
+/// Per-connection listener of a [`ShutdownSignal`].
+pub(crate) struct ShutdownListener {
+    inner: Arc<Inner>,
+    waker: Arc<AtomicWaker>,
+}
+
+impl ShutdownListener {
+    /// Resolves once the shutdown signal is triggered.
+    pub(crate) fn poll_triggered(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.inner.triggered.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+
+        self.waker.register(cx.waker());
+
+        // check again in case signal was triggered before waker was registered
+        if self.inner.triggered.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+
+    #[test]
+    fn trigger_resolves_listeners() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let signal = ShutdownSignal::new();
+        let listener = signal.listener();
+        assert!(!signal.is_triggered());
+        assert!(listener.poll_triggered(&mut cx).is_pending());
+
+        signal.clone().trigger();
+        assert!(signal.is_triggered());
+        assert!(listener.poll_triggered(&mut cx).is_ready());
+
+        // listeners created after trigger resolve immediately
+        assert!(signal.listener().poll_triggered(&mut cx).is_ready());
+    }
+
+    #[actix_rt::test]
+    async fn triggered_resolves() {
+        let signal = ShutdownSignal::new();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let waiter = actix_rt::spawn({
+            let signal = signal.clone();
+            let done = Arc::clone(&done);
+            async move {
+                signal.triggered().await;
+                done.store(true, Ordering::Release);
+            }
+        });
+
+        actix_rt::task::yield_now().await;
+        assert!(!done.load(Ordering::Acquire));
+
+        signal.trigger();
+        waiter.await.unwrap();
+        assert!(done.load(Ordering::Acquire));
+
+        // already triggered signal resolves immediately
+        signal.triggered().await;
+    }
+
+    #[test]
+    fn closed_listeners_are_dropped() {
+        let signal = ShutdownSignal::new();
+
+        for _ in 0..64 {
+            drop(signal.listener());
+        }
+
+        let listeners = signal.inner.listeners.lock().unwrap();
+        assert!(listeners.len() < 64);
+    }
+}
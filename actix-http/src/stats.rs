@@ -0,0 +1,286 @@
+//! Per-connection statistics.
+
+use std::{
+    cell::Cell,
+    fmt, net,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{ConnectCallback, Extensions, Protocol, ServiceConfig};
+
+/// Statistics of a single connection.
+///
+/// When enabled with [`HttpServiceBuilder::connection_stats`] or
+/// [`HttpServiceBuilder::on_disconnect`], a handle is stored in the connection data of every
+/// connection, from where it is reachable through `Request::conn_data`. Handles are cheap to clone
+/// and observe the live counters of the connection.
+///
+/// Bytes are counted as they pass through the HTTP/1 and HTTP/2 dispatchers; traffic of
+/// connections handed to an upgrade service (e.g., WebSockets over HTTP/1) is not counted after
+/// the upgrade.
+///
+/// [`HttpServiceBuilder::connection_stats`]: crate::HttpServiceBuilder::connection_stats
+/// [`HttpServiceBuilder::on_disconnect`]: crate::HttpServiceBuilder::on_disconnect
+#[derive(Clone)]
+pub struct ConnectionStats {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    protocol: Cell<Protocol>,
+    peer_addr: Option<net::SocketAddr>,
+    tls: Option<TlsInfo>,
+    started: Instant,
+    bytes_read: Cell<u64>,
+    bytes_written: Cell<u64>,
+    requests: Cell<u64>,
+}
+
+impl ConnectionStats {
+    pub(crate) fn new(
+        protocol: Protocol,
+        peer_addr: Option<net::SocketAddr>,
+        tls: Option<TlsInfo>,
+    ) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                protocol: Cell::new(protocol),
+                peer_addr,
+                tls,
+                started: Instant::now(),
+                bytes_read: Cell::new(0),
+                bytes_written: Cell::new(0),
+                requests: Cell::new(0),
+            }),
+        }
+    }
+
+    /// Returns the HTTP protocol version spoken on the connection.
+    ///
+    /// Connections that start out as HTTP/1 and switch to HTTP/2 over cleartext (h2c) report
+    /// [`Protocol::Http2`] after the switch.
+    pub fn protocol(&self) -> Protocol {
+        self.inner.protocol.get()
+    }
+
+    /// Returns the address of the peer, if known.
+    pub fn peer_addr(&self) -> Option<net::SocketAddr> {
+        self.inner.peer_addr
+    }
+
+    /// Returns details of the TLS session, if the connection is secured by TLS.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.inner.tls.as_ref()
+    }
+
+    /// Returns the time since the connection was accepted.
+    pub fn duration(&self) -> Duration {
+        self.inner.started.elapsed()
+    }
+
+    /// Returns the number of bytes read from the connection.
+    pub fn bytes_read(&self) -> u64 {
+        self.inner.bytes_read.get()
+    }
+
+    /// Returns the number of bytes written to the connection.
+    pub fn bytes_written(&self) -> u64 {
+        self.inner.bytes_written.get()
+    }
+
+    /// Returns the number of requests received on the connection.
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.get()
+    }
+
+    pub(crate) fn set_protocol(&self, protocol: Protocol) {
+        self.inner.protocol.set(protocol);
+    }
+
+    pub(crate) fn add_read(&self, n: usize) {
+        let read = &self.inner.bytes_read;
+        read.set(read.get() + n as u64);
+    }
+
+    pub(crate) fn add_written(&self, n: usize) {
+        let written = &self.inner.bytes_written;
+        written.set(written.get() + n as u64);
+    }
+
+    pub(crate) fn add_request(&self) {
+        let requests = &self.inner.requests;
+        requests.set(requests.get() + 1);
+    }
+}
+
+impl fmt::Debug for ConnectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionStats")
+            .field("protocol", &self.protocol())
+            .field("peer_addr", &self.peer_addr())
+            .field("tls", &self.tls())
+            .field("duration", &self.duration())
+            .field("bytes_read", &self.bytes_read())
+            .field("bytes_written", &self.bytes_written())
+            .field("requests", &self.requests())
+            .finish()
+    }
+}
+
+/// Details of the TLS session of a connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TlsInfo {
+    /// Negotiated protocol version, e.g. `TLSv1.3`.
+    pub version: Option<String>,
+
+    /// Negotiated cipher suite.
+    pub cipher: Option<String>,
+
+    /// Protocol selected using ALPN.
+    pub alpn_protocol: Option<Vec<u8>>,
+
+    /// Server name sent by the client using SNI.
+    ///
+    /// Only recorded for connections accepted with rustls.
+    pub server_name: Option<String>,
+}
+
+/// Callback that is run with the statistics of a connection once it is closed.
+pub(crate) type DisconnectCallback = dyn Fn(&ConnectionStats);
+
+/// Connection close hook.
+#[derive(Clone)]
+pub(crate) struct OnDisconnect(pub(crate) Rc<DisconnectCallback>);
+
+impl fmt::Debug for OnDisconnect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnDisconnect")
+    }
+}
+
+/// Runs the close hook when dropped.
+///
+/// Stored in the connection data, which is dropped once the dispatcher and all requests of the
+/// connection are gone.
+pub(crate) struct DisconnectGuard {
+    stats: ConnectionStats,
+    on_disconnect: Option<OnDisconnect>,
+}
+
+impl DisconnectGuard {
+    pub(crate) fn new(stats: ConnectionStats, on_disconnect: Option<OnDisconnect>) -> Self {
+        Self {
+            stats,
+            on_disconnect,
+        }
+    }
+}
+
+impl Drop for DisconnectGuard {
+    fn drop(&mut self) {
+        if let Some(OnDisconnect(ref hook)) = self.on_disconnect {
+            hook(&self.stats);
+        }
+    }
+}
+
+/// Chain on-connect callback with one that records TLS session details for connection stats.
+///
+/// Returns the callback unchanged when connection stats are disabled.
+pub(crate) fn with_tls_info<T: 'static>(
+    config: &ServiceConfig,
+    on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    tls_info: fn(&T) -> TlsInfo,
+) -> Option<Rc<ConnectCallback<T>>> {
+    if !config.connection_stats() {
+        return on_connect_ext;
+    }
+
+    Some(Rc::new(move |io: &T, ext: &mut Extensions| {
+        if let Some(ref on_connect_ext) = on_connect_ext {
+            on_connect_ext(io, ext);
+        }
+
+        ext.insert(tls_info(io));
+    }))
+}
+
+#[cfg(feature = "openssl")]
+pub(crate) fn openssl_tls_info(
+    io: &actix_tls::accept::openssl::TlsStream<actix_rt::net::TcpStream>,
+) -> TlsInfo {
+    let ssl = io.ssl();
+
+    TlsInfo {
+        version: Some(ssl.version_str().to_owned()),
+        cipher: ssl.current_cipher().map(|cipher| cipher.name().to_owned()),
+        alpn_protocol: ssl.selected_alpn_protocol().map(ToOwned::to_owned),
+        server_name: None,
+    }
+}
+
+#[cfg(feature = "rustls")]
+pub(crate) fn rustls_tls_info(
+    io: &actix_tls::accept::rustls::TlsStream<actix_rt::net::TcpStream>,
+) -> TlsInfo {
+    let conn = io.get_ref().1;
+
+    TlsInfo {
+        version: conn
+            .protocol_version()
+            .map(|version| format!("{:?}", version)),
+        cipher: conn
+            .negotiated_cipher_suite()
+            .map(|suite| format!("{:?}", suite.suite())),
+        alpn_protocol: conn.alpn_protocol().map(ToOwned::to_owned),
+        server_name: conn.sni_hostname().map(ToOwned::to_owned),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[test]
+    fn counters() {
+        let stats = ConnectionStats::new(Protocol::Http1, None, None);
+        stats.add_read(10);
+        stats.add_read(5);
+        stats.add_written(7);
+        stats.add_request();
+        stats.set_protocol(Protocol::Http2);
+
+        let handle = stats.clone();
+        assert_eq!(handle.bytes_read(), 15);
+        assert_eq!(handle.bytes_written(), 7);
+        assert_eq!(handle.requests(), 1);
+        assert_eq!(handle.protocol(), Protocol::Http2);
+        assert!(handle.tls().is_none());
+    }
+
+    #[test]
+    fn disconnect_guard() {
+        let reported = Rc::new(RefCell::new(None));
+
+        let hook = {
+            let reported = Rc::clone(&reported);
+            OnDisconnect(Rc::new(move |stats: &ConnectionStats| {
+                *reported.borrow_mut() = Some(stats.requests());
+            }))
+        };
+
+        let stats = ConnectionStats::new(Protocol::Http1, None, None);
+        let mut ext = Extensions::new();
+        ext.insert(DisconnectGuard::new(stats.clone(), Some(hook)));
+
+        stats.add_request();
+        assert!(reported.borrow().is_none());
+
+        drop(ext);
+        assert_eq!(*reported.borrow(), Some(1));
+    }
+}
@@ -0,0 +1,296 @@
+//! Taking over HTTP/1 connections after switching protocols.
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp, fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+use bytes::{Buf as _, BytesMut};
+use futures_core::{ready, Stream as _};
+use local_channel::mpsc;
+
+use crate::{error::UpgradeError, StatusCode};
+
+/// Handle for taking over the connection of a request that may switch protocols.
+///
+/// A handle is stored in the extensions of HTTP/1 requests that use the `CONNECT` method or ask
+/// for a protocol upgrade with `Connection: upgrade`, unless the request is handled by the
+/// service's [`upgrade`](crate::HttpServiceBuilder::upgrade) service. Requests over HTTP/2 do not
+/// get a handle.
+///
+/// Calling [`on_upgrade`](Self::on_upgrade) asks the dispatcher to hand over the connection once
+/// the response to the request has been written, if that response is `101 Switching Protocols`
+/// or, for `CONNECT` requests, has a successful (2xx) status code. The response body is not sent
+/// in that case. For any other response, the connection continues to be served as HTTP/1 and
+/// the [`OnUpgrade`] future resolves with an error.
+///
+/// ```
+/// use actix_http::{
+///     body::BoxBody, upgrade::Upgrade, HttpMessage as _, Request, Response, StatusCode,
+/// };
+///
+/// fn tunnel(req: &Request) -> Response<BoxBody> {
+///     let upgrade = match req.extensions_mut().remove::<Upgrade>() {
+///         Some(upgrade) => upgrade,
+///         None => return Response::new(StatusCode::BAD_REQUEST),
+///     };
+///
+///     let on_upgrade = upgrade.on_upgrade();
+///
+///     actix_rt::spawn(async move {
+///         if let Ok(io) = on_upgrade.await {
+///             // read from and write to the raw connection
+///         }
+///     });
+///
+///     Response::new(StatusCode::OK)
+/// }
+/// ```
+pub struct Upgrade {
+    shared: Rc<Shared>,
+    rx: mpsc::Receiver<Upgraded>,
+}
+
+struct Shared {
+    connect: bool,
+    requested: Cell<bool>,
+    tx: RefCell<Option<mpsc::Sender<Upgraded>>>,
+}
+
+impl Upgrade {
+    /// Create handle for a request; `connect` is true for `CONNECT` requests.
+    pub(crate) fn new(connect: bool) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        Self {
+            shared: Rc::new(Shared {
+                connect,
+                requested: Cell::new(false),
+                tx: RefCell::new(Some(tx)),
+            }),
+            rx,
+        }
+    }
+
+    /// Returns true if the request uses the `CONNECT` method.
+    pub fn is_connect(&self) -> bool {
+        self.shared.connect
+    }
+
+    /// Ask for the connection to be handed over after the response is written.
+    ///
+    /// The returned future resolves once the switching response has been flushed. The request
+    /// body can be read until this is called; data received afterwards is left for the new
+    /// protocol.
+    pub fn on_upgrade(self) -> OnUpgrade {
+        self.shared.requested.set(true);
+        OnUpgrade { rx: self.rx }
+    }
+
+    /// Returns dispatcher side of this handle.
+    pub(crate) fn pending(&self) -> PendingUpgrade {
+        PendingUpgrade {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgrade")
+            .field("connect", &self.shared.connect)
+            .finish()
+    }
+}
+
+/// Dispatcher side of an [`Upgrade`] handle.
+pub(crate) struct PendingUpgrade {
+    shared: Rc<Shared>,
+}
+
+impl PendingUpgrade {
+    /// Returns true if the service asked to take over the connection.
+    pub(crate) fn is_requested(&self) -> bool {
+        self.shared.requested.get()
+    }
+
+    /// Returns true if a response with the given status code hands over the connection.
+    pub(crate) fn switches(&self, status: StatusCode) -> bool {
+        self.shared.requested.get()
+            && (status == StatusCode::SWITCHING_PROTOCOLS
+                || (self.shared.connect && status.is_success()))
+    }
+
+    /// Hand over connection to the waiting [`OnUpgrade`] future.
+    pub(crate) fn complete(self, upgraded: Upgraded) {
+        if let Some(tx) = self.shared.tx.borrow_mut().take() {
+            let _ = tx.send(upgraded);
+        }
+    }
+}
+
+impl Drop for PendingUpgrade {
+    fn drop(&mut self) {
+        // resolve waiting future with an error when the connection is not handed over
+        self.shared.tx.borrow_mut().take();
+    }
+}
+
+/// Future that resolves to the connection once it has been handed over.
+///
+/// Created with [`Upgrade::on_upgrade`].
+pub struct OnUpgrade {
+    rx: mpsc::Receiver<Upgraded>,
+}
+
+impl fmt::Debug for OnUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OnUpgrade")
+    }
+}
+
+impl Future for OnUpgrade {
+    type Output = Result<Upgraded, UpgradeError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match ready!(Pin::new(&mut self.rx).poll_next(cx)) {
+            Some(upgraded) => Poll::Ready(Ok(upgraded)),
+            None => Poll::Ready(Err(UpgradeError::NotUpgraded)),
+        }
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
+
+/// Connection taken over after switching protocols.
+///
+/// Reads return bytes that were received after the request head and not consumed by the
+/// dispatcher first, then continue with the underlying I/O.
+pub struct Upgraded {
+    io: Box<dyn Io>,
+    read_buf: BytesMut,
+}
+
+impl Upgraded {
+    pub(crate) fn new<T>(io: T, read_buf: BytesMut) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Unpin + 'static,
+    {
+        Self {
+            io: Box::new(io),
+            read_buf,
+        }
+    }
+
+    /// Returns bytes received from the client that have not been read yet.
+    pub fn read_buf(&self) -> &[u8] {
+        &self.read_buf
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgraded")
+            .field("read_buf", &self.read_buf.len())
+            .finish()
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if !this.read_buf.is_empty() {
+            let n = cmp::min(buf.remaining(), this.read_buf.len());
+            buf.put_slice(&this.read_buf[..n]);
+            this.read_buf.advance(n);
+            return Poll::Ready(Ok(()));
+        }
+
+        Pin::new(&mut this.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt as _;
+
+    use super::*;
+    use crate::test::TestBuffer;
+
+    #[actix_rt::test]
+    async fn upgraded_reads_buffered_bytes_first() {
+        let upgrade = Upgrade::new(true);
+        let pending = upgrade.pending();
+        let on_upgrade = upgrade.on_upgrade();
+
+        assert!(pending.switches(StatusCode::OK));
+        assert!(!pending.switches(StatusCode::FORBIDDEN));
+
+        let io = TestBuffer::new("world");
+        pending.complete(Upgraded::new(io, BytesMut::from(&b"hello "[..])));
+
+        let mut io = on_upgrade.await.unwrap();
+        assert_eq!(io.read_buf(), b"hello ");
+
+        let mut data = [0; 11];
+        io.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"hello world");
+    }
+
+    #[actix_rt::test]
+    async fn not_requested() {
+        let upgrade = Upgrade::new(false);
+        let pending = upgrade.pending();
+        assert!(!pending.switches(StatusCode::SWITCHING_PROTOCOLS));
+
+        let on_upgrade = upgrade.on_upgrade();
+        assert!(pending.switches(StatusCode::SWITCHING_PROTOCOLS));
+        assert!(!pending.switches(StatusCode::OK));
+
+        drop(pending);
+        assert_eq!(on_upgrade.await.unwrap_err(), UpgradeError::NotUpgraded);
+    }
+}
@@ -150,29 +150,38 @@ pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
 }
 
 /// Verify WebSocket handshake request.
+///
+/// Accepts HTTP/1.1 `Upgrade` requests as well as HTTP/2 extended CONNECT requests (RFC 8441).
 pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
-    // WebSocket accepts only GET
-    if req.method != Method::GET {
-        return Err(HandshakeError::GetMethodRequired);
-    }
+    if let Some(protocol) = extended_connect_protocol(req) {
+        // `:protocol` pseudo-header takes the place of `Upgrade` and `Connection` headers
+        if !protocol.eq_ignore_ascii_case("websocket") {
+            return Err(HandshakeError::NoWebsocketUpgrade);
+        }
+    } else {
+        // WebSocket accepts only GET
+        if req.method != Method::GET {
+            return Err(HandshakeError::GetMethodRequired);
+        }
 
-    // Check for "UPGRADE" to WebSocket header
-    let has_hdr = if let Some(hdr) = req.headers().get(header::UPGRADE) {
-        if let Ok(s) = hdr.to_str() {
-            s.to_ascii_lowercase().contains("websocket")
+        // Check for "UPGRADE" to WebSocket header
+        let has_hdr = if let Some(hdr) = req.headers().get(header::UPGRADE) {
+            if let Ok(s) = hdr.to_str() {
+                s.to_ascii_lowercase().contains("websocket")
+            } else {
+                false
+            }
         } else {
             false
+        };
+        if !has_hdr {
+            return Err(HandshakeError::NoWebsocketUpgrade);
         }
-    } else {
-        false
-    };
-    if !has_hdr {
-        return Err(HandshakeError::NoWebsocketUpgrade);
-    }
 
-    // Upgrade connection
-    if !req.upgrade() {
-        return Err(HandshakeError::NoConnectionUpgrade);
+        // Upgrade connection
+        if !req.upgrade() {
+            return Err(HandshakeError::NoConnectionUpgrade);
+        }
     }
 
     // check supported version
@@ -190,8 +199,10 @@ pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
         return Err(HandshakeError::UnsupportedVersion);
     }
 
-    // check client handshake for validity
-    if !req.headers().contains_key(header::SEC_WEBSOCKET_KEY) {
+    // check client handshake for validity; there is no key exchange over HTTP/2
+    if extended_connect_protocol(req).is_none()
+        && !req.headers().contains_key(header::SEC_WEBSOCKET_KEY)
+    {
         return Err(HandshakeError::BadWebsocketKey);
     }
     Ok(())
@@ -199,8 +210,13 @@ pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
 
 /// Create WebSocket handshake response.
 ///
-/// This function returns handshake `Response`, ready to send to peer.
+/// This function returns handshake `Response`, ready to send to peer. HTTP/2 extended CONNECT
+/// requests are answered with `200 OK`, after which the stream carries WebSocket frames.
 pub fn handshake_response(req: &RequestHead) -> ResponseBuilder {
+    if extended_connect_protocol(req).is_some() {
+        return Response::build(StatusCode::OK);
+    }
+
     let key = {
         let key = req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap();
         proto::hash_key(key.as_ref())
@@ -216,6 +232,15 @@ pub fn handshake_response(req: &RequestHead) -> ResponseBuilder {
         .take()
 }
 
+/// Returns `:protocol` of HTTP/2 extended CONNECT request.
+fn extended_connect_protocol(req: &RequestHead) -> Option<&str> {
+    if req.method == Method::CONNECT {
+        req.protocol()
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{header, Method};
@@ -333,6 +358,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_handshake_extended_connect() {
+        let mut req = TestRequest::default()
+            .method(Method::CONNECT)
+            .version(crate::Version::HTTP_2)
+            .finish();
+        req.head_mut().set_protocol(Some("webtransport"));
+        assert_eq!(
+            HandshakeError::NoWebsocketUpgrade,
+            verify_handshake(req.head()).unwrap_err(),
+        );
+
+        req.head_mut().set_protocol(Some("websocket"));
+        assert_eq!(
+            HandshakeError::NoVersionHeader,
+            verify_handshake(req.head()).unwrap_err(),
+        );
+
+        req.headers_mut().insert(
+            header::SEC_WEBSOCKET_VERSION,
+            header::HeaderValue::from_static("13"),
+        );
+        assert!(verify_handshake(req.head()).is_ok());
+
+        let res = handshake(req.head()).unwrap().finish();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::SEC_WEBSOCKET_ACCEPT));
+        assert!(!res.headers().contains_key(header::UPGRADE));
+
+        // CONNECT without protocol is not a WebSocket handshake
+        req.head_mut().set_protocol(None);
+        assert_eq!(
+            HandshakeError::GetMethodRequired,
+            verify_handshake(req.head()).unwrap_err(),
+        );
+    }
+
     #[test]
     fn test_ws_error_http_response() {
         let resp: Response<BoxBody> = HandshakeError::GetMethodRequired.into();
@@ -0,0 +1,181 @@
+#![cfg(feature = "http3")]
+
+extern crate tls_rustls as rustls;
+
+use std::{net::SocketAddr, rc::Rc, sync::Arc};
+
+use actix_http::{
+    error::ParseError,
+    h3::{reexports::Endpoint, ALPN},
+    Error, HttpMessage as _, HttpServiceBuilder, Request, Response, StatusCode, Version,
+};
+use actix_service::{Service as _, ServiceFactory as _};
+use actix_utils::future::ready;
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_util::StreamExt as _;
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig};
+
+struct TestCert {
+    der: Vec<u8>,
+    key: Vec<u8>,
+}
+
+fn test_cert() -> TestCert {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+
+    TestCert {
+        der: cert.serialize_der().unwrap(),
+        key: cert.serialize_private_key_der(),
+    }
+}
+
+fn server_config(cert: &TestCert) -> quinn::ServerConfig {
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(cert.der.clone())],
+            PrivateKey(cert.key.clone()),
+        )
+        .unwrap();
+    config.alpn_protocols = vec![ALPN.to_vec()];
+
+    quinn::ServerConfig::with_crypto(Arc::new(config))
+}
+
+fn client_endpoint(cert: &TestCert) -> Endpoint {
+    let mut roots = RootCertStore::empty();
+    roots.add(&Certificate(cert.der.clone())).unwrap();
+
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = vec![ALPN.to_vec()];
+
+    let mut endpoint = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(config)));
+    endpoint
+}
+
+/// Start HTTP/3 server on a loopback UDP port and return its address.
+async fn start_server<F, Fut>(cert: &TestCert, handler: F) -> SocketAddr
+where
+    F: Fn(Request) -> Fut + Clone + 'static,
+    Fut: std::future::Future<Output = Result<Response<Bytes>, Error>> + 'static,
+{
+    let endpoint =
+        Endpoint::server(server_config(cert), "127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = endpoint.local_addr().unwrap();
+
+    let factory = HttpServiceBuilder::<(), _>::new().h3(handler);
+    let srv = factory.new_service(()).await.unwrap();
+
+    actix_rt::spawn(async move {
+        let srv = Rc::new(srv);
+
+        while let Some(conn) = endpoint.accept().await {
+            let srv = srv.clone();
+            actix_rt::spawn(async move {
+                let _ = srv.call(conn).await;
+            });
+        }
+    });
+
+    addr
+}
+
+async fn send(
+    cert: &TestCert,
+    addr: SocketAddr,
+    req: http::Request<()>,
+    body: Option<Bytes>,
+) -> (http::response::Parts, Bytes) {
+    let endpoint = client_endpoint(cert);
+    let conn = endpoint.connect(addr, "localhost").unwrap().await.unwrap();
+
+    let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(conn))
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = futures_util::future::poll_fn(|cx| driver.poll_close(cx)).await;
+    });
+
+    let mut stream = send_request.send_request(req).await.unwrap();
+    if let Some(body) = body {
+        stream.send_data(body).await.unwrap();
+    }
+    stream.finish().await.unwrap();
+
+    let res = stream.recv_response().await.unwrap();
+
+    let mut body = BytesMut::new();
+    while let Some(mut chunk) = stream.recv_data().await.unwrap() {
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+
+    (res.into_parts().0, body.freeze())
+}
+
+#[actix_rt::test]
+async fn h3_get() {
+    let cert = test_cert();
+
+    let addr = start_server(&cert, |req: Request| {
+        assert_eq!(req.version(), Version::HTTP_3);
+        ready(Ok::<_, Error>(Response::with_body(
+            StatusCode::OK,
+            Bytes::from_static(b"hello h3"),
+        )))
+    })
+    .await;
+
+    let req = http::Request::get("https://localhost/").body(()).unwrap();
+    let (res, body) = send(&cert, addr, req, None).await;
+
+    assert_eq!(res.status, http::StatusCode::OK);
+    assert_eq!(res.version, http::Version::HTTP_3);
+    assert_eq!(res.headers["content-length"], "8");
+    assert!(res.headers.contains_key("date"));
+    assert_eq!(body, Bytes::from_static(b"hello h3"));
+}
+
+#[actix_rt::test]
+async fn h3_echo_body() {
+    let cert = test_cert();
+
+    let addr = start_server(&cert, |mut req: Request| async move {
+        let mut pl = req.take_payload();
+        let mut body = BytesMut::new();
+
+        while let Some(chunk) = pl.next().await {
+            body.extend_from_slice(&chunk?);
+        }
+
+        Ok::<_, Error>(Response::with_body(StatusCode::OK, body.freeze()))
+    })
+    .await;
+
+    let req = http::Request::post("https://localhost/echo")
+        .body(())
+        .unwrap();
+    let (res, body) = send(&cert, addr, req, Some(Bytes::from_static(b"ping"))).await;
+
+    assert_eq!(res.status, http::StatusCode::OK);
+    assert_eq!(body, Bytes::from_static(b"ping"));
+}
+
+#[actix_rt::test]
+async fn h3_service_error_response() {
+    let cert = test_cert();
+
+    let addr = start_server(&cert, |_| {
+        ready(Err::<Response<Bytes>, Error>(ParseError::Method.into()))
+    })
+    .await;
+
+    let req = http::Request::get("https://localhost/").body(()).unwrap();
+    let (res, _) = send(&cert, addr, req, None).await;
+
+    assert_eq!(res.status, http::StatusCode::BAD_REQUEST);
+}
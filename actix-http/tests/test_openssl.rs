@@ -2,13 +2,13 @@
 
 extern crate tls_openssl as openssl;
 
-use std::{convert::Infallible, io};
+use std::{convert::Infallible, io, time::Duration};
 
 use actix_http::{
     body::{BodyStream, BoxBody, SizedStream},
     error::PayloadError,
     header::{self, HeaderValue},
-    Error, HttpService, Method, Request, Response, StatusCode, Version,
+    Error, HttpService, Method, Request, Response, ShutdownSignal, StatusCode, Version,
 };
 use actix_http_test::test_server;
 use actix_service::{fn_service, ServiceFactoryExt};
@@ -99,6 +99,26 @@ async fn test_h2_1() -> io::Result<()> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_h2_alt_svc() -> io::Result<()> {
+    let srv = test_server(move || {
+        HttpService::build()
+            .alt_svc(HeaderValue::from_static("h3=\":443\"; ma=86400"))
+            .h2(|_| ok::<_, Error>(Response::ok()))
+            .openssl(tls_config())
+            .map_err(|_| ())
+    })
+    .await;
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert_eq!(response.version(), Version::HTTP_2);
+    assert_eq!(
+        response.headers().get(header::ALT_SVC).unwrap(),
+        "h3=\":443\"; ma=86400"
+    );
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_h2_body() -> io::Result<()> {
     let data = "HELLOWORLD".to_owned().repeat(64 * 1024); // 640 KiB
@@ -121,6 +141,74 @@ async fn test_h2_body() -> io::Result<()> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_h2_custom_settings() -> io::Result<()> {
+    // large body round trip; effect of each setting is checked in `test_h2_settings_applied`
+    let data = "HELLOWORLD".to_owned().repeat(64 * 1024); // 640 KiB
+    let mut srv = test_server(move || {
+        HttpService::build()
+            .h2_max_concurrent_streams(10)
+            .h2_initial_window_size(1024 * 1024)
+            .h2_initial_connection_window_size(2 * 1024 * 1024)
+            .h2_max_frame_size(32 * 1024)
+            .h2_max_header_list_size(16 * 1024)
+            .h2_max_concurrent_reset_streams(5)
+            .h2_reset_stream_duration(Duration::from_secs(5))
+            .h2_max_pending_accept_reset_streams(10)
+            .h2_max_local_error_reset_streams(100)
+            .h2(|mut req: Request<_>| async move {
+                let body = load_body(req.take_payload()).await?;
+                Ok::<_, Error>(Response::ok().set_body(body))
+            })
+            .openssl(tls_config())
+            .map_err(|_| ())
+    })
+    .await;
+
+    let response = srv.sget("/").send_body(data.clone()).await.unwrap();
+    assert!(response.status().is_success());
+
+    let body = srv.load_body(response).await.unwrap();
+    assert_eq!(&body, data.as_bytes());
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_h2_graceful_shutdown() -> io::Result<()> {
+    let signal = ShutdownSignal::new();
+
+    let mut srv = test_server({
+        let signal = signal.clone();
+
+        move || {
+            let signal = signal.clone();
+
+            HttpService::build()
+                .shutdown_signal(signal.clone())
+                .h2(move |_| {
+                    // server starts shutting down while request is in-flight
+                    signal.trigger();
+
+                    async {
+                        actix_rt::time::sleep(Duration::from_millis(100)).await;
+                        Ok::<_, Error>(Response::ok().set_body("in-flight"))
+                    }
+                })
+                .openssl(tls_config())
+                .map_err(|_| ())
+        }
+    })
+    .await;
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(signal.is_triggered());
+
+    let body = srv.load_body(response).await.unwrap();
+    assert_eq!(&body, &b"in-flight"[..]);
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_h2_content_length() {
     let srv = test_server(move || {
@@ -13,7 +13,8 @@ use actix_http::{
     body::{BodyStream, BoxBody, SizedStream},
     error::PayloadError,
     header::{self, HeaderName, HeaderValue},
-    Error, HttpService, Method, Request, Response, StatusCode, Version,
+    ConnectionStats, Error, HttpService, Method, Protocol, Request, Response, StatusCode,
+    Version,
 };
 use actix_http_test::test_server;
 use actix_service::{fn_factory_with_config, fn_service};
@@ -156,6 +157,33 @@ async fn test_h2_1() -> io::Result<()> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_h2_connection_stats() -> io::Result<()> {
+    let srv = test_server(move || {
+        HttpService::build()
+            .connection_stats(true)
+            .finish(|req: Request| {
+                let stats = req.conn_data::<ConnectionStats>().unwrap();
+                assert_eq!(stats.protocol(), Protocol::Http2);
+                assert_eq!(stats.requests(), 1);
+                assert!(stats.bytes_read() > 0);
+
+                let tls = stats.tls().unwrap();
+                assert!(tls.version.is_some());
+                assert!(tls.cipher.is_some());
+                assert_eq!(tls.alpn_protocol.as_deref(), Some(&b"h2"[..]));
+
+                ok::<_, Error>(Response::ok())
+            })
+            .rustls(tls_config())
+    })
+    .await;
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_h2_body1() -> io::Result<()> {
     let data = "HELLOWORLD".to_owned().repeat(64 * 1024);
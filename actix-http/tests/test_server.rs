@@ -1,13 +1,22 @@
 use std::{
+    cell::RefCell,
     convert::Infallible,
-    io::{Read, Write},
-    net, thread,
+    io::{self, Read, Write},
+    net,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    thread,
     time::Duration,
 };
 
+use actix_codec::{AsyncRead, AsyncWrite, ReadBuf};
+
+use actix_http::upgrade::Upgrade;
 use actix_http::{
     body::{self, BodyStream, BoxBody, SizedStream},
-    header, Error, HttpService, KeepAlive, Request, Response, StatusCode,
+    header, ConnectionStats, Error, HttpMessage as _, HttpService, InformationalSender,
+    KeepAlive, Protocol, Request, Response, StatusCode,
 };
 use actix_http_test::test_server;
 use actix_rt::time::sleep;
@@ -42,6 +51,36 @@ async fn test_h1() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_h1_alt_svc() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .alt_svc(header::HeaderValue::from_static("h3=\":443\"; ma=86400"))
+            .h1(|req: Request| {
+                let mut res = Response::ok();
+                if req.path() == "/custom" {
+                    res.headers_mut()
+                        .insert(header::ALT_SVC, header::HeaderValue::from_static("clear"));
+                }
+                ok::<_, Infallible>(res)
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert_eq!(
+        response.headers().get(header::ALT_SVC).unwrap(),
+        "h3=\":443\"; ma=86400"
+    );
+
+    // header set by service is preserved
+    let response = srv.get("/custom").send().await.unwrap();
+    assert_eq!(response.headers().get(header::ALT_SVC).unwrap(), "clear");
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn test_h1_2() {
     let mut srv = test_server(|| {
@@ -280,6 +319,866 @@ async fn test_http1_keepalive_timeout() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_h2c_prior_knowledge() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .finish(|req: Request| {
+                assert_eq!(req.version(), http::Version::HTTP_2);
+                ok::<_, Infallible>(Response::ok().set_body("h2c"))
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let req = http::Request::get(srv.url("/")).body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+    let res = res.await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+
+    let body = res.into_body().data().await.unwrap().unwrap();
+    assert_eq!(body, Bytes::from_static(b"h2c"));
+}
+
+#[actix_rt::test]
+async fn test_h2_settings_applied() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .h2_max_concurrent_streams(1)
+            .h2_initial_window_size(1024 * 1024)
+            .h2_initial_connection_window_size(2 * 1024 * 1024)
+            // request bodies are never read so that flow control windows stay open
+            .finish(|_| {
+                futures_util::future::pending::<Result<Response<BoxBody>, Infallible>>()
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::Builder::new()
+        .max_send_buffer_size(4 * 1024 * 1024)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let req = http::Request::post(srv.url("/")).body(()).unwrap();
+    let (_res, mut stream) = client.send_request(req, false).unwrap();
+
+    // send capacity grows up to the stream window advertised by the server
+    stream.reserve_capacity(4 * 1024 * 1024);
+    actix_rt::time::timeout(Duration::from_secs(5), async {
+        while stream.capacity() < 1024 * 1024 {
+            futures_util::future::poll_fn(|cx| stream.poll_capacity(cx))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(stream.capacity(), 1024 * 1024);
+
+    // a second stream is queued by the client while the first one is active
+    let mut client = client.ready().await.unwrap();
+    let req = http::Request::get(srv.url("/")).body(()).unwrap();
+    let _res = client.send_request(req, true).unwrap();
+    let ready = actix_rt::time::timeout(Duration::from_millis(200), client.ready()).await;
+    assert!(ready.is_err());
+}
+
+#[actix_rt::test]
+async fn test_h2_service_settings() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2(|_| futures_util::future::pending::<Result<Response<BoxBody>, Infallible>>())
+            .initial_window_size(1024 * 1024)
+            .initial_connection_window_size(2 * 1024 * 1024)
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::Builder::new()
+        .max_send_buffer_size(4 * 1024 * 1024)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let mut client = client.ready().await.unwrap();
+    let req = http::Request::post(srv.url("/")).body(()).unwrap();
+    let (_res, mut stream) = client.send_request(req, false).unwrap();
+
+    stream.reserve_capacity(4 * 1024 * 1024);
+    actix_rt::time::timeout(Duration::from_secs(5), async {
+        while stream.capacity() < 1024 * 1024 {
+            futures_util::future::poll_fn(|cx| stream.poll_capacity(cx))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(stream.capacity(), 1024 * 1024);
+}
+
+#[actix_rt::test]
+async fn test_h1_strict() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1_strict(true)
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let send = |req: &[u8]| {
+        let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let _ = stream.write_all(req);
+        let mut data = vec![0; 1024];
+        let n = stream.read(&mut data).unwrap();
+        data.truncate(n);
+        String::from_utf8(data).unwrap()
+    };
+
+    let res = send(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello");
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    let res = send(
+        b"POST / HTTP/1.1\r\nContent-Length: 6\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nX",
+    );
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let res = send(b"GET / HTTP/1.1\r\nX-Foo: bar\r\n baz\r\n\r\n");
+    assert!(res.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_header_read_timeout() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1_header_read_timeout(Duration::from_millis(200))
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // first request is served
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // head of second request is never completed
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nHost: exam");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_min_body_rate() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1_min_body_rate(10, Duration::from_millis(200))
+            .h1(|req: Request| async move {
+                let body = body::to_bytes(BodyStream::new(req.into_parts().1)).await;
+                match body {
+                    Ok(body) => Ok::<_, Infallible>(Response::with_body(StatusCode::OK, body)),
+                    Err(_) => Ok(Response::bad_request().set_body(Bytes::new())),
+                }
+            })
+            .tcp()
+    })
+    .await;
+
+    // complete body
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 20\r\n\r\n0123456789");
+    sleep(Duration::from_millis(50)).await;
+    let _ = stream.write_all(b"0123456789");
+
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(data[..n].ends_with(b"01234567890123456789"));
+
+    // body trickles in slower than the minimum rate
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 20\r\n\r\n01234");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    assert!(data.contains("connection: close\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h1_min_body_rate_keep_alive() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1_min_body_rate(10, Duration::from_millis(400))
+            .h1(|req: Request| async move {
+                let body = body::to_bytes(BodyStream::new(req.into_parts().1)).await;
+                match body {
+                    Ok(body) => Ok::<_, Infallible>(Response::with_body(StatusCode::OK, body)),
+                    Err(_) => Ok(Response::bad_request().set_body(Bytes::new())),
+                }
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 20\r\n\r\n0123456789");
+    sleep(Duration::from_millis(300)).await;
+
+    // second body starts its own data rate period
+    let _ = stream.write_all(b"abcdefghijPOST / HTTP/1.1\r\nContent-Length: 20\r\n\r\n01234");
+    sleep(Duration::from_millis(200)).await;
+    let _ = stream.write_all(b"56789abcdefghij");
+
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    loop {
+        let res = String::from_utf8_lossy(&data[..n]);
+        if res.matches("0123456789abcdefghij").count() == 2 || res.contains("408") {
+            break;
+        }
+        match stream.read(&mut data[n..]).unwrap() {
+            0 => break,
+            read => n += read,
+        }
+    }
+    let res = String::from_utf8_lossy(&data[..n]);
+    assert_eq!(res.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+}
+
+#[actix_rt::test]
+async fn test_h1_max_requests_per_conn() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .max_requests_per_conn(2)
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(REQ);
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    let res = String::from_utf8_lossy(&data[..n]);
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(!res.contains("connection: close\r\n"));
+
+    // last allowed request is answered with `Connection: close`; pipelined rest is ignored
+    let _ = stream.write_all(&[REQ, REQ].concat());
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.contains("connection: close\r\n"));
+    assert_eq!(data.matches("HTTP/1.1 200 OK").count(), 1);
+}
+
+/// Service that answers with given status and echoes data sent through the taken over connection.
+async fn echo_tunnel(
+    mut req: Request,
+    status: StatusCode,
+) -> Result<Response<BoxBody>, Infallible> {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    let upgrade = match req.extensions_mut().remove::<Upgrade>() {
+        Some(upgrade) => upgrade,
+        None => return Ok(Response::new(StatusCode::BAD_REQUEST)),
+    };
+
+    let on_upgrade = upgrade.on_upgrade();
+
+    actix_rt::spawn(async move {
+        let mut io = on_upgrade.await.unwrap();
+        let mut buf = [0; 1024];
+
+        loop {
+            match io.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => io.write_all(&buf[..n]).await.unwrap(),
+            }
+        }
+    });
+
+    Ok(Response::new(status))
+}
+
+#[actix_rt::test]
+async fn test_h1_connect_tunnel() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                assert!(req.extensions().get::<Upgrade>().unwrap().is_connect());
+                echo_tunnel(req, StatusCode::OK)
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // data sent right after the request head is not lost
+    let _ = stream
+        .write_all(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\nping");
+
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    while !data[..n].ends_with(b"ping") {
+        n += stream.read(&mut data[n..]).unwrap();
+    }
+    let res = String::from_utf8_lossy(&data[..n]);
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(!res.contains("content-length"));
+    assert!(res.ends_with("\r\n\r\nping"));
+
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    while n < 18 {
+        n += stream.read(&mut data[n..]).unwrap();
+    }
+    assert_eq!(&data[..n], b"GET / HTTP/1.1\r\n\r\n");
+}
+
+#[actix_rt::test]
+async fn test_h1_connect_reads_body() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|mut req: Request| async move {
+                // body is streamed until the connection is taken over
+                let mut payload = req.take_payload();
+                let mut body = Vec::new();
+                while body.len() < 5 {
+                    body.extend_from_slice(&payload.next().await.unwrap().unwrap());
+                }
+                assert_eq!(body, b"hello");
+
+                echo_tunnel(req, StatusCode::OK).await
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ =
+        stream.write_all(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n");
+    thread::sleep(Duration::from_millis(100));
+    let _ = stream.write_all(b"hello");
+
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    while !data[..n].ends_with(b"\r\n\r\n") {
+        n += stream.read(&mut data[n..]).unwrap();
+    }
+    assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    let _ = stream.write_all(b"ping");
+    let mut data = vec![0; 4];
+    stream.read_exact(&mut data).unwrap();
+    assert_eq!(&data, b"ping");
+}
+
+#[actix_rt::test]
+async fn test_h1_upgrade_tunnel() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let status = if req.path() == "/switch" {
+                    StatusCode::SWITCHING_PROTOCOLS
+                } else {
+                    StatusCode::OK
+                };
+
+                echo_tunnel(req, status)
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // response that does not switch protocols keeps serving HTTP/1
+    let _ = stream.write_all(
+        b"GET / HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: echo\r\n\r\n\
+        GET /switch HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: echo\r\n\r\n",
+    );
+
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    while !String::from_utf8_lossy(&data[..n]).contains("101 Switching Protocols") {
+        n += stream.read(&mut data[n..]).unwrap();
+    }
+    let res = String::from_utf8_lossy(&data[..n]);
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    let _ = stream.write_all(b"echo");
+    let mut data = vec![0; 4];
+    stream.read_exact(&mut data).unwrap();
+    assert_eq!(data, b"echo");
+}
+
+#[actix_rt::test]
+async fn test_h1_upgrade_tunnel_pipelined() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| async move {
+                if req.path() == "/slow" {
+                    sleep(Duration::from_millis(100)).await;
+                    return Ok(Response::ok());
+                }
+
+                echo_tunnel(req, StatusCode::SWITCHING_PROTOCOLS).await
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // upgrade request queued behind one still being handled is handed over too
+    let _ = stream.write_all(
+        b"GET /slow HTTP/1.1\r\n\r\n\
+        GET /switch HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: echo\r\n\r\n",
+    );
+
+    let mut data = vec![0; 1024];
+    let mut n = 0;
+    while !String::from_utf8_lossy(&data[..n]).contains("101 Switching Protocols")
+        || !data[..n].ends_with(b"\r\n\r\n")
+    {
+        let read = stream.read(&mut data[n..]).unwrap();
+        assert_ne!(read, 0, "connection closed before switching protocols");
+        n += read;
+    }
+
+    let _ = stream.write_all(b"echo");
+    let mut data = vec![0; 4];
+    stream.read_exact(&mut data).unwrap();
+    assert_eq!(data, b"echo");
+}
+
+#[cfg(target_os = "linux")]
+#[actix_rt::test]
+async fn test_h1_sendfile() {
+    use actix_http::SendFile;
+
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let file = std::fs::File::open("Cargo.toml").unwrap();
+
+                // body differs from the file so that the test can tell which one was sent
+                let mut res = if req.path() == "/stream" {
+                    let body = once(ok::<_, Error>(Bytes::from(vec![b'x'; 100])));
+                    Response::ok().set_body(BoxBody::new(BodyStream::new(body)))
+                } else {
+                    Response::ok().set_body(BoxBody::new(vec![b'x'; 100]))
+                };
+                res.extensions_mut().insert(SendFile::new(file, 10, 100));
+
+                ok::<_, Infallible>(res)
+            })
+            .tcp()
+    })
+    .await;
+
+    let expected = std::fs::read("Cargo.toml").unwrap();
+
+    let res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+    let bytes = srv.load_body(res).await.unwrap();
+    assert_eq!(bytes, &expected[10..110]);
+
+    // body size does not match file region; body is sent
+    let res = srv.get("/stream").send().await.unwrap();
+    let bytes = srv.load_body(res).await.unwrap();
+    assert_eq!(bytes, vec![b'x'; 100]);
+
+    // connection is reused after file data
+    let res = srv.head("/").send().await.unwrap();
+    assert_eq!(res.headers().get(header::CONTENT_LENGTH).unwrap(), "100");
+    let res = srv.get("/").send().await.unwrap();
+    let bytes = srv.load_body(res).await.unwrap();
+    assert_eq!(bytes, &expected[10..110]);
+}
+
+#[actix_rt::test]
+async fn test_h1_conn_lifetime() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .keep_alive(KeepAlive::Timeout(30))
+            .conn_lifetime(Duration::from_millis(200))
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // idle connection is closed once it is too old
+    let n = stream.read(&mut data).unwrap();
+    assert_eq!(n, 0);
+}
+
+#[actix_rt::test]
+async fn test_h2_max_requests_per_conn() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .max_requests_per_conn(2)
+            .finish(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    let conn = actix_rt::spawn(conn);
+
+    for _ in 0..2 {
+        let req = http::Request::get(srv.url("/")).body(()).unwrap();
+        let (res, _) = client.send_request(req, true).unwrap();
+        assert_eq!(res.await.unwrap().status(), http::StatusCode::OK);
+    }
+
+    // server sends GOAWAY and closes the connection
+    actix_rt::time::timeout(Duration::from_secs(5), conn)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn test_h1_connection_stats() {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let srv = test_server(move || {
+        let tx = tx.clone();
+
+        HttpService::build()
+            .on_disconnect(move |stats: &ConnectionStats| {
+                let _ = tx.send((
+                    stats.protocol(),
+                    stats.requests(),
+                    stats.bytes_read(),
+                    stats.bytes_written(),
+                ));
+            })
+            .h1(|req: Request| {
+                let stats = req.conn_data::<ConnectionStats>().unwrap();
+                assert!(stats.tls().is_none());
+                assert!(stats.peer_addr().is_some());
+                let body = format!("{}", stats.requests());
+                ok::<_, Infallible>(Response::ok().set_body(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let mut written = 0;
+
+    for expected in ["1", "2"] {
+        let _ = stream.write_all(REQ);
+        let mut data = vec![0; 1024];
+        let n = stream.read(&mut data).unwrap();
+        assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(data[..n].ends_with(expected.as_bytes()));
+        written += n as u64;
+    }
+
+    drop(stream);
+
+    let (protocol, requests, read, write) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(protocol, Protocol::Http1);
+    assert_eq!(requests, 2);
+    assert_eq!(read, 2 * REQ.len() as u64);
+    assert_eq!(write, written);
+}
+
+/// Service that sends `103 Early Hints` before answering after a short delay.
+async fn early_hints_service(req: Request) -> Result<Response<&'static str>, Infallible> {
+    let informational = req
+        .extensions()
+        .get::<InformationalSender>()
+        .cloned()
+        .unwrap();
+
+    informational
+        .send_early_hints(vec![header::HeaderValue::from_static(
+            "</style.css>; rel=preload; as=style",
+        )])
+        .unwrap();
+
+    sleep(Duration::from_millis(50)).await;
+
+    Ok(Response::ok().set_body("done"))
+}
+
+#[actix_rt::test]
+async fn test_h1_early_hints() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(fn_service(early_hints_service))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+
+    assert!(data.starts_with(
+        "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style\r\n\r\nHTTP/1.1 200 OK\r\n"
+    ));
+    assert!(data.ends_with("\r\n\r\ndone"));
+
+    // HTTP/1.0 clients only get the final response
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let _ = stream.write_all(b"GET / HTTP/1.0\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+
+    assert!(data.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(!data.contains("103"));
+    assert!(data.ends_with("\r\n\r\ndone"));
+}
+
+/// Stream that records all bytes read from it.
+struct RecordingStream {
+    io: actix_rt::net::TcpStream,
+    read: Rc<RefCell<Vec<u8>>>,
+}
+
+impl AsyncRead for RecordingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let res = Pin::new(&mut self.io).poll_read(cx, buf);
+        self.read
+            .borrow_mut()
+            .extend_from_slice(&buf.filled()[filled..]);
+        res
+    }
+}
+
+impl AsyncWrite for RecordingStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+#[actix_rt::test]
+async fn test_h2_early_hints() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .finish(fn_service(early_hints_service))
+            .tcp()
+    })
+    .await;
+
+    let read = Rc::new(RefCell::new(Vec::new()));
+    let io = RecordingStream {
+        io: actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap(),
+        read: Rc::clone(&read),
+    };
+
+    // shrinking the HPACK dynamic table must be signalled by the first header block that follows
+    let (mut client, conn) = h2::client::Builder::new()
+        .header_table_size(0)
+        .handshake::<_, Bytes>(io)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    // the h2 client skips informational responses; check they were received intact
+    for _ in 0..2 {
+        let req = http::Request::get(srv.url("/")).body(()).unwrap();
+        let (res, _) = client.send_request(req, true).unwrap();
+        let res = res.await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let body = res.into_body().data().await.unwrap().unwrap();
+        assert_eq!(body, Bytes::from_static(b"done"));
+    }
+
+    let read = read.borrow();
+    let hints = read
+        .windows(11)
+        .filter(|window| window == b":status\x03103")
+        .count();
+    assert_eq!(hints, 2);
+
+    // first informational response is sent ahead of any other header block
+    let resized = read
+        .windows(14)
+        .filter(|window| window == b"\x20\x00\x07:status\x03103")
+        .count();
+    assert_eq!(resized, 1);
+}
+
+#[actix_rt::test]
+async fn test_h2c_upgrade() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .finish(|req: Request| {
+                let body = format!("{:?} {}", req.version(), req.path());
+                ok::<_, Infallible>(Response::ok().set_body(body))
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // requests with a body are not upgraded
+    let _ = stream.write_all(
+        b"POST /h1 HTTP/1.1\r\nhost: localhost\r\ncontent-length: 2\r\n\
+        connection: upgrade, http2-settings\r\nupgrade: h2c\r\n\
+        http2-settings: AAMAAABkAAQAAP__\r\n\r\nhi",
+    );
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(data[..n].ends_with(b"HTTP/1.1 /h1"));
+
+    let _ = stream.write_all(
+        b"GET /h2 HTTP/1.1\r\nhost: localhost\r\n\
+        connection: upgrade, http2-settings\r\nupgrade: h2c\r\n\
+        http2-settings: AAMAAABkAAQAAP__\r\n\r\n",
+    );
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 101 Switching Protocols\r\n"));
+
+    // client connection preface followed by empty SETTINGS frame
+    let _ = stream.write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0");
+
+    // upgrade request is answered on stream 1
+    let expected = b"HTTP/2.0 /h2";
+    let mut buf = data[..n].to_vec();
+    let pos = loop {
+        if let Some(pos) = buf.windows(expected.len()).position(|w| w == expected) {
+            break pos;
+        }
+
+        let n = stream.read(&mut data).unwrap();
+        assert!(n > 0);
+        buf.extend_from_slice(&data[..n]);
+    };
+
+    // DATA frame header of stream 1 precedes body
+    assert_eq!(buf[pos - 6], 0x0);
+    assert_eq!(&buf[pos - 4..pos], &[0, 0, 0, 1]);
+}
+
 #[actix_rt::test]
 async fn test_http1_keepalive_close() {
     let mut srv = test_server(|| {
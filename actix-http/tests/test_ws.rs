@@ -2,18 +2,19 @@ use std::{
     cell::Cell,
     convert::Infallible,
     task::{Context, Poll},
+    time::Duration,
 };
 
-use actix_codec::{AsyncRead, AsyncWrite, Framed};
+use actix_codec::{AsyncRead, AsyncWrite, Decoder as _, Encoder as _, Framed};
 use actix_http::{
-    body::{BodySize, BoxBody},
-    h1,
+    body::{BodySize, BodyStream, BoxBody},
+    h1, header,
     ws::{self, CloseCode, Frame, Item, Message},
     Error, HttpService, Request, Response,
 };
 use actix_http_test::test_server;
 use actix_service::{fn_factory, Service};
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use derive_more::{Display, Error, From};
 use futures_core::future::LocalBoxFuture;
 use futures_util::{SinkExt as _, StreamExt as _};
@@ -193,3 +194,97 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, Frame::Close(Some(CloseCode::Normal.into())));
 }
+
+#[actix_rt::test]
+async fn test_h2_extended_connect() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .h2_enable_connect_protocol(true)
+            .finish(|mut req: Request| async move {
+                let mut res = ws::handshake(req.head())?;
+
+                let mut codec = ws::Codec::new();
+                let echo = req.take_payload().map(move |chunk| {
+                    let mut buf = BytesMut::from(&chunk?[..]);
+
+                    let msg = match codec.decode(&mut buf)? {
+                        Some(Frame::Text(text)) => {
+                            Message::Text(String::from_utf8_lossy(&text).into_owned().into())
+                        }
+                        _ => return Err(ws::ProtocolError::BadOpCode.into()),
+                    };
+
+                    codec.encode(msg, &mut buf)?;
+                    Ok::<_, Error>(buf.freeze())
+                });
+
+                Ok::<_, ws::HandshakeError>(res.body(BodyStream::new(echo)))
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    // server advertises extended CONNECT support in its initial SETTINGS frame
+    let mut client = client.ready().await.unwrap();
+    for _ in 0..100 {
+        if client.is_extended_connect_protocol_enabled() {
+            break;
+        }
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(client.is_extended_connect_protocol_enabled());
+
+    let req = http::Request::builder()
+        .method(http::Method::CONNECT)
+        .uri(srv.url("/"))
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .extension(h2::ext::Protocol::from("websocket"))
+        .body(())
+        .unwrap();
+    let (res, mut stream) = client.send_request(req, false).unwrap();
+    let res = res.await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::OK);
+
+    let mut codec = ws::Codec::new().client_mode();
+    let mut buf = BytesMut::new();
+    codec
+        .encode(Message::Text("text".into()), &mut buf)
+        .unwrap();
+    stream.send_data(buf.freeze(), false).unwrap();
+
+    let chunk = res.into_body().data().await.unwrap().unwrap();
+    let item = codec.decode(&mut BytesMut::from(&chunk[..])).unwrap();
+    assert_eq!(item, Some(Frame::Text(Bytes::from_static(b"text"))));
+}
+
+#[actix_rt::test]
+async fn test_h2_extended_connect_disabled() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .finish(|_| async { Ok::<_, Infallible>(Response::ok()) })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    // server SETTINGS frame is received before the first response
+    let mut client = client.ready().await.unwrap();
+    let req = http::Request::get(srv.url("/")).body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+    assert_eq!(res.await.unwrap().status(), http::StatusCode::OK);
+
+    assert!(!client.is_extended_connect_protocol_enabled());
+}
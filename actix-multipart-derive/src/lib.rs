@@ -0,0 +1,289 @@
+//! Multipart form derive macro for Actix Web.
+//!
+//! See [`macro@MultipartForm`] for usage examples.
+
+#![deny(rust_2018_idioms, nonstandard_style)]
+#![warn(future_incompatible)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned as _, Lit, Meta, NestedMeta};
+
+/// Implements `MultipartCollect` for a struct so that it can be used with the `MultipartForm`
+/// extractor.
+///
+/// # Basic Use
+/// Each field type should implement the `FieldGroupReader` trait:
+/// - `T` where `T: FieldReader`, for fields that must be present exactly once
+/// - `Option<T>`, for optional fields
+/// - `Vec<T>`, for fields that can be repeated any number of times
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct ImageUpload {
+///     description: Text<String>,
+///     timestamp: Text<i64>,
+///     image: TempFile,
+/// }
+/// ```
+///
+/// # Optional and List Fields
+/// A missing `Option` field is `None` and a missing `Vec` field is empty.
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     category: Option<Text<String>>,
+///     files: Vec<TempFile>,
+/// }
+/// ```
+///
+/// # Field Renaming
+/// Use `#[multipart(rename = "...")]` to read a field with a name that is not a valid identifier.
+///
+/// ```
+/// use actix_multipart::form::{text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     #[multipart(rename = "files[]")]
+///     files: Vec<Text<String>>,
+/// }
+/// ```
+///
+/// # Unknown Fields
+/// Fields that are not part of the struct are skipped. Use `#[multipart(deny_unknown_fields)]` to
+/// fail with `MultipartError::UnsupportedField` instead.
+///
+/// ```
+/// use actix_multipart::form::MultipartForm;
+///
+/// #[derive(MultipartForm)]
+/// #[multipart(deny_unknown_fields)]
+/// struct Form {}
+/// ```
+///
+/// # Duplicate Fields
+/// By default, a non-`Vec` field that is sent more than once fails with
+/// `MultipartError::DuplicateField`. Use `#[multipart(duplicate_field = "...")]` to change this:
+/// - `"deny"`: fail with `MultipartError::DuplicateField` (default)
+/// - `"ignore"`: keep the first value and skip the rest
+/// - `"replace"`: keep the last value
+///
+/// ```
+/// use actix_multipart::form::{text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// #[multipart(duplicate_field = "replace")]
+/// struct Form {
+///     name: Text<String>,
+/// }
+/// ```
+#[proc_macro_derive(MultipartForm, attributes(multipart))]
+pub fn impl_multipart_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Container attributes of a form struct.
+#[derive(Default)]
+struct FormAttrs {
+    deny_unknown_fields: bool,
+    duplicate_field: DuplicateField,
+}
+
+#[derive(Clone, Copy)]
+enum DuplicateField {
+    Ignore,
+    Deny,
+    Replace,
+}
+
+impl Default for DuplicateField {
+    fn default() -> Self {
+        DuplicateField::Deny
+    }
+}
+
+fn expand(input: syn::DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "MultipartForm can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let attrs = parse_form_attrs(&input.attrs)?;
+
+    let duplicate_field = match attrs.duplicate_field {
+        DuplicateField::Ignore => quote!(::actix_multipart::form::DuplicateField::Ignore),
+        DuplicateField::Deny => quote!(::actix_multipart::form::DuplicateField::Deny),
+        DuplicateField::Replace => quote!(::actix_multipart::form::DuplicateField::Replace),
+    };
+
+    let mut handle_arms = Vec::with_capacity(fields.len());
+    let mut from_state_fields = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field has identifier");
+        let ty = &field.ty;
+        let field_name = match parse_field_rename(&field.attrs)? {
+            Some(rename) => rename,
+            None => ident.to_string(),
+        };
+
+        handle_arms.push(quote! {
+            #field_name => <#ty as ::actix_multipart::form::FieldGroupReader>::handle_field(
+                req, field, limits, state, #duplicate_field,
+            ),
+        });
+
+        from_state_fields.push(quote! {
+            #ident: <#ty as ::actix_multipart::form::FieldGroupReader>::from_state(
+                #field_name, &mut state,
+            )?,
+        });
+    }
+
+    let unknown_field = if attrs.deny_unknown_fields {
+        quote! {
+            ::std::boxed::Box::pin(::std::future::ready(::std::result::Result::Err(
+                ::actix_multipart::MultipartError::UnsupportedField(field_name),
+            )))
+        }
+    } else {
+        // dropping the field skips over its contents
+        quote! {
+            ::std::boxed::Box::pin(::std::future::ready(::std::result::Result::Ok(())))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::actix_multipart::form::MultipartCollect for #name #ty_generics
+        #where_clause
+        {
+            fn handle_field<'t>(
+                req: &'t ::actix_web::HttpRequest,
+                field: ::actix_multipart::Field,
+                limits: &'t mut ::actix_multipart::form::Limits,
+                state: &'t mut ::actix_multipart::form::State,
+            ) -> ::std::pin::Pin<::std::boxed::Box<
+                dyn ::std::future::Future<
+                    Output = ::std::result::Result<(), ::actix_multipart::MultipartError>,
+                > + 't,
+            >> {
+                let field_name = field.name().to_owned();
+
+                match field_name.as_str() {
+                    #(#handle_arms)*
+                    _ => #unknown_field,
+                }
+            }
+
+            fn from_state(
+                mut state: ::actix_multipart::form::State,
+            ) -> ::std::result::Result<Self, ::actix_multipart::MultipartError> {
+                ::std::result::Result::Ok(Self {
+                    #(#from_state_fields)*
+                })
+            }
+        }
+    })
+}
+
+/// Returns the nested items of all `#[multipart(...)]` attributes.
+fn multipart_items(attrs: &[syn::Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut items = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("multipart")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => items.extend(list.nested),
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "expected attribute arguments in parentheses: #[multipart(...)]",
+                ))
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+fn parse_form_attrs(attrs: &[syn::Attribute]) -> syn::Result<FormAttrs> {
+    let mut form_attrs = FormAttrs::default();
+
+    for item in multipart_items(attrs)? {
+        match item {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("deny_unknown_fields") => {
+                form_attrs.deny_unknown_fields = true;
+            }
+
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("duplicate_field") => {
+                form_attrs.duplicate_field = match &nv.lit {
+                    Lit::Str(lit) if lit.value() == "ignore" => DuplicateField::Ignore,
+                    Lit::Str(lit) if lit.value() == "deny" => DuplicateField::Deny,
+                    Lit::Str(lit) if lit.value() == "replace" => DuplicateField::Replace,
+                    lit => {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            r#"expected one of "ignore", "deny" or "replace""#,
+                        ))
+                    }
+                };
+            }
+
+            item => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    "unknown multipart form attribute",
+                ))
+            }
+        }
+    }
+
+    Ok(form_attrs)
+}
+
+fn parse_field_rename(attrs: &[syn::Attribute]) -> syn::Result<Option<String>> {
+    let mut rename = None;
+
+    for item in multipart_items(attrs)? {
+        match item {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                match &nv.lit {
+                    Lit::Str(lit) => rename = Some(lit.value()),
+                    lit => return Err(syn::Error::new(lit.span(), "expected string literal")),
+                }
+            }
+
+            item => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    "unknown multipart field attribute",
+                ))
+            }
+        }
+    }
+
+    Ok(rename)
+}
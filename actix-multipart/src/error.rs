@@ -46,6 +46,33 @@ pub enum MultipartError {
     /// Not consumed
     #[display(fmt = "Multipart stream is not consumed")]
     NotConsumed,
+
+    /// Form field is required but was not found in the multipart stream.
+    #[display(fmt = "Field `{}` is missing", _0)]
+    #[from(ignore)]
+    MissingField(#[error(not(source))] String),
+
+    /// Form field was sent more than once but only a single value is accepted.
+    #[display(fmt = "Field `{}` is duplicated", _0)]
+    #[from(ignore)]
+    DuplicateField(#[error(not(source))] String),
+
+    /// Form field is not part of the form and unknown fields are denied.
+    #[display(fmt = "Field `{}` is not supported", _0)]
+    #[from(ignore)]
+    UnsupportedField(#[error(not(source))] String),
+
+    /// Form field contents could not be read or parsed into the expected type.
+    #[display(fmt = "Field `{}` could not be parsed: {}", name, error)]
+    #[from(ignore)]
+    FieldParse {
+        /// Name of the field.
+        name: String,
+
+        /// Description of the parse error.
+        #[error(not(source))]
+        error: String,
+    },
 }
 
 /// Return `BadRequest` for `MultipartError`
@@ -0,0 +1,44 @@
+//! In-memory file form field reader.
+
+use actix_web::HttpRequest;
+use futures_core::future::LocalBoxFuture;
+use mime::Mime;
+
+use super::{read_to_bytes, FieldReader, Limits};
+use crate::{Field, MultipartError};
+
+/// Form field read into memory, such as a small uploaded file.
+///
+/// Contents count towards the memory limit.
+#[derive(Debug, Clone)]
+pub struct Bytes {
+    /// Contents of the field.
+    pub data: ::bytes::Bytes,
+
+    /// Content type of the field, `application/octet-stream` if not sent.
+    pub content_type: Mime,
+
+    /// File name sent by the client, if any.
+    pub file_name: Option<String>,
+}
+
+impl FieldReader for Bytes {
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_to_bytes(&mut field, limits).await?;
+
+            Ok(Bytes {
+                data: data.freeze(),
+                content_type: field.content_type().clone(),
+                file_name: field
+                    .content_disposition()
+                    .get_filename()
+                    .map(str::to_owned),
+            })
+        })
+    }
+}
@@ -0,0 +1,56 @@
+//! JSON form field reader.
+
+use std::ops::{Deref, DerefMut};
+
+use actix_web::HttpRequest;
+use futures_core::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+
+use super::{read_to_bytes, FieldReader, Limits};
+use crate::{Field, MultipartError};
+
+/// Form field deserialized from JSON using [`serde`].
+///
+/// Contents are held in memory while deserializing and count towards the memory limit.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    /// Unwraps into inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FieldReader for Json<T> {
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_to_bytes(&mut field, limits).await?;
+
+            serde_json::from_slice(&data)
+                .map(Json)
+                .map_err(|err| MultipartError::FieldParse {
+                    name: field.name().to_owned(),
+                    error: err.to_string(),
+                })
+        })
+    }
+}
@@ -0,0 +1,426 @@
+//! Typed multipart form extraction.
+//!
+//! The [`MultipartForm`] extractor reads a multipart stream into a struct that implements
+//! [`MultipartCollect`], which is usually derived:
+//!
+//! ```
+//! use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+//! use actix_web::{post, HttpResponse};
+//!
+//! #[derive(MultipartForm)]
+//! struct Upload {
+//!     description: Option<Text<String>>,
+//!     #[multipart(rename = "file")]
+//!     files: Vec<TempFile>,
+//! }
+//!
+//! #[post("/upload")]
+//! async fn upload(MultipartForm(form): MultipartForm<Upload>) -> HttpResponse {
+//!     HttpResponse::Ok().body(format!("received {} files", form.files.len()))
+//! }
+//! ```
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use ::bytes::BytesMut;
+use actix_utils::future::poll_fn;
+use actix_web::{dev, error::PayloadError, web, Error, FromRequest, HttpRequest};
+use futures_core::{future::LocalBoxFuture, Stream as _};
+
+use crate::{Field, Multipart, MultipartError};
+
+pub mod bytes;
+pub mod json;
+pub mod tempfile;
+pub mod text;
+
+/// Implements [`MultipartCollect`] for a struct. See [the derive docs](macro@MultipartForm).
+#[cfg(feature = "derive")]
+pub use actix_multipart_derive::MultipartForm;
+
+/// Values of form fields read so far, keyed by field name.
+pub type State = HashMap<String, Box<dyn Any>>;
+
+/// Reads the contents of a single form field into a value.
+pub trait FieldReader: Sized + Any {
+    /// Reads `field`, accounting for its size in `limits`.
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>>;
+}
+
+/// Collects all fields sharing a name into a struct member.
+///
+/// Implemented for:
+/// - `T` where `T: FieldReader`, for fields that must be sent exactly once
+/// - `Option<T>`, for fields that may be left out
+/// - `Vec<T>`, for fields that may be repeated any number of times
+pub trait FieldGroupReader: Sized + Any {
+    /// Reads `field` and stores its value in `state`.
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+        duplicate_field: DuplicateField,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>>;
+
+    /// Takes the value of the field called `name` out of `state`.
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError>;
+}
+
+/// Collects a multipart stream into a form struct.
+///
+/// Usually implemented with the [`MultipartForm`](macro@MultipartForm) derive macro.
+pub trait MultipartCollect: Sized {
+    /// Reads `field` into `state`, delegating to the [`FieldGroupReader`] of the matching member.
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> Pin<Box<dyn Future<Output = Result<(), MultipartError>> + 't>>;
+
+    /// Builds the form from the values collected in `state`.
+    fn from_state(state: State) -> Result<Self, MultipartError>;
+}
+
+/// How to handle a field that is sent more than once but only accepts a single value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateField {
+    /// Keep the first value and skip the rest.
+    Ignore,
+
+    /// Fail with [`MultipartError::DuplicateField`].
+    Deny,
+
+    /// Keep the last value.
+    Replace,
+}
+
+impl<T: FieldReader> FieldGroupReader for T {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+        duplicate_field: DuplicateField,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        Box::pin(async move {
+            let name = field.name().to_owned();
+
+            if state.contains_key(&name) {
+                match duplicate_field {
+                    // dropping the field skips over its contents
+                    DuplicateField::Ignore => return Ok(()),
+                    DuplicateField::Deny => return Err(MultipartError::DuplicateField(name)),
+                    DuplicateField::Replace => {}
+                }
+            }
+
+            let value = T::read_field(req, field, limits).await?;
+            state.insert(name, Box::new(value));
+            Ok(())
+        })
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        take_value(name, state).ok_or_else(|| MultipartError::MissingField(name.to_owned()))
+    }
+}
+
+impl<T: FieldReader> FieldGroupReader for Option<T> {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+        duplicate_field: DuplicateField,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        T::handle_field(req, field, limits, state, duplicate_field)
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        Ok(take_value(name, state))
+    }
+}
+
+impl<T: FieldReader> FieldGroupReader for Vec<T> {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+        _duplicate_field: DuplicateField,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        Box::pin(async move {
+            let name = field.name().to_owned();
+            let value = T::read_field(req, field, limits).await?;
+
+            state
+                .entry(name)
+                .or_insert_with(|| Box::new(Vec::<T>::new()))
+                .downcast_mut::<Vec<T>>()
+                .expect("field values are stored with the type of their struct member")
+                .push(value);
+
+            Ok(())
+        })
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        Ok(take_value(name, state).unwrap_or_default())
+    }
+}
+
+/// Removes the value of the field called `name` from `state`.
+fn take_value<T: Any>(name: &str, state: &mut State) -> Option<T> {
+    state.remove(name).map(|value| {
+        *value
+            .downcast::<T>()
+            .expect("field values are stored with the type of their struct member")
+    })
+}
+
+/// Remaining amounts of data that may be read from a multipart stream.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Bytes that may still be read from all fields.
+    pub total_limit_remaining: usize,
+
+    /// Bytes that may still be buffered in memory.
+    pub memory_limit_remaining: usize,
+}
+
+impl Limits {
+    /// Creates limits allowing `total_limit` bytes in total, of which `memory_limit` bytes may be
+    /// held in memory.
+    pub fn new(total_limit: usize, memory_limit: usize) -> Self {
+        Self {
+            total_limit_remaining: total_limit,
+            memory_limit_remaining: memory_limit,
+        }
+    }
+
+    /// Accounts for `bytes` more bytes being read, which are held in memory if `in_memory`.
+    ///
+    /// Fails with [`PayloadError::Overflow`] if a limit is exceeded.
+    pub fn try_consume_limits(
+        &mut self,
+        bytes: usize,
+        in_memory: bool,
+    ) -> Result<(), MultipartError> {
+        self.total_limit_remaining = self
+            .total_limit_remaining
+            .checked_sub(bytes)
+            .ok_or(MultipartError::Payload(PayloadError::Overflow))?;
+
+        if in_memory {
+            self.memory_limit_remaining = self
+                .memory_limit_remaining
+                .checked_sub(bytes)
+                .ok_or(MultipartError::Payload(PayloadError::Overflow))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the whole contents of `field` into memory.
+pub(crate) async fn read_to_bytes(
+    field: &mut Field,
+    limits: &mut Limits,
+) -> Result<BytesMut, MultipartError> {
+    let mut buf = BytesMut::new();
+
+    while let Some(chunk) = poll_fn(|cx| Pin::new(&mut *field).poll_next(cx)).await {
+        let chunk = chunk?;
+        limits.try_consume_limits(chunk.len(), true)?;
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(buf)
+}
+
+/// Typed multipart form extractor.
+///
+/// Reads the request payload into `T`, which is usually a struct deriving
+/// [`MultipartForm`](macro@MultipartForm). Extraction is bound by the [`MultipartFormConfig`] found
+/// in app data, or its defaults.
+///
+/// Fails with [`MultipartError::MissingField`] for missing required fields and with
+/// [`MultipartError::DuplicateField`] for repeated single-value fields.
+#[derive(Debug)]
+pub struct MultipartForm<T: MultipartCollect>(pub T);
+
+impl<T: MultipartCollect> MultipartForm<T> {
+    /// Unwraps into inner form value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: MultipartCollect> Deref for MultipartForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: MultipartCollect> DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: MultipartCollect + 'static> FromRequest for MultipartForm<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let mut multipart = Multipart::new(req.headers(), payload.take());
+        let config = MultipartFormConfig::from_req(req).clone();
+        let req = req.clone();
+
+        Box::pin(async move {
+            let mut state = State::new();
+            let mut limits = Limits::new(config.total_limit, config.memory_limit);
+
+            let res = async {
+                while let Some(field) =
+                    poll_fn(|cx| Pin::new(&mut multipart).poll_next(cx)).await
+                {
+                    T::handle_field(&req, field?, &mut limits, &mut state).await?;
+                }
+
+                T::from_state(state)
+            }
+            .await;
+
+            match res {
+                Ok(form) => Ok(MultipartForm(form)),
+                Err(err) => Err(match config.err_handler {
+                    Some(err_handler) => err_handler(err, &req),
+                    None => err.into(),
+                }),
+            }
+        })
+    }
+}
+
+type MultipartFormErrorHandler =
+    Option<Arc<dyn Fn(MultipartError, &HttpRequest) -> Error + Send + Sync>>;
+
+/// [`MultipartForm`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_multipart::form::MultipartFormConfig;
+/// use actix_web::App;
+///
+/// let config = MultipartFormConfig::default()
+///     // limit size of all fields combined
+///     .total_limit(100 * 1024 * 1024)
+///     // limit size of fields held in memory, such as text fields
+///     .memory_limit(64 * 1024)
+///     // keep temporary files on the same file system as their final destination
+///     .temp_dir("./uploads/tmp");
+///
+/// App::new().app_data(config);
+/// ```
+#[derive(Clone)]
+pub struct MultipartFormConfig {
+    total_limit: usize,
+    memory_limit: usize,
+    temp_dir: Option<PathBuf>,
+    err_handler: MultipartFormErrorHandler,
+}
+
+impl MultipartFormConfig {
+    /// Sets maximum size of all fields combined. By default this limit is 50MiB.
+    pub fn total_limit(mut self, total_limit: usize) -> Self {
+        self.total_limit = total_limit;
+        self
+    }
+
+    /// Sets maximum size of fields held in memory. By default this limit is 2MiB.
+    ///
+    /// Fields read into temporary files do not count towards this limit.
+    pub fn memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Sets directory in which temporary files are created. By default, the system's temporary
+    /// directory is used.
+    pub fn temp_dir(mut self, temp_dir: impl AsRef<Path>) -> Self {
+        self.temp_dir = Some(temp_dir.as_ref().to_owned());
+        self
+    }
+
+    /// Sets custom error handler.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(MultipartError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract form config from app data. Check both `T` and `Data<T>`, in that order, and fall
+    /// back to the default form config.
+    pub(crate) fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+const DEFAULT_CONFIG: MultipartFormConfig = MultipartFormConfig {
+    total_limit: 52_428_800, // 50 MiB
+    memory_limit: 2_097_152, // 2 MiB
+    temp_dir: None,
+    err_handler: None,
+};
+
+impl Default for MultipartFormConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let mut limits = Limits::new(10, 4);
+
+        limits.try_consume_limits(4, true).unwrap();
+        limits.try_consume_limits(6, false).unwrap();
+        assert_eq!(limits.total_limit_remaining, 0);
+        assert_eq!(limits.memory_limit_remaining, 0);
+
+        let mut limits = Limits::new(10, 4);
+        assert!(matches!(
+            limits.try_consume_limits(5, true),
+            Err(MultipartError::Payload(PayloadError::Overflow))
+        ));
+        assert!(matches!(
+            Limits::new(10, 4).try_consume_limits(11, false),
+            Err(MultipartError::Payload(PayloadError::Overflow))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Temporary file form field reader.
+
+use std::{
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use actix_utils::future::poll_fn;
+use actix_web::{web, HttpRequest};
+use futures_core::{future::LocalBoxFuture, Stream as _};
+use mime::Mime;
+use rand::Rng as _;
+
+use super::{FieldReader, Limits, MultipartFormConfig};
+use crate::{Field, MultipartError};
+
+/// Form field streamed to a temporary file, such as an uploaded file.
+///
+/// The file is created in the directory set with [`MultipartFormConfig::temp_dir`] and deleted when
+/// this value is dropped, unless it is [persisted](Self::persist). Contents count towards the total
+/// limit but not the memory limit.
+pub struct TempFile {
+    file: File,
+    path: TempPath,
+
+    /// Content type of the field, `application/octet-stream` if not sent.
+    pub content_type: Mime,
+
+    /// File name sent by the client, if any.
+    pub file_name: Option<String>,
+
+    /// Size of the file in bytes.
+    pub size: usize,
+}
+
+impl TempFile {
+    /// Returns a reference to the temporary file.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Returns the path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.path
+            .0
+            .as_deref()
+            .expect("temporary file is not persisted")
+    }
+
+    /// Moves the temporary file to `path` so that it is kept after this value is dropped.
+    ///
+    /// `path` should be on the same file system as the temporary file, otherwise moving fails.
+    pub fn persist(mut self, path: impl AsRef<Path>) -> io::Result<File> {
+        let tmp_path = self.path.0.take().expect("temporary file is not persisted");
+
+        match fs::rename(&tmp_path, path) {
+            Ok(()) => Ok(self.file),
+            Err(err) => {
+                self.path.0 = Some(tmp_path);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TempFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TempFile")
+            .field("path", &self.path.0)
+            .field("content_type", &self.content_type)
+            .field("file_name", &self.file_name)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Path of a temporary file, which is removed on drop if still set.
+struct TempPath(Option<PathBuf>);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl FieldReader for TempFile {
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        let dir = MultipartFormConfig::from_req(req)
+            .temp_dir
+            .clone()
+            .unwrap_or_else(env::temp_dir);
+
+        Box::pin(async move {
+            let (mut file, path) = web::block(move || create_temp_file(&dir))
+                .await
+                .map_err(|err| field_io_error(&field, err.to_string()))?
+                .map_err(|err| field_io_error(&field, err.to_string()))?;
+
+            // removes file if reading fails
+            let path = TempPath(Some(path));
+            let mut size = 0;
+
+            while let Some(chunk) = poll_fn(|cx| Pin::new(&mut field).poll_next(cx)).await {
+                let chunk = chunk?;
+                limits.try_consume_limits(chunk.len(), false)?;
+                size += chunk.len();
+
+                file = web::block(move || file.write_all(&chunk).map(|_| file))
+                    .await
+                    .map_err(|err| field_io_error(&field, err.to_string()))?
+                    .map_err(|err| field_io_error(&field, err.to_string()))?;
+            }
+
+            Ok(TempFile {
+                file,
+                path,
+                content_type: field.content_type().clone(),
+                file_name: field
+                    .content_disposition()
+                    .get_filename()
+                    .map(str::to_owned),
+                size,
+            })
+        })
+    }
+}
+
+/// Creates a new file with a random name in `dir`.
+fn create_temp_file(dir: &Path) -> io::Result<(File, PathBuf)> {
+    loop {
+        let name = format!(
+            "actix-multipart-{}.tmp",
+            rand::thread_rng()
+                .sample_iter(&rand::distributions::Alphanumeric)
+                .take(16)
+                .map(char::from)
+                .collect::<String>()
+        );
+        let path = dir.join(name);
+
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((file, path)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn field_io_error(field: &Field, error: String) -> MultipartError {
+    MultipartError::FieldParse {
+        name: field.name().to_owned(),
+        error: format!("could not write temporary file: {}", error),
+    }
+}
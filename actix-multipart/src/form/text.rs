@@ -0,0 +1,66 @@
+//! Text form field reader.
+
+use std::{
+    ops::{Deref, DerefMut},
+    str::{self, FromStr},
+};
+
+use actix_web::HttpRequest;
+use futures_core::future::LocalBoxFuture;
+
+use super::{read_to_bytes, FieldReader, Limits};
+use crate::{Field, MultipartError};
+
+/// Form field parsed from UTF-8 text using [`FromStr`].
+///
+/// Contents are held in memory while parsing and count towards the memory limit.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Text<T>(pub T);
+
+impl<T> Text<T> {
+    /// Unwraps into inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Text<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Text<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FieldReader for Text<T>
+where
+    T: FromStr + 'static,
+    T::Err: std::fmt::Display,
+{
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_to_bytes(&mut field, limits).await?;
+
+            let parse_error = |error: String| MultipartError::FieldParse {
+                name: field.name().to_owned(),
+                error,
+            };
+
+            let text = str::from_utf8(&data).map_err(|err| parse_error(err.to_string()))?;
+
+            text.parse()
+                .map(Text)
+                .map_err(|err: T::Err| parse_error(err.to_string()))
+        })
+    }
+}
@@ -6,6 +6,7 @@
 
 mod error;
 mod extractor;
+pub mod form;
 mod server;
 
 pub use self::error::MultipartError;
@@ -0,0 +1,278 @@
+use actix_multipart::{
+    form::{bytes::Bytes, json::Json, tempfile::TempFile, text::Text, MultipartForm},
+    form::{MultipartCollect, MultipartFormConfig},
+    MultipartError,
+};
+use actix_web::{
+    error::{InternalError, PayloadError},
+    http::StatusCode,
+    test, web, App, Error, FromRequest as _, HttpResponse,
+};
+
+const BOUNDARY: &str = "abbc761f78ff4d7cb7573b5a23f96ef0";
+
+/// Builds a multipart request from `(name, file_name, content)` parts.
+fn form_request(parts: &[(&str, Option<&str>, &str)]) -> test::TestRequest {
+    let mut body = String::new();
+
+    for (name, file_name, content) in parts {
+        body.push_str(&format!("--{}\r\n", BOUNDARY));
+
+        match file_name {
+            Some(file_name) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                 Content-Type: text/plain\r\n",
+                name, file_name
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n",
+                name
+            )),
+        }
+
+        body.push_str(&format!("\r\n{}\r\n", content));
+    }
+
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+
+    test::TestRequest::post()
+        .insert_header((
+            "content-type",
+            format!("multipart/form-data; boundary=\"{}\"", BOUNDARY),
+        ))
+        .set_payload(body)
+}
+
+async fn extract<T: MultipartCollect + 'static>(req: test::TestRequest) -> Result<T, Error> {
+    let (req, mut payload) = req.to_http_parts();
+
+    MultipartForm::<T>::from_request(&req, &mut payload)
+        .await
+        .map(MultipartForm::into_inner)
+}
+
+#[derive(MultipartForm)]
+struct Simple {
+    name: Text<String>,
+    age: Text<u8>,
+    nickname: Option<Text<String>>,
+    #[multipart(rename = "tags[]")]
+    tags: Vec<Text<String>>,
+}
+
+#[actix_rt::test]
+async fn test_simple_form() {
+    let form = extract::<Simple>(form_request(&[
+        ("name", None, "John"),
+        ("age", None, "42"),
+        ("tags[]", None, "a"),
+        ("unknown", None, "skipped"),
+        ("tags[]", None, "b"),
+    ]))
+    .await
+    .unwrap();
+
+    assert_eq!(form.name.as_str(), "John");
+    assert_eq!(*form.age, 42);
+    assert!(form.nickname.is_none());
+    assert_eq!(
+        form.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>(),
+        ["a", "b"]
+    );
+
+    let form = extract::<Simple>(form_request(&[
+        ("nickname", None, "Johnny"),
+        ("age", None, "42"),
+        ("name", None, "John"),
+    ]))
+    .await
+    .unwrap();
+
+    assert_eq!(form.nickname.unwrap().into_inner(), "Johnny");
+    assert!(form.tags.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_form_errors() {
+    let err = extract::<Simple>(form_request(&[("age", None, "42")]))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::MissingField(name)) if name == "name"
+    ));
+
+    let err = extract::<Simple>(form_request(&[
+        ("name", None, "John"),
+        ("name", None, "Jane"),
+        ("age", None, "42"),
+    ]))
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::DuplicateField(name)) if name == "name"
+    ));
+
+    let err = extract::<Simple>(form_request(&[
+        ("name", None, "John"),
+        ("age", None, "old"),
+    ]))
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::FieldParse { name, .. }) if name == "age"
+    ));
+}
+
+#[derive(MultipartForm)]
+#[multipart(deny_unknown_fields, duplicate_field = "replace")]
+struct Strict {
+    name: Text<String>,
+}
+
+#[derive(MultipartForm)]
+#[multipart(duplicate_field = "ignore")]
+struct FirstValue {
+    name: Text<String>,
+}
+
+#[actix_rt::test]
+async fn test_form_attributes() {
+    let duplicated = [("name", None, "John"), ("name", None, "Jane")];
+
+    let form = extract::<Strict>(form_request(&duplicated)).await.unwrap();
+    assert_eq!(form.name.as_str(), "Jane");
+
+    let form = extract::<FirstValue>(form_request(&duplicated))
+        .await
+        .unwrap();
+    assert_eq!(form.name.as_str(), "John");
+
+    let err = extract::<Strict>(form_request(&[
+        ("name", None, "John"),
+        ("other", None, "value"),
+    ]))
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::UnsupportedField(name)) if name == "other"
+    ));
+}
+
+#[derive(MultipartForm)]
+struct Upload {
+    file: TempFile,
+    data: Bytes,
+    meta: Json<Vec<u32>>,
+}
+
+#[actix_rt::test]
+async fn test_file_fields() {
+    let form = extract::<Upload>(form_request(&[
+        ("file", Some("a.txt"), "file contents"),
+        ("data", Some("b.txt"), "in memory"),
+        ("meta", None, "[1, 2, 3]"),
+    ]))
+    .await
+    .unwrap();
+
+    assert_eq!(form.file.file_name.as_deref(), Some("a.txt"));
+    assert_eq!(form.file.size, 13);
+    assert_eq!(form.file.content_type, mime::TEXT_PLAIN);
+    let path = form.file.path().to_owned();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "file contents");
+
+    assert_eq!(form.data.file_name.as_deref(), Some("b.txt"));
+    assert_eq!(&form.data.data[..], b"in memory");
+    assert_eq!(*form.meta, [1, 2, 3]);
+
+    // temporary file is removed when dropped
+    drop(form);
+    assert!(!path.exists());
+}
+
+#[actix_rt::test]
+async fn test_temp_file_persist() {
+    let form = extract::<Upload>(form_request(&[
+        ("file", Some("a.txt"), "file contents"),
+        ("data", None, ""),
+        ("meta", None, "[]"),
+    ]))
+    .await
+    .unwrap();
+
+    let tmp_path = form.file.path().to_owned();
+    let path = tmp_path.with_extension("persisted");
+
+    form.file.persist(&path).unwrap();
+    assert!(!tmp_path.exists());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "file contents");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[actix_rt::test]
+async fn test_limits() {
+    let req = form_request(&[("name", None, "John"), ("age", None, "42")])
+        .app_data(MultipartFormConfig::default().memory_limit(5));
+    let err = extract::<Simple>(req).await.err().unwrap();
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::Payload(PayloadError::Overflow))
+    ));
+
+    // files count towards the total limit only
+    let parts = [
+        ("file", Some("a.txt"), "file contents"),
+        ("data", None, ""),
+        ("meta", None, "[]"),
+    ];
+
+    let req = form_request(&parts).app_data(MultipartFormConfig::default().memory_limit(2));
+    assert!(extract::<Upload>(req).await.is_ok());
+
+    let req = form_request(&parts).app_data(MultipartFormConfig::default().total_limit(10));
+    let err = extract::<Upload>(req).await.err().unwrap();
+    assert!(matches!(
+        err.as_error::<MultipartError>(),
+        Some(MultipartError::Payload(PayloadError::Overflow))
+    ));
+}
+
+#[actix_rt::test]
+async fn test_extractor() {
+    async fn handler(MultipartForm(form): MultipartForm<Simple>) -> HttpResponse {
+        HttpResponse::Ok().body(form.name.into_inner())
+    }
+
+    let srv = test::init_service(App::new().route("/", web::post().to(handler)).app_data(
+        MultipartFormConfig::default().error_handler(|err, _| {
+            InternalError::from_response(err, HttpResponse::Conflict().finish()).into()
+        }),
+    ))
+    .await;
+
+    let req = form_request(&[("name", None, "John"), ("age", None, "42")])
+        .uri("/")
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "John");
+
+    let req = form_request(&[("name", None, "John")])
+        .uri("/")
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+}
@@ -287,32 +287,45 @@ pub fn handshake(req: &HttpRequest) -> Result<HttpResponseBuilder, HandshakeErro
 ///
 /// `protocols` is a sequence of known protocols. On successful handshake, the returned response
 /// headers contain the first protocol in this list which the server also knows.
+///
+/// HTTP/2 extended CONNECT requests (RFC 8441) are answered with `200 OK` instead of
+/// `101 Switching Protocols`; the WebSocket then runs over the request's HTTP/2 stream.
 pub fn handshake_with_protocols(
     req: &HttpRequest,
     protocols: &[&str],
 ) -> Result<HttpResponseBuilder, HandshakeError> {
-    // WebSocket accepts only GET
-    if *req.method() != Method::GET {
-        return Err(HandshakeError::GetMethodRequired);
-    }
+    let extended_connect = *req.method() == Method::CONNECT && req.head().protocol().is_some();
+
+    if extended_connect {
+        // `:protocol` pseudo-header takes the place of `Upgrade` and `Connection` headers
+        let protocol = req.head().protocol().unwrap_or_default();
+        if !protocol.eq_ignore_ascii_case("websocket") {
+            return Err(HandshakeError::NoWebsocketUpgrade);
+        }
+    } else {
+        // WebSocket accepts only GET
+        if *req.method() != Method::GET {
+            return Err(HandshakeError::GetMethodRequired);
+        }
 
-    // check for "UPGRADE" to WebSocket header
-    let has_hdr = if let Some(hdr) = req.headers().get(&header::UPGRADE) {
-        if let Ok(s) = hdr.to_str() {
-            s.to_ascii_lowercase().contains("websocket")
+        // check for "UPGRADE" to WebSocket header
+        let has_hdr = if let Some(hdr) = req.headers().get(&header::UPGRADE) {
+            if let Ok(s) = hdr.to_str() {
+                s.to_ascii_lowercase().contains("websocket")
+            } else {
+                false
+            }
         } else {
             false
+        };
+        if !has_hdr {
+            return Err(HandshakeError::NoWebsocketUpgrade);
         }
-    } else {
-        false
-    };
-    if !has_hdr {
-        return Err(HandshakeError::NoWebsocketUpgrade);
-    }
 
-    // Upgrade connection
-    if !req.head().upgrade() {
-        return Err(HandshakeError::NoConnectionUpgrade);
+        // Upgrade connection
+        if !req.head().upgrade() {
+            return Err(HandshakeError::NoConnectionUpgrade);
+        }
     }
 
     // check supported version
@@ -330,14 +343,10 @@ pub fn handshake_with_protocols(
         return Err(HandshakeError::UnsupportedVersion);
     }
 
-    // check client handshake for validity
-    if !req.headers().contains_key(&header::SEC_WEBSOCKET_KEY) {
+    // check client handshake for validity; there is no key exchange over HTTP/2
+    if !extended_connect && !req.headers().contains_key(&header::SEC_WEBSOCKET_KEY) {
         return Err(HandshakeError::BadWebsocketKey);
     }
-    let key = {
-        let key = req.headers().get(&header::SEC_WEBSOCKET_KEY).unwrap();
-        hash_key(key.as_ref())
-    };
 
     // check requested protocols
     let protocol =
@@ -351,14 +360,23 @@ pub fn handshake_with_protocols(
                     .find(|req_p| protocols.iter().any(|p| p == req_p))
             });
 
-    let mut response = HttpResponse::build(StatusCode::SWITCHING_PROTOCOLS)
-        .upgrade("websocket")
-        .insert_header((
-            header::SEC_WEBSOCKET_ACCEPT,
-            // key is known to be header value safe ascii
-            HeaderValue::from_bytes(&key).unwrap(),
-        ))
-        .take();
+    let mut response = if extended_connect {
+        HttpResponse::Ok()
+    } else {
+        let key = {
+            let key = req.headers().get(&header::SEC_WEBSOCKET_KEY).unwrap();
+            hash_key(key.as_ref())
+        };
+
+        HttpResponse::build(StatusCode::SWITCHING_PROTOCOLS)
+            .upgrade("websocket")
+            .insert_header((
+                header::SEC_WEBSOCKET_ACCEPT,
+                // key is known to be header value safe ascii
+                HeaderValue::from_bytes(&key).unwrap(),
+            ))
+            .take()
+    };
 
     if let Some(protocol) = protocol {
         response.insert_header((header::SEC_WEBSOCKET_PROTOCOL, protocol));
@@ -735,7 +753,7 @@ where
 #[cfg(test)]
 mod tests {
     use actix_web::{
-        http::{header, Method},
+        http::{header, Method, Version},
         test::TestRequest,
     };
 
@@ -976,4 +994,46 @@ mod tests {
                 .get(&header::SEC_WEBSOCKET_PROTOCOL)
         );
     }
+
+    #[test]
+    fn test_handshake_extended_connect() {
+        let mut req = TestRequest::default()
+            .method(Method::CONNECT)
+            .version(Version::HTTP_2)
+            .insert_header((
+                header::SEC_WEBSOCKET_VERSION,
+                header::HeaderValue::from_static("13"),
+            ))
+            .insert_header((
+                header::SEC_WEBSOCKET_PROTOCOL,
+                header::HeaderValue::from_static("graphql"),
+            ))
+            .to_srv_request();
+        req.head_mut().set_protocol(Some("websocket"));
+        let (req, _) = req.into_parts();
+
+        let resp = handshake_with_protocols(&req, &["graphql"])
+            .unwrap()
+            .finish();
+
+        assert_eq!(StatusCode::OK, resp.status());
+        assert!(!resp.headers().contains_key(&header::SEC_WEBSOCKET_ACCEPT));
+        assert_eq!(
+            Some(&header::HeaderValue::from_static("graphql")),
+            resp.headers().get(&header::SEC_WEBSOCKET_PROTOCOL)
+        );
+
+        // extended CONNECT for other protocols is rejected
+        let mut req = TestRequest::default()
+            .method(Method::CONNECT)
+            .version(Version::HTTP_2)
+            .to_srv_request();
+        req.head_mut().set_protocol(Some("webtransport"));
+        let (req, _) = req.into_parts();
+
+        assert_eq!(
+            HandshakeError::NoWebsocketUpgrade,
+            handshake(&req).err().unwrap()
+        );
+    }
 }
@@ -15,6 +15,7 @@ pub(crate) struct ConnectorConfig {
     pub(crate) conn_window_size: u32,
     pub(crate) stream_window_size: u32,
     pub(crate) local_address: Option<IpAddr>,
+    pub(crate) h2_prior_knowledge: bool,
 }
 
 impl Default for ConnectorConfig {
@@ -29,6 +30,7 @@ impl Default for ConnectorConfig {
             conn_window_size: DEFAULT_H2_CONN_WINDOW,
             stream_window_size: DEFAULT_H2_STREAM_WINDOW,
             local_address: None,
+            h2_prior_knowledge: false,
         }
     }
 }
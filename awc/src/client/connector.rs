@@ -269,6 +269,18 @@ where
         self
     }
 
+    /// Use HTTP/2 with prior knowledge for plain `http://` connections.
+    ///
+    /// When enabled, connections that are not secured with TLS skip HTTP/1.1 and start talking
+    /// HTTP/2 over cleartext TCP (h2c) right away. Only enable this when every server the client
+    /// talks to over plain TCP is known to support it.
+    ///
+    /// Disabled by default.
+    pub fn h2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.config.h2_prior_knowledge = enabled;
+        self
+    }
+
     /// Finish configuration process and create connector service.
     /// The Connector builder always concludes by calling `finish()` last in
     /// its combinator chain.
@@ -282,6 +294,11 @@ where
         #[allow(clippy::redundant_clone)]
         let tcp_service = TcpConnectorService {
             service: tcp_service_inner.clone(),
+            protocol: if self.config.h2_prior_knowledge {
+                Protocol::Http2
+            } else {
+                Protocol::Http1
+            },
         };
 
         let tls_service = match self.ssl {
@@ -424,6 +441,7 @@ where
 #[derive(Clone)]
 pub struct TcpConnectorService<S: Clone> {
     service: S,
+    protocol: Protocol,
 }
 
 impl<S, Io> Service<Connect> for TcpConnectorService<S>
@@ -441,6 +459,7 @@ where
     fn call(&self, req: Connect) -> Self::Future {
         TcpConnectorFuture {
             fut: self.service.call(req),
+            protocol: self.protocol,
         }
     }
 }
@@ -450,6 +469,7 @@ pin_project! {
     pub struct TcpConnectorFuture<Fut> {
         #[pin]
         fut: Fut,
+        protocol: Protocol,
     }
 }
 
@@ -460,10 +480,11 @@ where
     type Output = Result<(Io, Protocol), ConnectError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.project()
-            .fut
+        let this = self.project();
+        let protocol = *this.protocol;
+        this.fut
             .poll(cx)
-            .map_ok(|res| (res.into_parts().0, Protocol::Http1))
+            .map_ok(|res| (res.into_parts().0, protocol))
     }
 }
 
@@ -21,7 +21,7 @@ use brotli2::write::BrotliEncoder;
 #[cfg(feature = "compress-gzip")]
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 
-use actix_http::{ContentEncoding, HttpService, StatusCode};
+use actix_http::{ContentEncoding, HttpService, StatusCode, Version};
 use actix_http_test::test_server;
 use actix_service::{fn_service, map_config, ServiceFactoryExt as _};
 use actix_web::{
@@ -906,3 +906,31 @@ async fn test_local_address() {
 
     assert_eq!(res.status(), 200);
 }
+
+#[actix_rt::test]
+async fn test_h2_prior_knowledge() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2c(true)
+            .finish(map_config(
+                App::new().service(web::resource("/").route(web::to(|req: HttpRequest| {
+                    assert_eq!(req.version(), Version::HTTP_2);
+                    HttpResponse::Ok().body(STR)
+                }))),
+                |_| AppConfig::default(),
+            ))
+            .tcp()
+    })
+    .await;
+
+    let client = awc::Client::builder()
+        .connector(awc::Connector::new().h2_prior_knowledge(true))
+        .finish();
+
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.version(), Version::HTTP_2);
+
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
@@ -6,7 +6,8 @@
 //
 // See <https://github.com/rust-lang/rust/issues/83375>
 pub use actix_http::error::{
-    BlockingError, ContentTypeError, DispatchError, HttpError, ParseError, PayloadError,
+    BlockingError, ContentTypeError, DispatchError, HttpError, InformationalError, ParseError,
+    PayloadError, UpgradeError,
 };
 
 use derive_more::{Display, Error, From};
@@ -113,6 +113,7 @@ impl ResponseError for actix_http::error::PayloadError {
     fn status_code(&self) -> StatusCode {
         match *self {
             actix_http::error::PayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            actix_http::error::PayloadError::Timeout => StatusCode::REQUEST_TIMEOUT,
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -126,6 +127,15 @@ impl ResponseError for actix_http::error::ContentTypeError {
     }
 }
 
+impl ResponseError for actix_http::error::UpgradeError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            actix_http::error::UpgradeError::Unsupported => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 impl ResponseError for actix_http::ws::HandshakeError {
     fn error_response(&self) -> HttpResponse<BoxBody> {
         Response::from(self).map_into_boxed_body().into()
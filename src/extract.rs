@@ -7,12 +7,12 @@ use std::{
     task::{Context, Poll},
 };
 
-use actix_http::{Method, Uri};
-use actix_utils::future::{ok, Ready};
+use actix_http::{error::UpgradeError, upgrade::Upgrade, InformationalSender, Method, Uri};
+use actix_utils::future::{err, ok, Ready};
 use futures_core::ready;
 use pin_project_lite::pin_project;
 
-use crate::{dev::Payload, Error, HttpRequest};
+use crate::{dev::Payload, Error, HttpMessage as _, HttpRequest};
 
 /// A type that implements [`FromRequest`] is called an **extractor** and can extract data from
 /// the request. Some types that implement this trait are: [`Json`], [`Header`], and [`Path`].
@@ -290,6 +290,77 @@ impl FromRequest for Method {
     }
 }
 
+/// Extract handle for sending informational (1xx) responses, such as `103 Early Hints`.
+///
+/// Requests that cannot receive informational responses (e.g., from HTTP/1.0 clients) get a handle
+/// for which sending is a no-op.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     http::{header::HeaderValue, InformationalSender},
+///     web, App, HttpResponse, Responder,
+/// };
+///
+/// async fn handler(hints: InformationalSender) -> impl Responder {
+///     let link = HeaderValue::from_static("</app.js>; rel=preload; as=script");
+///     let _ = hints.send_early_hints(vec![link]);
+///
+///     // ...slow work...
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// let app = App::new().default_service(web::to(handler));
+/// ```
+impl FromRequest for InformationalSender {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(req
+            .extensions()
+            .get::<InformationalSender>()
+            .cloned()
+            .unwrap_or_else(InformationalSender::disabled))
+    }
+}
+
+/// Extract handle for taking over the connection after `CONNECT` or a protocol upgrade.
+///
+/// Only available for HTTP/1 requests that use the `CONNECT` method or carry a
+/// `Connection: upgrade` header; otherwise extraction fails with [`UpgradeError::Unsupported`].
+/// See [`Upgrade`] for details.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::upgrade::Upgrade, web, App, HttpResponse, Responder};
+///
+/// async fn connect(upgrade: Upgrade) -> impl Responder {
+///     let on_upgrade = upgrade.on_upgrade();
+///
+///     actix_web::rt::spawn(async move {
+///         if let Ok(io) = on_upgrade.await {
+///             // connect to target and copy data in both directions
+///         }
+///     });
+///
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// let app = App::new().default_service(web::to(connect));
+/// ```
+impl FromRequest for Upgrade {
+    type Error = UpgradeError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions_mut().remove::<Upgrade>() {
+            Some(upgrade) => ok(upgrade),
+            None => err(UpgradeError::Unsupported),
+        }
+    }
+}
+
 #[doc(hidden)]
 impl FromRequest for () {
     type Error = Infallible;
@@ -500,6 +571,14 @@ mod tests {
         assert_eq!(method, Method::GET);
     }
 
+    #[actix_rt::test]
+    async fn test_informational_sender() {
+        let req = TestRequest::default().to_http_request();
+        let sender = InformationalSender::extract(&req).await.unwrap();
+        assert!(!sender.is_supported());
+        assert!(sender.send_early_hints(None).is_ok());
+    }
+
     #[actix_rt::test]
     async fn test_concurrent() {
         let (req, mut pl) = TestRequest::default()
@@ -2,4 +2,6 @@
 
 pub mod header;
 
-pub use actix_http::{uri, ConnectionType, Error, Method, StatusCode, Uri, Version};
+pub use actix_http::{
+    upgrade, uri, ConnectionType, Error, InformationalSender, Method, StatusCode, Uri, Version,
+};
@@ -62,7 +62,9 @@
 //! * `compress-zstd` - zstd content encoding compression support (enabled by default)
 //! * `openssl` - HTTPS support via `openssl` crate, supports `HTTP/2`
 //! * `rustls` - HTTPS support via `rustls` crate, supports `HTTP/2`
+//! * `http3` - `HTTP/3` support over QUIC via `quinn` and `h3` crates
 //! * `secure-cookies` - secure cookies support
+//! * `proxy` - reverse proxy service built on `awc`
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![warn(future_incompatible)]
@@ -83,6 +85,8 @@ mod helpers;
 pub mod http;
 mod info;
 pub mod middleware;
+#[cfg(feature = "proxy")]
+pub mod proxy;
 mod request;
 mod request_data;
 mod resource;
@@ -0,0 +1,751 @@
+//! Reverse proxy service.
+//!
+//! See [`Proxy`] for details.
+
+use std::{borrow::Cow, fmt, net::IpAddr, rc::Rc, time::Duration};
+
+use actix_http::{
+    body::SizedStream,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    upgrade::Upgrade,
+    ws, Method, StatusCode, Version,
+};
+use actix_router::ResourceDef;
+use actix_service::{Service, ServiceFactory};
+use actix_utils::future::{ok, Ready};
+use awc::{
+    error::{SendRequestError, WsClientError},
+    Client, Connector,
+};
+use derive_more::Display;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{AppService, HttpServiceFactory, Payload, ServiceRequest, ServiceResponse},
+    error::{Error, ResponseError},
+    HttpMessage as _, HttpRequest, HttpResponse,
+};
+
+/// Headers that only apply to a single connection and are not forwarded.
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+type PathRewrite = dyn Fn(&str) -> String;
+
+/// Reverse proxy service that forwards requests under a path prefix to an upstream server.
+///
+/// Request and response bodies are streamed in both directions. Hop-by-hop headers (including
+/// those listed in the `Connection` header) are not forwarded. The proxy's own hop is appended to
+/// the `Forwarded` and `X-Forwarded-For` headers of upstream requests, while `X-Forwarded-Proto`
+/// and `X-Forwarded-Host` are replaced with the scheme and host the proxy received. `Location`
+/// headers of upstream responses that point at the upstream are rewritten to point at the scheme
+/// and host the proxy received, unless [`trust_forwarded_headers`](Self::trust_forwarded_headers)
+/// is enabled.
+///
+/// The path after the prefix is appended to the path of the upstream URL, unless changed with
+/// [`rewrite_path`](Self::rewrite_path). WebSocket handshakes received over HTTP/1 are passed
+/// through to the upstream and the connection is tunneled once the upstream accepts them.
+///
+/// Requests that cannot be forwarded are answered with `502 Bad Gateway`, or `504 Gateway Timeout`
+/// when the upstream does not respond in time.
+///
+/// Connections to HTTPS upstreams need the `openssl` or `rustls` feature of `awc`.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{proxy::{InvalidUpstream, Proxy}, web, App};
+///
+/// # fn main() -> Result<(), InvalidUpstream> {
+/// let app = App::new()
+///     .service(web::proxy("/api", "http://127.0.0.1:8081")?)
+///     .service(
+///         Proxy::new("/static", "http://127.0.0.1:8082/assets")?
+///             .timeout(Duration::from_secs(30))
+///             .rewrite_path(|path| path.to_lowercase()),
+///     );
+/// # Ok(())
+/// # }
+/// ```
+pub struct Proxy {
+    path: String,
+    origin: String,
+    base_path: String,
+    rewrite: Option<Rc<PathRewrite>>,
+    trust_forwarded: bool,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+struct ProxyInner {
+    /// Scheme and authority of the upstream, e.g. `http://backend:8080`.
+    origin: String,
+
+    /// Path of the upstream URL without trailing slash.
+    base_path: String,
+
+    rewrite: Option<Rc<PathRewrite>>,
+
+    /// Use forwarding headers of requests when rewriting `Location` headers.
+    trust_forwarded: bool,
+}
+
+impl Proxy {
+    /// Create proxy for requests under `path` to the `upstream` URL.
+    ///
+    /// # Errors
+    /// Returns an error if `upstream` is not an absolute `http` or `https` URL.
+    pub fn new(path: &str, upstream: &str) -> Result<Self, InvalidUpstream> {
+        let uri = upstream
+            .parse::<actix_http::Uri>()
+            .map_err(|_| InvalidUpstream::Url)?;
+
+        let scheme = match uri.scheme_str() {
+            Some(scheme @ "http") | Some(scheme @ "https") => scheme,
+            _ => return Err(InvalidUpstream::Scheme),
+        };
+
+        let authority = uri.authority().ok_or(InvalidUpstream::Url)?;
+
+        Ok(Self {
+            path: path.trim_end_matches('/').to_owned(),
+            origin: format!("{}://{}", scheme, authority),
+            base_path: uri.path().trim_end_matches('/').to_owned(),
+            rewrite: None,
+            trust_forwarded: false,
+            timeout: None,
+            connect_timeout: None,
+        })
+    }
+
+    /// Set time to wait for the upstream response head, including connecting to the upstream.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set time to wait for a connection to the upstream to be established.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Rewrite path of forwarded requests.
+    ///
+    /// The function is called with the part of the request path after the proxy prefix (starting
+    /// with a `/` unless empty) and returns the path that is appended to the upstream URL. The
+    /// query string is forwarded unchanged.
+    pub fn rewrite_path<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> String + 'static,
+    {
+        self.rewrite = Some(Rc::new(f));
+        self
+    }
+
+    /// Rewrite `Location` headers using the scheme and host from the `Forwarded` and
+    /// `X-Forwarded-*` headers of requests.
+    ///
+    /// Only enable this when the proxy itself runs behind a proxy that sets these headers, since
+    /// clients can send arbitrary values. See [`ConnectionInfo`](crate::dev::ConnectionInfo).
+    ///
+    /// Disabled by default; the scheme and `Host` header the proxy received are used.
+    pub fn trust_forwarded_headers(mut self, trust: bool) -> Self {
+        self.trust_forwarded = trust;
+        self
+    }
+}
+
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("path", &self.path)
+            .field(
+                "upstream",
+                &format_args!("{}{}", self.origin, self.base_path),
+            )
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .finish()
+    }
+}
+
+impl HttpServiceFactory for Proxy {
+    fn register(self, config: &mut AppService) {
+        let rdef = if config.is_root() {
+            ResourceDef::root_prefix(&self.path)
+        } else {
+            ResourceDef::prefix(&self.path)
+        };
+
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl ServiceFactory<ServiceRequest> for Proxy {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = ProxyService;
+    type InitError = ();
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let mut connector = Connector::new();
+        if let Some(timeout) = self.connect_timeout {
+            connector = connector.timeout(timeout);
+        }
+
+        let mut builder = Client::builder()
+            .connector(connector)
+            .disable_redirects()
+            .no_default_headers();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        ok(ProxyService {
+            inner: Rc::new(ProxyInner {
+                origin: self.origin.clone(),
+                base_path: self.base_path.clone(),
+                rewrite: self.rewrite.clone(),
+                trust_forwarded: self.trust_forwarded,
+            }),
+            client: builder.finish(),
+        })
+    }
+}
+
+/// Service created by [`Proxy`].
+pub struct ProxyService {
+    inner: Rc<ProxyInner>,
+    client: Client,
+}
+
+impl fmt::Debug for ProxyService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyService")
+            .field(
+                "upstream",
+                &format_args!("{}{}", self.inner.origin, self.inner.base_path),
+            )
+            .finish()
+    }
+}
+
+impl Service<ServiceRequest> for ProxyService {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::always_ready!();
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let inner = Rc::clone(&self.inner);
+        let client = self.client.clone();
+
+        Box::pin(async move {
+            let (req, payload) = req.into_parts();
+
+            let upgrade = if is_websocket(&req) {
+                req.extensions_mut().remove::<Upgrade>()
+            } else {
+                None
+            };
+
+            let res = match upgrade {
+                Some(upgrade) => inner.forward_websocket(&client, &req, upgrade).await,
+                None => inner.forward(&client, &req, payload).await,
+            };
+
+            let res = res.unwrap_or_else(|err| err.error_response());
+            Ok(ServiceResponse::new(req, res))
+        })
+    }
+}
+
+impl ProxyInner {
+    /// Returns upstream URL for request.
+    fn upstream_url(&self, req: &HttpRequest) -> String {
+        let tail = req.match_info().unprocessed();
+
+        let path = match self.rewrite {
+            Some(ref rewrite) => Cow::Owned(rewrite(tail)),
+            None => Cow::Borrowed(tail),
+        };
+
+        let mut url = format!("{}{}{}", self.origin, self.base_path, path);
+        if self.base_path.is_empty() && path.is_empty() {
+            url.push('/');
+        }
+
+        if !req.query_string().is_empty() {
+            url.push('?');
+            url.push_str(req.query_string());
+        }
+
+        url
+    }
+
+    /// Returns headers to send to the upstream.
+    fn upstream_headers(&self, req: &HttpRequest) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        copy_headers(req.headers(), &mut headers);
+
+        // host is set from upstream URL
+        headers.remove(header::HOST);
+
+        add_forwarded(req, &mut headers);
+        headers
+    }
+
+    /// Forward request to upstream and stream back its response.
+    async fn forward(
+        &self,
+        client: &Client,
+        req: &HttpRequest,
+        payload: Payload,
+    ) -> Result<HttpResponse, ProxyError> {
+        let mut upstream_req = client
+            .request(req.method().clone(), self.upstream_url(req))
+            .no_decompress();
+        *upstream_req.headers_mut() = self.upstream_headers(req);
+
+        let res = match body_length(req) {
+            Some(len) => upstream_req.send_body(SizedStream::new(len, payload)),
+            None if has_streaming_body(req) => upstream_req.send_stream(payload),
+            None => upstream_req.send(),
+        }
+        .await
+        .map_err(ProxyError::from_send)?;
+
+        let mut builder = HttpResponse::build(res.status());
+        let mut headers = HeaderMap::new();
+        copy_headers(res.headers(), &mut headers);
+
+        // body size is set from upstream response below
+        let len = match res.status() {
+            StatusCode::NOT_MODIFIED => None,
+            _ => headers
+                .remove(header::CONTENT_LENGTH)
+                .next()
+                .and_then(|len| len.to_str().ok()?.parse::<u64>().ok()),
+        };
+
+        for (name, value) in headers.iter() {
+            if name == header::LOCATION {
+                if let Some(location) = self.rewrite_location(req, value) {
+                    builder.append_header((header::LOCATION, location));
+                    continue;
+                }
+            }
+
+            builder.append_header((name.clone(), value.clone()));
+        }
+
+        Ok(match len {
+            Some(len) => builder.body(SizedStream::new(len, res)),
+            None => builder.streaming(res),
+        })
+    }
+
+    /// Pass WebSocket handshake through to upstream and tunnel connection once accepted.
+    async fn forward_websocket(
+        &self,
+        client: &Client,
+        req: &HttpRequest,
+        upgrade: Upgrade,
+    ) -> Result<HttpResponse, ProxyError> {
+        let key = match req.headers().get(header::SEC_WEBSOCKET_KEY) {
+            Some(key) => key.clone(),
+            None => return Ok(HttpResponse::BadRequest().finish()),
+        };
+
+        let mut ws_req = client.ws(self.upstream_url(req));
+        for (name, value) in self.upstream_headers(req).iter() {
+            // handshake headers are generated for the upstream connection
+            if name != header::SEC_WEBSOCKET_KEY && name != header::SEC_WEBSOCKET_VERSION {
+                ws_req = ws_req.header(name.clone(), value.clone());
+            }
+        }
+
+        let (res, framed) = match ws_req.connect().await {
+            Ok(res) => res,
+            Err(WsClientError::InvalidResponseStatus(status)) => {
+                return Ok(HttpResponse::build(status).finish())
+            }
+            Err(WsClientError::SendRequest(err)) => return Err(ProxyError::from_send(err)),
+            Err(err) => {
+                log::debug!("WebSocket handshake with upstream failed: {}", err);
+                return Err(ProxyError::Upstream);
+            }
+        };
+
+        let mut builder = HttpResponse::SwitchingProtocols();
+        builder.upgrade("websocket").insert_header((
+            header::SEC_WEBSOCKET_ACCEPT,
+            HeaderValue::from_bytes(&ws::hash_key(key.as_bytes())).unwrap(),
+        ));
+
+        let mut headers = HeaderMap::new();
+        copy_headers(res.headers(), &mut headers);
+        for (name, value) in headers.iter() {
+            if name != header::SEC_WEBSOCKET_ACCEPT && name != header::CONTENT_LENGTH {
+                builder.append_header((name.clone(), value.clone()));
+            }
+        }
+
+        let on_upgrade = upgrade.on_upgrade();
+
+        actix_rt::spawn(async move {
+            use tokio::io::AsyncWriteExt as _;
+
+            let mut client = match on_upgrade.await {
+                Ok(io) => io,
+                Err(_) => return,
+            };
+
+            let mut upstream = framed.into_parts();
+
+            // data received from upstream right after its handshake response
+            if client.write_all(&upstream.read_buf).await.is_err() {
+                return;
+            }
+
+            if let Err(err) = tokio::io::copy_bidirectional(&mut client, &mut upstream.io).await
+            {
+                log::debug!("WebSocket tunnel closed with error: {}", err);
+            }
+        });
+
+        Ok(builder.finish())
+    }
+
+    /// Returns `Location` pointing at the proxy if upstream response points at the upstream.
+    fn rewrite_location(
+        &self,
+        req: &HttpRequest,
+        location: &HeaderValue,
+    ) -> Option<HeaderValue> {
+        let location = location.to_str().ok()?;
+
+        let path = req.match_info().get_ref().path();
+        let mount = &path[..path.len() - req.match_info().unprocessed().len()];
+
+        let rest = match location.strip_prefix(&self.origin) {
+            Some(rest) => {
+                let rest = strip_path_prefix(rest, &self.base_path)?;
+
+                if self.trust_forwarded {
+                    let info = req.connection_info();
+                    format!("{}://{}{}{}", info.scheme(), info.host(), mount, rest)
+                } else {
+                    match received_host(req) {
+                        Some(host) => {
+                            format!("{}://{}{}{}", received_scheme(req), host, mount, rest)
+                        }
+                        None => format!("{}{}", mount, rest),
+                    }
+                }
+            }
+            None if location.starts_with('/') && !location.starts_with("//") => {
+                let rest = strip_path_prefix(location, &self.base_path)?;
+                format!("{}{}", mount, rest)
+            }
+            None => return None,
+        };
+
+        HeaderValue::from_str(&rest).ok()
+    }
+}
+
+/// Error returned by [`Proxy::new`] for invalid upstream URLs.
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum InvalidUpstream {
+    /// Upstream URL could not be parsed or has no host.
+    #[display(fmt = "Upstream URL is invalid")]
+    Url,
+
+    /// Upstream URL does not use the `http` or `https` scheme.
+    #[display(fmt = "Upstream URL must use http or https scheme")]
+    Scheme,
+}
+
+impl std::error::Error for InvalidUpstream {}
+
+/// Errors that can occur when forwarding a request to the upstream.
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum ProxyError {
+    /// Request could not be forwarded or upstream response was invalid.
+    #[display(fmt = "Request could not be forwarded to upstream")]
+    Upstream,
+
+    /// Upstream did not respond in time.
+    #[display(fmt = "Upstream did not respond in time")]
+    Timeout,
+}
+
+impl ProxyError {
+    fn from_send(err: SendRequestError) -> Self {
+        match err {
+            SendRequestError::Timeout => ProxyError::Timeout,
+            err => {
+                log::debug!("Request could not be forwarded to upstream: {}", err);
+                ProxyError::Upstream
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {}
+
+impl ResponseError for ProxyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ProxyError::Upstream => StatusCode::BAD_GATEWAY,
+            ProxyError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+}
+
+/// Returns true if request is a WebSocket handshake.
+fn is_websocket(req: &HttpRequest) -> bool {
+    req.method() == Method::GET
+        && req.head().upgrade()
+        && req
+            .headers()
+            .get(header::UPGRADE)
+            .and_then(|hdr| hdr.to_str().ok())
+            .map_or(false, |proto| proto.eq_ignore_ascii_case("websocket"))
+}
+
+/// Returns length of request body, if known up front.
+fn body_length(req: &HttpRequest) -> Option<u64> {
+    if req.headers().contains_key(header::TRANSFER_ENCODING) {
+        return None;
+    }
+
+    req.headers()
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Returns true if request has a body of unknown length.
+fn has_streaming_body(req: &HttpRequest) -> bool {
+    req.headers().contains_key(header::TRANSFER_ENCODING)
+        || (req.version() == Version::HTTP_2
+            && matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH))
+}
+
+/// Copy end-to-end headers.
+fn copy_headers(src: &HeaderMap, dst: &mut HeaderMap) {
+    let listed = src
+        .get_all(header::CONNECTION)
+        .filter_map(|hdr| hdr.to_str().ok())
+        .flat_map(|hdr| hdr.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+
+    for (name, value) in src.iter() {
+        let name_str = name.as_str();
+
+        if !HOP_BY_HOP.contains(&name_str) && !listed.iter().any(|listed| listed == name_str) {
+            dst.append(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Returns scheme of the connection the proxy received request on.
+fn received_scheme(req: &HttpRequest) -> &'static str {
+    if req.app_config().secure() {
+        "https"
+    } else {
+        "http"
+    }
+}
+
+/// Returns host the proxy received request for, ignoring forwarding headers.
+fn received_host(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(header::HOST)
+        .and_then(|hdr| hdr.to_str().ok())
+        .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
+}
+
+/// Add headers describing the client connection to upstream request headers.
+fn add_forwarded(req: &HttpRequest, headers: &mut HeaderMap) {
+    let proto = received_scheme(req);
+    let host = received_host(req);
+
+    let mut forwarded = Vec::new();
+    let peer_ip = req.peer_addr().map(|addr| addr.ip());
+
+    if let Some(ip) = peer_ip {
+        forwarded.push(match ip {
+            IpAddr::V4(ip) => format!("for={}", ip),
+            IpAddr::V6(ip) => format!("for=\"[{}]\"", ip),
+        });
+    }
+
+    if let Some(host) = host {
+        forwarded.push(format!("host={}", forwarded_value(host)));
+    }
+
+    forwarded.push(format!("proto={}", proto));
+
+    append_list(headers, header::FORWARDED, &forwarded.join(";"));
+
+    if let Some(ip) = peer_ip {
+        append_list(
+            headers,
+            HeaderName::from_static("x-forwarded-for"),
+            &ip.to_string(),
+        );
+    }
+
+    // values sent by the client are not trusted
+    headers.insert(
+        HeaderName::from_static("x-forwarded-proto"),
+        HeaderValue::from_static(proto),
+    );
+
+    let host_name = HeaderName::from_static("x-forwarded-host");
+    match host.and_then(|host| HeaderValue::from_str(host).ok()) {
+        Some(host) => headers.insert(host_name, host),
+        None => headers.remove(host_name),
+    };
+}
+
+/// Append element to comma separated list header, merging existing values into one.
+fn append_list(headers: &mut HeaderMap, name: HeaderName, element: &str) {
+    let mut list = headers
+        .remove(&name)
+        .filter_map(|hdr| hdr.to_str().ok().map(ToOwned::to_owned))
+        .collect::<Vec<_>>();
+    list.push(element.to_owned());
+
+    if let Ok(value) = HeaderValue::from_str(&list.join(", ")) {
+        headers.insert(name, value);
+    }
+}
+
+/// Returns value for `Forwarded` header, quoted if it is not a token.
+fn forwarded_value(value: &str) -> Cow<'_, str> {
+    let is_token = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+
+    if is_token {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!(
+            "\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
+    }
+}
+
+/// Strip path prefix, only at segment boundary.
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(prefix)?;
+
+    if rest.is_empty() || rest.starts_with('/') || rest.starts_with('?') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn hop_by_hop_headers() {
+        let req = TestRequest::default()
+            .insert_header((header::CONNECTION, "keep-alive, x-secret"))
+            .insert_header(("keep-alive", "timeout=5"))
+            .insert_header(("x-secret", "1"))
+            .insert_header((header::TE, "trailers"))
+            .insert_header((header::ACCEPT, "*/*"))
+            .to_http_request();
+
+        let mut headers = HeaderMap::new();
+        copy_headers(req.headers(), &mut headers);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get(header::ACCEPT).unwrap(), "*/*");
+    }
+
+    #[test]
+    fn forwarded_headers() {
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "example.com:8080"))
+            .insert_header((header::FORWARDED, "for=192.0.2.1"))
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .insert_header(("x-forwarded-proto", "https"))
+            .insert_header(("x-forwarded-host", "spoofed.example"))
+            .peer_addr("[2001:db8::1]:1234".parse().unwrap())
+            .to_http_request();
+
+        let mut headers = req.headers().clone();
+        add_forwarded(&req, &mut headers);
+
+        assert_eq!(
+            headers.get(header::FORWARDED).unwrap(),
+            "for=192.0.2.1, for=\"[2001:db8::1]\";host=\"example.com:8080\";proto=http"
+        );
+        assert_eq!(
+            headers.get("x-forwarded-for").unwrap(),
+            "192.0.2.1, 2001:db8::1"
+        );
+        assert_eq!(headers.get("x-forwarded-proto").unwrap(), "http");
+        assert_eq!(headers.get("x-forwarded-host").unwrap(), "example.com:8080");
+    }
+
+    #[test]
+    fn path_prefix() {
+        assert_eq!(strip_path_prefix("/v1/users", "/v1"), Some("/users"));
+        assert_eq!(strip_path_prefix("/v1", "/v1"), Some(""));
+        assert_eq!(strip_path_prefix("/v10", "/v1"), None);
+        assert_eq!(strip_path_prefix("/users", ""), Some("/users"));
+    }
+
+    #[test]
+    fn invalid_upstream() {
+        assert!(matches!(
+            Proxy::new("/", "ftp://example.com"),
+            Err(InvalidUpstream::Scheme)
+        ));
+        assert!(matches!(
+            Proxy::new("/", "/relative"),
+            Err(InvalidUpstream::Scheme)
+        ));
+        assert!(matches!(
+            Proxy::new("/", "http://exa mple.com"),
+            Err(InvalidUpstream::Url)
+        ));
+        assert!(Proxy::new("/", "http://example.com/v1").is_ok());
+    }
+}
@@ -4,9 +4,15 @@ use std::{
     marker::PhantomData,
     net,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use actix_http::{body::MessageBody, Extensions, HttpService, KeepAlive, Request, Response};
+use actix_http::{
+    body::{BoxBody, MessageBody},
+    header::HeaderValue,
+    ConnectionStats, Extensions, HttpServiceBuilder, KeepAlive, Request, Response,
+    ShutdownSignal,
+};
 use actix_server::{Server, ServerBuilder};
 use actix_service::{
     map_config, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
@@ -29,6 +35,98 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    h1_header_read_timeout: Option<Duration>,
+    h1_min_body_rate: Option<(u64, Duration)>,
+    max_requests_per_conn: Option<usize>,
+    conn_lifetime: Option<Duration>,
+    connection_stats: bool,
+    h2c: bool,
+    on_disconnect: Option<Arc<dyn Fn(&ConnectionStats) + Send + Sync>>,
+    h2_max_concurrent_streams: Option<u32>,
+    h2_initial_window_size: Option<u32>,
+    h2_initial_connection_window_size: Option<u32>,
+    h2_max_frame_size: Option<u32>,
+    h2_max_header_list_size: Option<u32>,
+    h2_max_concurrent_reset_streams: Option<usize>,
+    h2_reset_stream_duration: Option<Duration>,
+    h2_max_pending_accept_reset_streams: Option<usize>,
+    h2_max_local_error_reset_streams: Option<usize>,
+    h2_enable_connect_protocol: bool,
+    shutdown_signal: ShutdownSignal,
+    alt_svc: Option<HeaderValue>,
+    #[cfg(feature = "http3")]
+    quic: Vec<quic::Pending>,
+}
+
+impl Config {
+    /// Create `HttpServiceBuilder` with the configured connection settings applied.
+    fn service_builder<T, S>(&self) -> HttpServiceBuilder<T, S>
+    where
+        S: ServiceFactory<Request, Config = ()>,
+        S::Error: Into<Response<BoxBody>> + 'static,
+        S::InitError: fmt::Debug,
+        <S::Service as Service<Request>>::Future: 'static,
+    {
+        let mut svc = HttpServiceBuilder::new()
+            .keep_alive(self.keep_alive)
+            .client_timeout(self.client_timeout)
+            .client_disconnect(self.client_shutdown)
+            .shutdown_signal(self.shutdown_signal.clone());
+
+        if let Some(timeout) = self.h1_header_read_timeout {
+            svc = svc.h1_header_read_timeout(timeout);
+        }
+        if let Some((bytes, period)) = self.h1_min_body_rate {
+            svc = svc.h1_min_body_rate(bytes, period);
+        }
+        if let Some(max) = self.max_requests_per_conn {
+            svc = svc.max_requests_per_conn(max);
+        }
+        if let Some(lifetime) = self.conn_lifetime {
+            svc = svc.conn_lifetime(lifetime);
+        }
+        if self.connection_stats {
+            svc = svc.connection_stats(true);
+        }
+        if let Some(hook) = self.on_disconnect.clone() {
+            svc = svc.on_disconnect(move |stats| hook(stats));
+        }
+        if let Some(max) = self.h2_max_concurrent_streams {
+            svc = svc.h2_max_concurrent_streams(max);
+        }
+        if let Some(size) = self.h2_initial_window_size {
+            svc = svc.h2_initial_window_size(size);
+        }
+        if let Some(size) = self.h2_initial_connection_window_size {
+            svc = svc.h2_initial_connection_window_size(size);
+        }
+        if let Some(max) = self.h2_max_frame_size {
+            svc = svc.h2_max_frame_size(max);
+        }
+        if let Some(max) = self.h2_max_header_list_size {
+            svc = svc.h2_max_header_list_size(max);
+        }
+        if let Some(max) = self.h2_max_concurrent_reset_streams {
+            svc = svc.h2_max_concurrent_reset_streams(max);
+        }
+        if let Some(dur) = self.h2_reset_stream_duration {
+            svc = svc.h2_reset_stream_duration(dur);
+        }
+        if let Some(max) = self.h2_max_pending_accept_reset_streams {
+            svc = svc.h2_max_pending_accept_reset_streams(max);
+        }
+        if let Some(max) = self.h2_max_local_error_reset_streams {
+            svc = svc.h2_max_local_error_reset_streams(max);
+        }
+        if self.h2_enable_connect_protocol {
+            svc = svc.h2_enable_connect_protocol(true);
+        }
+        if let Some(alt_svc) = self.alt_svc.clone() {
+            svc = svc.alt_svc(alt_svc);
+        }
+
+        svc
+    }
 }
 
 /// An HTTP Server.
@@ -64,6 +162,7 @@ where
     sockets: Vec<Socket>,
     builder: ServerBuilder,
     on_connect_fn: Option<Arc<dyn Fn(&dyn Any, &mut Extensions) + Send + Sync>>,
+    handle_signals: bool,
     _phantom: PhantomData<(S, B)>,
 }
 
@@ -90,11 +189,33 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                h1_header_read_timeout: None,
+                h1_min_body_rate: None,
+                max_requests_per_conn: None,
+                conn_lifetime: None,
+                connection_stats: false,
+                h2c: false,
+                on_disconnect: None,
+                h2_max_concurrent_streams: None,
+                h2_initial_window_size: None,
+                h2_initial_connection_window_size: None,
+                h2_max_frame_size: None,
+                h2_max_header_list_size: None,
+                h2_max_concurrent_reset_streams: None,
+                h2_reset_stream_duration: None,
+                h2_max_pending_accept_reset_streams: None,
+                h2_max_local_error_reset_streams: None,
+                h2_enable_connect_protocol: false,
+                shutdown_signal: ShutdownSignal::new(),
+                alt_svc: None,
+                #[cfg(feature = "http3")]
+                quic: Vec::new(),
             })),
             backlog: 1024,
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
             on_connect_fn: None,
+            handle_signals: true,
             _phantom: PhantomData,
         }
     }
@@ -120,6 +241,7 @@ where
             sockets: self.sockets,
             builder: self.builder,
             on_connect_fn: Some(Arc::new(f)),
+            handle_signals: self.handle_signals,
             _phantom: PhantomData,
         }
     }
@@ -217,6 +339,197 @@ where
         self
     }
 
+    /// Set time limit for receiving the head of each HTTP/1 request, counted from its first byte.
+    ///
+    /// Unlike [`client_timeout`](Self::client_timeout), this also applies to requests after the
+    /// first one on a keep-alive connection. Clients that are too slow get a
+    /// `408 Request Timeout` response and the connection is closed.
+    ///
+    /// By default, there is no limit.
+    pub fn h1_header_read_timeout(self, timeout: Duration) -> Self {
+        self.config.lock().unwrap().h1_header_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set minimum rate at which HTTP/1 request body data must be received: at least `bytes`
+    /// bytes per `period`.
+    ///
+    /// Clients sending the body slower get a `408 Request Timeout` response (or, if the handler
+    /// already responded, have their connection closed) and the request payload fails with
+    /// [`PayloadError::Timeout`](crate::error::PayloadError::Timeout).
+    ///
+    /// By default, there is no minimum rate.
+    pub fn h1_min_body_rate(self, bytes: u64, period: Duration) -> Self {
+        self.config.lock().unwrap().h1_min_body_rate = Some((bytes, period));
+        self
+    }
+
+    /// Set maximum number of requests served on a single connection.
+    ///
+    /// HTTP/1 connections are closed after answering the last allowed request with
+    /// `Connection: close`; HTTP/2 connections are sent a GOAWAY frame. This helps layer 4 load
+    /// balancers spread long-lived clients across instances.
+    ///
+    /// By default, the number of requests is not limited.
+    pub fn max_requests_per_conn(self, max: usize) -> Self {
+        self.config.lock().unwrap().max_requests_per_conn = Some(max);
+        self
+    }
+
+    /// Set maximum lifetime of a connection.
+    ///
+    /// Older HTTP/1 connections are closed when idle or after their next response, which carries
+    /// `Connection: close`; HTTP/2 connections are sent a GOAWAY frame.
+    ///
+    /// By default, the lifetime of connections is not limited.
+    pub fn conn_lifetime(self, lifetime: Duration) -> Self {
+        self.config.lock().unwrap().conn_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Enable collection of per-connection statistics.
+    ///
+    /// When enabled, a [`ConnectionStats`](crate::dev::ConnectionStats) handle can be retrieved
+    /// from the connection data using [`HttpRequest::conn_data`](crate::HttpRequest::conn_data).
+    ///
+    /// By default, statistics are not collected.
+    pub fn connection_stats(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().connection_stats = enabled;
+        self
+    }
+
+    /// Sets the callback to be run with the statistics of each connection once it is closed.
+    ///
+    /// Setting a callback enables [connection statistics](Self::connection_stats).
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// HttpServer::new(|| App::new().route("/", web::get().to(HttpResponse::Ok)))
+    ///     .on_disconnect(|stats| {
+    ///         log::info!(
+    ///             "{:?} connection closed after {} requests ({} bytes in, {} bytes out)",
+    ///             stats.protocol(),
+    ///             stats.requests(),
+    ///             stats.bytes_read(),
+    ///             stats.bytes_written(),
+    ///         );
+    ///     });
+    /// ```
+    pub fn on_disconnect<H>(self, f: H) -> Self
+    where
+        H: Fn(&ConnectionStats) + Send + Sync + 'static,
+    {
+        self.config.lock().unwrap().on_disconnect = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the maximum number of concurrent HTTP/2 streams the client may open per connection.
+    ///
+    /// By default, the number of concurrent streams is not limited.
+    pub fn h2_max_concurrent_streams(self, max: u32) -> Self {
+        self.config.lock().unwrap().h2_max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set the initial HTTP/2 stream-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn h2_initial_window_size(self, size: u32) -> Self {
+        self.config.lock().unwrap().h2_initial_window_size = Some(size);
+        self
+    }
+
+    /// Set the initial HTTP/2 connection-level flow control window size in bytes.
+    ///
+    /// By default the window size is set to 65,535 bytes.
+    pub fn h2_initial_connection_window_size(self, size: u32) -> Self {
+        self.config
+            .lock()
+            .unwrap()
+            .h2_initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set the maximum HTTP/2 frame size in bytes the server is willing to receive.
+    ///
+    /// The value must be between 16,384 and 16,777,215 bytes. By default it is set to 16,384.
+    pub fn h2_max_frame_size(self, max: u32) -> Self {
+        self.config.lock().unwrap().h2_max_frame_size = Some(max);
+        self
+    }
+
+    /// Set the maximum size in bytes of the HTTP/2 header list the server is willing to accept.
+    ///
+    /// By default it is set to 16MiB.
+    pub fn h2_max_header_list_size(self, max: u32) -> Self {
+        self.config.lock().unwrap().h2_max_header_list_size = Some(max);
+        self
+    }
+
+    /// Set the maximum number of concurrently reset HTTP/2 streams.
+    ///
+    /// By default at most 10 reset streams are tracked.
+    pub fn h2_max_concurrent_reset_streams(self, max: usize) -> Self {
+        self.config.lock().unwrap().h2_max_concurrent_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the duration for which locally reset HTTP/2 streams are remembered.
+    ///
+    /// By default reset streams are remembered for 30 seconds.
+    pub fn h2_reset_stream_duration(self, dur: Duration) -> Self {
+        self.config.lock().unwrap().h2_reset_stream_duration = Some(dur);
+        self
+    }
+
+    /// Set the maximum number of HTTP/2 streams that were reset by the client before they were
+    /// accepted by the server.
+    ///
+    /// Once the limit is reached the connection is closed. This protects against "rapid reset"
+    /// floods. By default the limit is 20.
+    pub fn h2_max_pending_accept_reset_streams(self, max: usize) -> Self {
+        self.config
+            .lock()
+            .unwrap()
+            .h2_max_pending_accept_reset_streams = Some(max);
+        self
+    }
+
+    /// Set the maximum number of HTTP/2 streams the server may reset because of client errors.
+    ///
+    /// Once the limit is reached the connection is closed. By default the limit is 1024.
+    pub fn h2_max_local_error_reset_streams(self, max: usize) -> Self {
+        self.config.lock().unwrap().h2_max_local_error_reset_streams = Some(max);
+        self
+    }
+
+    /// Advertise support for extended CONNECT requests (RFC 8441) on HTTP/2 connections.
+    ///
+    /// Clients use extended CONNECT to open WebSockets over HTTP/2 streams. Only enable this when
+    /// the application has WebSocket routes.
+    ///
+    /// By default, extended CONNECT is not advertised.
+    pub fn h2_enable_connect_protocol(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().h2_enable_connect_protocol = enabled;
+        self
+    }
+
+    /// Enable HTTP/2 over cleartext (h2c) on plain TCP and Unix socket listeners.
+    ///
+    /// Connections may then switch from HTTP/1.x to HTTP/2, either by sending the HTTP/2
+    /// connection preface directly or by upgrading with an `Upgrade: h2c` request. Only enable
+    /// this when the clients or reverse proxies in front of the server are known to handle h2c;
+    /// a proxy that forwards `Upgrade` headers unchecked may otherwise be tunneled through.
+    /// TLS listeners negotiate HTTP/2 using ALPN and are not affected.
+    ///
+    /// By default, h2c is disabled.
+    pub fn h2c(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().h2c = enabled;
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -238,6 +551,7 @@ where
     /// Disable signal handling
     pub fn disable_signals(mut self) -> Self {
         self.builder = self.builder.disable_signals();
+        self.handle_signals = false;
         self
     }
 
@@ -253,6 +567,21 @@ where
         self
     }
 
+    /// Set the signal that starts draining open connections on graceful shutdown.
+    ///
+    /// Once the signal is triggered, HTTP/1 connections send `Connection: close` with the next
+    /// response and idle keep-alive connections are closed. HTTP/2 connections send `GOAWAY` and
+    /// are closed after in-flight streams complete. Requests that are still running have until
+    /// the [shutdown timeout](Self::shutdown_timeout) to finish.
+    ///
+    /// Unless signal handling is disabled, the signal is triggered when the server receives
+    /// `SIGTERM`, which starts a graceful stop. Trigger it before stopping the server through its
+    /// [handle](actix_server::ServerHandle::stop) to drain connections in the same way.
+    pub fn shutdown_signal(self, signal: ShutdownSignal) -> Self {
+        self.config.lock().unwrap().shutdown_signal = signal;
+        self
+    }
+
     /// Get addresses of bound sockets.
     pub fn addrs(&self) -> Vec<net::SocketAddr> {
         self.sockets.iter().map(|s| s.addr).collect()
@@ -272,6 +601,8 @@ where
     ///
     /// HttpServer does not change any configuration for TcpListener,
     /// it needs to be configured before passing it to listen() method.
+    ///
+    /// Connections use HTTP/1.x, or HTTP/2 over cleartext if [`h2c`](Self::h2c) is enabled.
     pub fn listen(mut self, lst: net::TcpListener) -> io::Result<Self> {
         let cfg = self.config.clone();
         let factory = self.factory.clone();
@@ -285,14 +616,13 @@ where
         self.builder =
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
+                    #[cfg(feature = "http3")]
+                    quic::start_next(&cfg);
+
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
 
-                    let mut svc = HttpService::build()
-                        .keep_alive(c.keep_alive)
-                        .client_timeout(c.client_timeout)
-                        .client_disconnect(c.client_shutdown)
-                        .local_addr(addr);
+                    let mut svc = c.service_builder().h2c(c.h2c).local_addr(addr);
 
                     if let Some(handler) = on_connect_fn.clone() {
                         svc = svc.on_connect_ext(move |io: &_, ext: _| {
@@ -343,14 +673,13 @@ where
         self.builder =
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
+                    #[cfg(feature = "http3")]
+                    quic::start_next(&cfg);
+
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
 
-                    let svc = HttpService::build()
-                        .keep_alive(c.keep_alive)
-                        .client_timeout(c.client_timeout)
-                        .client_disconnect(c.client_shutdown)
-                        .local_addr(addr);
+                    let svc = c.service_builder().local_addr(addr);
 
                     let svc = if let Some(handler) = on_connect_fn.clone() {
                         svc.on_connect_ext(move |io: &_, ext: _| {
@@ -404,13 +733,13 @@ where
         self.builder =
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
+                    #[cfg(feature = "http3")]
+                    quic::start_next(&cfg);
+
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
 
-                    let svc = HttpService::build()
-                        .keep_alive(c.keep_alive)
-                        .client_timeout(c.client_timeout)
-                        .client_disconnect(c.client_shutdown);
+                    let svc = c.service_builder();
 
                     let svc = if let Some(handler) = on_connect_fn.clone() {
                         svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
@@ -505,6 +834,39 @@ where
         Ok(self)
     }
 
+    #[cfg(feature = "http3")]
+    /// Start listening for incoming HTTP/3 (QUIC) connections.
+    ///
+    /// This method sets the alpn protocol to "h3". Each bound QUIC endpoint is served by one of the
+    /// server workers, so at least one TCP or Unix socket listener has to be bound as well. All
+    /// HTTP/1 and HTTP/2 listeners of this server advertise the endpoints using an `Alt-Svc`
+    /// header. The bound UDP sockets are not included in [`addrs`](Self::addrs).
+    pub fn bind_quic<A: net::ToSocketAddrs>(
+        self,
+        addr: A,
+        mut config: RustlsServerConfig,
+    ) -> io::Result<Self> {
+        config.alpn_protocols = vec![actix_http::h3::ALPN.to_vec()];
+
+        for addr in addr.to_socket_addrs()? {
+            let socket = net::UdpSocket::bind(addr)?;
+            let addr = socket.local_addr()?;
+
+            let quic = QuicSocket {
+                socket,
+                addr,
+                config: config.clone(),
+            };
+            let pending = quic::pending(quic, self.factory.clone(), self.config.clone());
+
+            let mut c = self.config.lock().unwrap();
+            c.alt_svc = Some(quic::alt_svc(c.alt_svc.as_ref(), addr.port())?);
+            c.quic.push(pending);
+        }
+
+        Ok(self)
+    }
+
     #[cfg(unix)]
     /// Start listening for unix domain (UDS) connections on existing listener.
     pub fn listen_uds(mut self, lst: std::os::unix::net::UnixListener) -> io::Result<Self> {
@@ -526,6 +888,9 @@ where
         let on_connect_fn = self.on_connect_fn.clone();
 
         self.builder = self.builder.listen_uds(name, lst, move || {
+            #[cfg(feature = "http3")]
+            quic::start_next(&cfg);
+
             let c = cfg.lock().unwrap();
             let config = AppConfig::new(
                 false,
@@ -534,10 +899,7 @@ where
             );
 
             fn_service(|io: UnixStream| async { Ok((io, Protocol::Http1, None)) }).and_then({
-                let mut svc = HttpService::build()
-                    .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout)
-                    .client_disconnect(c.client_shutdown);
+                let mut svc = c.service_builder().h2c(c.h2c);
 
                 if let Some(handler) = on_connect_fn.clone() {
                     svc = svc
@@ -578,6 +940,9 @@ where
             format!("actix-web-service-{:?}", addr.as_ref()),
             addr,
             move || {
+                #[cfg(feature = "http3")]
+                quic::start_next(&cfg);
+
                 let c = cfg.lock().unwrap();
                 let config = AppConfig::new(
                     false,
@@ -590,10 +955,8 @@ where
                     .map_err(|err| err.into().error_response());
 
                 fn_service(|io: UnixStream| async { Ok((io, Protocol::Http1, None)) }).and_then(
-                    HttpService::build()
-                        .keep_alive(c.keep_alive)
-                        .client_timeout(c.client_timeout)
-                        .client_disconnect(c.client_shutdown)
+                    c.service_builder()
+                        .h2c(c.h2c)
                         .finish(map_config(fac, move |_| config.clone())),
                 )
             },
@@ -636,10 +999,196 @@ where
     /// }
     /// ```
     pub fn run(self) -> Server {
+        if self.handle_signals {
+            #[cfg(unix)]
+            trigger_on_sigterm(self.config.lock().unwrap().shutdown_signal.clone());
+        }
+
         self.builder.run()
     }
 }
 
+#[cfg(feature = "http3")]
+struct QuicSocket {
+    socket: net::UdpSocket,
+    addr: net::SocketAddr,
+    config: RustlsServerConfig,
+}
+
+#[cfg(feature = "http3")]
+mod quic {
+    use std::rc::Rc;
+
+    use actix_http::h3::reexports::{Endpoint, EndpointConfig, ServerConfig};
+    use futures_util::future::{select, Either};
+
+    use super::*;
+
+    /// HTTP/3 endpoint waiting to be started on a server worker.
+    pub(super) type Pending = Box<dyn FnOnce() + Send>;
+
+    /// Returns `Alt-Svc` header value advertising HTTP/3 on `port` in addition to the entries of
+    /// `alt_svc`.
+    pub(super) fn alt_svc(alt_svc: Option<&HeaderValue>, port: u16) -> io::Result<HeaderValue> {
+        let entry = format!("h3=\":{}\"; ma=86400", port);
+
+        let value = match alt_svc {
+            Some(alt_svc) => {
+                let alt_svc = alt_svc
+                    .to_str()
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+                if alt_svc.split(", ").any(|item| item == entry) {
+                    alt_svc.to_owned()
+                } else {
+                    format!("{}, {}", alt_svc, entry)
+                }
+            }
+            None => entry,
+        };
+
+        HeaderValue::from_str(&value)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    /// Starts the next pending HTTP/3 endpoint, if any, on the current server worker.
+    pub(super) fn start_next(cfg: &Mutex<Config>) {
+        let pending = cfg.lock().unwrap().quic.pop();
+
+        if let Some(start) = pending {
+            start();
+        }
+    }
+
+    /// Creates pending endpoint serving HTTP/3 connections of a QUIC socket until the shutdown
+    /// signal is triggered and all open connections are closed.
+    pub(super) fn pending<F, I, S, B>(
+        quic: QuicSocket,
+        factory: F,
+        cfg: Arc<Mutex<Config>>,
+    ) -> Pending
+    where
+        F: Fn() -> I + Send + Clone + 'static,
+        I: IntoServiceFactory<S, Request>,
+
+        S: ServiceFactory<Request, Config = AppConfig> + 'static,
+        S::Error: Into<Error> + 'static,
+        S::InitError: fmt::Debug,
+        S::Response: Into<Response<B>> + 'static,
+        <S::Service as Service<Request>>::Future: 'static,
+        S::Service: 'static,
+
+        B: MessageBody + 'static,
+    {
+        Box::new(move || {
+            let addr = quic.addr;
+
+            actix_rt::spawn(async move {
+                if let Err(err) = serve(quic, factory, cfg).await {
+                    log::error!("Can not start HTTP/3 service on {}: {}", addr, err);
+                }
+            });
+        })
+    }
+
+    async fn serve<F, I, S, B>(
+        quic: QuicSocket,
+        factory: F,
+        cfg: Arc<Mutex<Config>>,
+    ) -> io::Result<()>
+    where
+        F: Fn() -> I + Send + Clone + 'static,
+        I: IntoServiceFactory<S, Request>,
+
+        S: ServiceFactory<Request, Config = AppConfig> + 'static,
+        S::Error: Into<Error> + 'static,
+        S::InitError: fmt::Debug,
+        S::Response: Into<Response<B>> + 'static,
+        <S::Service as Service<Request>>::Future: 'static,
+        S::Service: 'static,
+
+        B: MessageBody + 'static,
+    {
+        let addr = quic.addr;
+
+        let (svc, shutdown) = {
+            let c = cfg.lock().unwrap();
+            let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+
+            let fac = factory()
+                .into_factory()
+                .map_err(|err| err.into().error_response());
+
+            let svc = c
+                .service_builder::<(), _>()
+                .local_addr(addr)
+                .secure()
+                .h3(map_config(fac, move |_| {
+                    AppConfig::new(true, host.clone(), addr)
+                }));
+
+            (svc, c.shutdown_signal.clone())
+        };
+
+        let svc = svc.new_service(()).await.map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("service init error: {:?}", err),
+            )
+        })?;
+        let svc = Rc::new(svc);
+
+        let server_config = ServerConfig::with_crypto(Arc::new(quic.config));
+        let endpoint =
+            Endpoint::new(EndpointConfig::default(), Some(server_config), quic.socket)?;
+
+        let triggered = shutdown.triggered();
+        actix_rt::pin!(triggered);
+
+        loop {
+            let accept = endpoint.accept();
+            actix_rt::pin!(accept);
+
+            match select(accept, triggered.as_mut()).await {
+                Either::Left((Some(conn), _)) => {
+                    let svc = Rc::clone(&svc);
+
+                    actix_rt::spawn(async move {
+                        if let Err(err) = svc.call(conn).await {
+                            log::trace!("HTTP/3 connection error: {}", err);
+                        }
+                    });
+                }
+
+                Either::Left((None, _)) | Either::Right(_) => break,
+            }
+        }
+
+        // stop accepting connections and let open ones finish their requests
+        endpoint.wait_idle().await;
+
+        Ok(())
+    }
+}
+
+/// Trigger shutdown signal when the process receives `SIGTERM`, on which `actix-server` starts a
+/// graceful stop. Stopping the server is left to `actix-server`.
+#[cfg(unix)]
+fn trigger_on_sigterm(signal: ShutdownSignal) {
+    use actix_rt::signal::unix::{signal as unix_signal, SignalKind};
+
+    match unix_signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            actix_rt::spawn(async move {
+                if sigterm.recv().await.is_some() {
+                    signal.trigger();
+                }
+            });
+        }
+        Err(err) => log::error!("Can not initialize SIGTERM handler: {}", err),
+    }
+}
+
 fn create_tcp_listener(addr: net::SocketAddr, backlog: u32) -> io::Result<net::TcpListener> {
     use socket2::{Domain, Protocol, Socket, Type};
     let domain = Domain::for_address(addr);
@@ -180,3 +180,24 @@ where
     let fut = actix_rt::task::spawn_blocking(f);
     async { fut.await.map_err(|_| BlockingError) }
 }
+
+/// Creates a reverse proxy for requests under `path` to the `upstream` URL.
+///
+/// See [`Proxy`](crate::proxy::Proxy) for details.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new().service(web::proxy("/api", "http://127.0.0.1:8081/v1").unwrap());
+/// ```
+///
+/// # Errors
+/// Returns an error if `upstream` is not an absolute `http` or `https` URL.
+#[cfg(feature = "proxy")]
+pub fn proxy(
+    path: &str,
+    upstream: &str,
+) -> Result<crate::proxy::Proxy, crate::proxy::InvalidUpstream> {
+    crate::proxy::Proxy::new(path, upstream)
+}
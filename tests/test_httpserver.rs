@@ -1,7 +1,9 @@
 #[cfg(feature = "openssl")]
 extern crate tls_openssl as openssl;
+#[cfg(feature = "http3")]
+extern crate tls_rustls as rustls;
 
-#[cfg(any(unix, feature = "openssl"))]
+#[cfg(any(unix, feature = "openssl", feature = "http3"))]
 use {
     actix_web::{web, App, HttpResponse, HttpServer},
     std::{sync::mpsc, thread, time::Duration},
@@ -55,6 +57,182 @@ async fn test_start() {
     srv.stop(false).await;
 }
 
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_graceful_stop_drains_connections() {
+    use std::io::{Read as _, Write as _};
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let signal = actix_http::ShutdownSignal::new();
+    let trigger = signal.clone();
+
+    let server = thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new()
+                        .service(web::resource("/").to(|| HttpResponse::Ok().body("test")))
+                })
+                .workers(1)
+                .keep_alive(60)
+                .shutdown_timeout(60)
+                .disable_signals()
+                .shutdown_signal(signal)
+                .bind(format!("{}", addr))
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    let mut data = [0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // idle keep-alive connection is closed instead of holding up the stop until the timeout
+    trigger.trigger();
+    srv.stop(true).await;
+    assert_eq!(stream.read(&mut data).unwrap(), 0);
+
+    server.join().unwrap();
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_h2c_opt_in() {
+    use std::io::{Read as _, Write as _};
+
+    for &h2c in &[false, true] {
+        let addr = actix_test::unused_addr();
+        let (tx, rx) = mpsc::channel();
+
+        let server = thread::spawn(move || {
+            actix_rt::System::new()
+                .block_on(async {
+                    let srv = HttpServer::new(|| {
+                        App::new().service(web::resource("/").to(HttpResponse::Ok))
+                    })
+                    .workers(1)
+                    .h2c(h2c)
+                    .disable_signals()
+                    .bind(format!("{}", addr))
+                    .unwrap()
+                    .run();
+
+                    tx.send(srv.handle()).unwrap();
+
+                    srv.await
+                })
+                .unwrap();
+        });
+
+        let srv = rx.recv().unwrap();
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        stream
+            .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            .unwrap();
+        // empty SETTINGS frame
+        stream.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).unwrap();
+
+        let mut data = [0; 1024];
+        let n = stream.read(&mut data).unwrap();
+
+        // HTTP/2 server answers with its SETTINGS frame, HTTP/1 with an error response
+        assert_eq!(data[..n].starts_with(b"HTTP/1.1 "), !h2c);
+        if h2c {
+            assert_eq!(data[3], 4);
+        }
+
+        srv.stop(false).await;
+        server.join().unwrap();
+    }
+}
+
+#[cfg(feature = "http3")]
+#[actix_rt::test]
+async fn test_quic_alt_svc() {
+    use rustls::{Certificate, PrivateKey, ServerConfig};
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(cert.serialize_der().unwrap())],
+            PrivateKey(cert.serialize_private_key_der()),
+        )
+        .unwrap();
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let server = thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().service(web::resource("/").to(HttpResponse::Ok))
+                })
+                .workers(1)
+                .disable_signals()
+                .bind(format!("{}", addr))
+                .unwrap()
+                .bind_quic("127.0.0.1:0", config.clone())
+                .unwrap()
+                .bind_quic("127.0.0.1:0", config)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let response = awc::Client::new()
+        .get(format!("http://{}", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    // both endpoints are advertised
+    let alt_svc = response
+        .headers()
+        .get(actix_web::http::header::ALT_SVC)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let entries = alt_svc.split(", ").collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2, "{}", alt_svc);
+    assert_ne!(entries[0], entries[1]);
+    assert!(entries.iter().all(|entry| entry.starts_with("h3=\":")));
+
+    srv.stop(false).await;
+    server.join().unwrap();
+}
+
 #[cfg(feature = "openssl")]
 fn ssl_acceptor() -> openssl::ssl::SslAcceptorBuilder {
     use openssl::{
@@ -0,0 +1,234 @@
+use std::{pin::Pin, time::Duration};
+
+use actix_codec::{Decoder as _, Encoder as _};
+use actix_http::ws;
+use actix_web::{
+    http::{header, upgrade::Upgrade, StatusCode},
+    proxy::Proxy,
+    web, App, HttpRequest, HttpResponse,
+};
+use bytes::{Bytes, BytesMut};
+use futures_util::{future::poll_fn, StreamExt as _};
+use tokio::io::{AsyncReadExt, AsyncWriteExt as _};
+
+/// Start proxy in front of backend server.
+fn start_proxy<F>(backend: &actix_test::TestServer, proxy: F) -> actix_test::TestServer
+where
+    F: Fn(&str) -> Proxy + Send + Clone + 'static,
+{
+    let upstream = backend.url("/v1");
+    actix_test::start(move || App::new().service(proxy(&upstream)))
+}
+
+async fn echo(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .map(|hdr| hdr.to_str().unwrap().to_owned())
+            .unwrap_or_default()
+    };
+
+    HttpResponse::Ok()
+        .insert_header(("x-method", req.method().as_str()))
+        .insert_header(("x-uri", req.uri().to_string()))
+        .insert_header(("x-forwarded-for-seen", header("x-forwarded-for")))
+        .insert_header(("x-forwarded-seen", header("forwarded")))
+        .insert_header(("x-proxy-auth-seen", header("proxy-authorization")))
+        .insert_header((header::PROXY_AUTHENTICATE, "Basic"))
+        .body(body)
+}
+
+async fn create(req: HttpRequest) -> HttpResponse {
+    let host = req.headers().get(header::HOST).unwrap().to_str().unwrap();
+    let location = format!("http://{}/v1/items/1", host);
+    HttpResponse::Created()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+#[actix_rt::test]
+async fn forwards_requests() {
+    let backend = actix_test::start(|| {
+        App::new()
+            .route("/v1/echo", web::to(echo))
+            .route("/v1/items", web::post().to(create))
+    });
+    let srv = start_proxy(&backend, |upstream| web::proxy("/api", upstream).unwrap());
+
+    let mut res = srv
+        .post("/api/echo?q=1")
+        .insert_header((header::PROXY_AUTHORIZATION, "Basic Zm9vOmJhcg=="))
+        .send_body("hello")
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("x-method").unwrap(), "POST");
+    assert_eq!(res.headers().get("x-uri").unwrap(), "/v1/echo?q=1");
+    assert_eq!(
+        res.headers().get("x-forwarded-for-seen").unwrap(),
+        "127.0.0.1"
+    );
+    let forwarded = res
+        .headers()
+        .get("x-forwarded-seen")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(forwarded.starts_with("for=127.0.0.1;host="));
+    // hop-by-hop headers are not forwarded
+    assert_eq!(res.headers().get("x-proxy-auth-seen").unwrap(), "");
+    assert!(!res.headers().contains_key(header::PROXY_AUTHENTICATE));
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"hello"));
+
+    // large streamed body
+    let chunks = (0..64).map(|_| Ok::<_, actix_web::Error>(Bytes::from(vec![b'x'; 4096])));
+    let mut res = srv
+        .post("/api/echo")
+        .send_stream(futures_util::stream::iter(chunks))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let bytes = res.body().limit(512 * 1024).await.unwrap();
+    assert_eq!(bytes.len(), 256 * 1024);
+
+    // location pointing at upstream is rewritten
+    let res = srv.post("/api/items").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let location = res.headers().get(header::LOCATION).unwrap();
+    assert_eq!(location.to_str().unwrap(), srv.url("/api/items/1"));
+
+    // client supplied forwarding headers are ignored
+    let res = srv
+        .post("/api/items")
+        .insert_header(("x-forwarded-host", "spoofed.example"))
+        .insert_header((header::FORWARDED, "host=spoofed.example;proto=https"))
+        .send()
+        .await
+        .unwrap();
+    let location = res.headers().get(header::LOCATION).unwrap();
+    assert_eq!(location.to_str().unwrap(), srv.url("/api/items/1"));
+
+    let srv = start_proxy(&backend, |upstream| {
+        Proxy::new("/api", upstream)
+            .unwrap()
+            .trust_forwarded_headers(true)
+    });
+    let res = srv
+        .post("/api/items")
+        .insert_header(("x-forwarded-host", "proxy.example"))
+        .send()
+        .await
+        .unwrap();
+    let location = res.headers().get(header::LOCATION).unwrap();
+    assert_eq!(
+        location.to_str().unwrap(),
+        "http://proxy.example/api/items/1"
+    );
+}
+
+#[actix_rt::test]
+async fn rewrites_path() {
+    let backend = actix_test::start(|| App::new().route("/v1/items/{id}", web::to(echo)));
+    let srv = start_proxy(&backend, |upstream| {
+        Proxy::new("/api", upstream)
+            .unwrap()
+            .rewrite_path(|path| path.replacen("/legacy", "/items", 1))
+    });
+
+    let res = srv.get("/api/legacy/42?x=y").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("x-uri").unwrap(), "/v1/items/42?x=y");
+}
+
+#[actix_rt::test]
+async fn upstream_errors() {
+    let backend = actix_test::start(|| {
+        App::new().route(
+            "/v1/slow",
+            web::to(|| async {
+                actix_rt::time::sleep(Duration::from_secs(2)).await;
+                "done"
+            }),
+        )
+    });
+    let srv = start_proxy(&backend, |upstream| {
+        Proxy::new("/api", upstream)
+            .unwrap()
+            .timeout(Duration::from_millis(200))
+    });
+
+    let res = srv.get("/api/slow").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+
+    // nothing listens on the upstream
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let srv = actix_test::start(move || {
+        App::new().service(web::proxy("/", &format!("http://{}", addr)).unwrap())
+    });
+
+    let res = srv.get("/").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+}
+
+/// WebSocket handshake that echoes text messages over the taken over connection.
+async fn ws_echo(req: HttpRequest, upgrade: Upgrade) -> HttpResponse {
+    let key = req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap();
+    let accept = ws::hash_key(key.as_bytes());
+    let on_upgrade = upgrade.on_upgrade();
+
+    actix_rt::spawn(async move {
+        let mut io = on_upgrade.await.unwrap();
+        let mut codec = ws::Codec::new();
+        let mut read_buf = BytesMut::new();
+
+        loop {
+            match codec.decode(&mut read_buf).unwrap() {
+                Some(ws::Frame::Text(text)) => {
+                    let mut write_buf = BytesMut::new();
+                    codec
+                        .encode(
+                            ws::Message::Text(String::from_utf8(text.to_vec()).unwrap().into()),
+                            &mut write_buf,
+                        )
+                        .unwrap();
+                    io.write_all(&write_buf).await.unwrap();
+                }
+                Some(_) => break,
+                None => {
+                    if AsyncReadExt::read_buf(&mut io, &mut read_buf)
+                        .await
+                        .unwrap()
+                        == 0
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, &accept[..]))
+        .finish()
+}
+
+#[actix_rt::test]
+async fn websocket_pass_through() {
+    let backend = actix_test::start(|| App::new().route("/v1/ws", web::get().to(ws_echo)));
+    let mut srv = start_proxy(&backend, |upstream| web::proxy("/api", upstream).unwrap());
+
+    let mut framed = srv.ws_at("/api/ws").await.unwrap();
+
+    Pin::new(&mut framed)
+        .write(ws::Message::Text("hello".into()))
+        .unwrap();
+    poll_fn(|cx| Pin::new(&mut framed).flush(cx)).await.unwrap();
+
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"hello")));
+}